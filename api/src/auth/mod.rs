@@ -0,0 +1,60 @@
+use crate::error::Error;
+use actix_web::dev::Payload;
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::{FromRequest, HttpRequest};
+use lazy_static::lazy_static;
+use std::env;
+use std::future::{ready, Ready};
+
+const ADMIN_API_KEY_ENV: &str = "ADMIN_API_KEY";
+const API_KEY_HEADER: &str = "X-Api-Key";
+const BEARER_PREFIX: &str = "Bearer ";
+
+lazy_static! {
+	static ref ADMIN_API_KEY: Option<String> = env::var(ADMIN_API_KEY_ENV)
+		.ok()
+		.filter(|key| !key.is_empty());
+}
+
+/// Extractor which only succeeds if the request carries the admin api key, either as bearer token
+/// or in the `X-Api-Key` header. Without `ADMIN_API_KEY` configured every request is rejected.
+pub struct Admin;
+
+impl FromRequest for Admin {
+	type Error = Error;
+	type Future = Ready<Result<Self, Self::Error>>;
+
+	fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+		ready(authorize_admin(req))
+	}
+}
+
+fn authorize_admin(req: &HttpRequest) -> Result<Admin, Error> {
+	let expected = ADMIN_API_KEY.as_deref().ok_or(Error::Unauthorized)?;
+
+	let provided = req
+		.headers()
+		.get(AUTHORIZATION)
+		.and_then(|value| value.to_str().ok())
+		.and_then(|value| value.strip_prefix(BEARER_PREFIX))
+		.or_else(|| {
+			req.headers()
+				.get(API_KEY_HEADER)
+				.and_then(|value| value.to_str().ok())
+		})
+		.ok_or(Error::Unauthorized)?;
+
+	if constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+		Ok(Admin)
+	} else {
+		Err(Error::Unauthorized)
+	}
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	if a.len() != b.len() {
+		return false;
+	}
+
+	a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
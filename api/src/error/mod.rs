@@ -1,3 +1,4 @@
+use actix_web::http::header::RETRY_AFTER;
 use actix_web::http::StatusCode;
 use actix_web::{HttpResponse, ResponseError};
 
@@ -8,6 +9,15 @@ pub enum Error {
 
 	#[error("a database error occurred: {0}")]
 	DbError(#[from] sea_orm::DbErr),
+
+	#[error("missing or invalid api key")]
+	Unauthorized,
+
+	#[error("bad request: {0}")]
+	BadRequest(String),
+
+	#[error("too many requests, retry in {0} seconds")]
+	TooManyRequests(u64),
 }
 
 impl ResponseError for Error {
@@ -15,11 +25,20 @@ impl ResponseError for Error {
 		match &self {
 			Self::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
 			Self::DbError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			Self::Unauthorized => StatusCode::UNAUTHORIZED,
+			Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+			Self::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
 		}
 	}
 
 	fn error_response(&self) -> HttpResponse {
-		HttpResponse::build(self.status_code()).body(self.to_string())
+		let mut response = HttpResponse::build(self.status_code());
+
+		if let Self::TooManyRequests(retry_after) = self {
+			response.insert_header((RETRY_AFTER, retry_after.to_string()));
+		}
+
+		response.body(self.to_string())
 	}
 }
 
@@ -1,6 +1,13 @@
+use crate::rate_limit::WeightedRateLimiter;
+use crate::routes::admin::{get_dat_import_failures, upload_dat};
 use crate::routes::company::{get_all_companies, get_company_by_id};
+use crate::routes::dat_file::{get_dat_file_import_changes_by_id, get_dat_file_imports_by_id};
+use crate::routes::game::{get_game_with_details_by_id, get_games, search_games};
 use crate::routes::health::{health, ready};
-use crate::routes::identify::identify;
+use crate::routes::identify::{
+	identify, identify_archive, identify_archive_by_entries, identify_batch, identify_by_serial,
+	identify_file, identify_game_set, IdentifyUploadLimits,
+};
 use crate::routes::igdb::{
 	get_age_rating_by_id, get_age_ratings_by_ids, get_alternative_name_by_id,
 	get_alternative_names_by_ids, get_artwork_by_id, get_artworks_by_ids, get_collection_by_id,
@@ -9,7 +16,9 @@ use crate::routes::igdb::{
 	get_games_by_ids, get_genre_by_id, get_genres_by_ids, search_game_by_name,
 };
 use crate::routes::platform::{get_all_platforms, get_platform_by_id};
-use crate::util::{wrap_download_and_parse_dats, wrap_match_db_to_igdb_entities};
+use crate::util::{
+	wrap_download_and_parse_dats, wrap_match_db_to_igdb_entities, wrap_watch_drop_in_dats,
+};
 use actix_governor::{Governor, GovernorConfigBuilder};
 use actix_web::middleware::{Compress, DefaultHeaders, Logger};
 use actix_web::web::{scope, Data};
@@ -20,6 +29,7 @@ use openapi::ApiDoc;
 use reqwest::Client;
 use sea_orm::{ConnectOptions, Database};
 use service::constants::http::X_VERSION_HEADER_API;
+use service::dat::drop_in::drop_in_path;
 use service::db::constants::MAX_CONNECTIONS;
 use service::metadata::igdb::IgdbClient;
 use std::env;
@@ -29,12 +39,17 @@ use tokio_cron_scheduler::{Job, JobScheduler};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::{SwaggerUi, Url};
 
+mod auth;
 pub mod error;
 pub mod model;
 mod openapi;
+mod rate_limit;
 pub mod routes;
 mod util;
 
+const RATE_LIMIT_MILLISECONDS_PER_REQUEST: u64 = 250;
+const RATE_LIMIT_BURST_SIZE: u32 = 20;
+
 #[actix_web::main]
 async fn start() -> anyhow::Result<()> {
 	let port = env::var("PORT").unwrap_or("8080".to_string());
@@ -47,10 +62,15 @@ async fn start() -> anyhow::Result<()> {
 	// and replenishes four element every seconds
 	let governor_conf = GovernorConfigBuilder::default()
 		.use_headers()
-		.milliseconds_per_request(250)
-		.burst_size(20)
+		.milliseconds_per_request(RATE_LIMIT_MILLISECONDS_PER_REQUEST)
+		.burst_size(RATE_LIMIT_BURST_SIZE)
 		.finish()
 		.unwrap();
+	let weighted_rate_limiter = Data::new(WeightedRateLimiter::new(
+		Duration::from_millis(RATE_LIMIT_MILLISECONDS_PER_REQUEST),
+		RATE_LIMIT_BURST_SIZE,
+	)?);
+	let identify_upload_limits = Data::new(IdentifyUploadLimits::from_env()?);
 
 	let mut opt = ConnectOptions::new(env::var("DATABASE_URL")?);
 	opt.max_connections(MAX_CONNECTIONS);
@@ -82,6 +102,8 @@ async fn start() -> anyhow::Result<()> {
 			.app_data(conn_data.clone())
 			.app_data(client_data.clone())
 			.app_data(igdb_data.clone())
+			.app_data(weighted_rate_limiter.clone())
+			.app_data(identify_upload_limits.clone())
 			.service(
 				scope("/api")
 					.wrap(Governor::new(&governor_conf))
@@ -95,7 +117,20 @@ async fn start() -> anyhow::Result<()> {
 					.service(get_company_by_id)
 					.service(get_all_platforms)
 					.service(get_platform_by_id)
+					.service(get_games)
+					.service(search_games)
+					.service(get_game_with_details_by_id)
+					.service(get_dat_file_imports_by_id)
+					.service(get_dat_file_import_changes_by_id)
+					.service(upload_dat)
+					.service(get_dat_import_failures)
 					.service(identify)
+					.service(identify_by_serial)
+					.service(identify_batch)
+					.service(identify_file)
+					.service(identify_archive)
+					.service(identify_archive_by_entries)
+					.service(identify_game_set)
 					.service(get_game_by_id)
 					.service(get_games_by_ids)
 					.service(search_game_by_name)
@@ -157,6 +192,10 @@ async fn start() -> anyhow::Result<()> {
 		});
 	}
 
+	if let Some(drop_in_path) = drop_in_path() {
+		tokio::spawn(wrap_watch_drop_in_dats(drop_in_path, conn_arc.clone()));
+	}
+
 	sched.start().await?;
 	debug!("Scheduler started");
 
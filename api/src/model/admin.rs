@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Multipart form of a DAT upload.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DatUploadForm {
+	/// The DAT file or an archive (zip, 7z, tar, tar.gz, gz) containing DAT files.
+	#[schema(value_type = String, format = Binary)]
+	pub file: Vec<u8>,
+
+	/// Name of the signature group the DATs belong to, e.g. "No-Intro".
+	pub signature_group: String,
+}
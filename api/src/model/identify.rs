@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Multipart form of a file or archive to identify.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FileIdentifyForm {
+	/// The file to identify, its file name is used for the filename + size match. For archives the
+	/// extension has to match the kind of archive.
+	#[schema(value_type = String, format = Binary)]
+	pub file: Vec<u8>,
+
+	/// Also match games which were removed from newer DAT versions, defaults to false.
+	pub include_retired: Option<bool>,
+}
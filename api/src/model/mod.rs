@@ -1 +1,3 @@
+pub mod admin;
+pub mod identify;
 pub mod igdb;
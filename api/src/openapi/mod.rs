@@ -1,6 +1,19 @@
+use crate::model::admin::DatUploadForm;
+use crate::model::identify::FileIdentifyForm;
+use crate::routes::admin::{__path_get_dat_import_failures, __path_upload_dat};
 use crate::routes::company::{__path_get_all_companies, __path_get_company_by_id};
+use crate::routes::dat_file::{
+	__path_get_dat_file_import_changes_by_id, __path_get_dat_file_imports_by_id,
+};
+use crate::routes::game::{
+	__path_get_game_with_details_by_id, __path_get_games, __path_search_games,
+};
 use crate::routes::health::{__path_health, __path_ready};
-use crate::routes::identify::__path_identify;
+use crate::routes::identify::{
+	__path_identify, __path_identify_archive, __path_identify_archive_by_entries,
+	__path_identify_batch, __path_identify_by_serial, __path_identify_file,
+	__path_identify_game_set,
+};
 use crate::routes::igdb::{
 	__path_get_age_rating_by_id, __path_get_age_ratings_by_ids, __path_get_alternative_name_by_id,
 	__path_get_alternative_names_by_ids, __path_get_artwork_by_id, __path_get_artworks_by_ids,
@@ -29,17 +42,32 @@ use service::metadata::igdb::model::{
 	ReleaseDateCategory, ReleaseDateRegion, ReleaseDateStatus, Screenshot, Theme, WebsiteCategory,
 };
 use service::model::{
-	AutomaticMatchReason, CompanyResponse, ExternalMetadata, FailedMatchReason, GameMatchResult,
-	GameMatchType, ManualMatchMode, MatchType, MetadataProvider, PlatformResponse,
+	ArchiveEntriesSearch, ArchiveEntryMatch, ArchiveEntrySearch, ArchiveIdentifyResponse,
+	AutomaticMatchReason, CompanyResponse, DatFileImportChangeResponse, DatFileImportChangeType,
+	DatFileImportFailureResponse, DatFileImportResponse, DatFileImportResultResponse,
+	DatUploadFailure, DatUploadResponse, ExternalMetadata, FailedMatchReason, FileHashesResponse,
+	FileIdentifyResponse, GameDetailsResponse, GameDisc, GameDiscGroup, GameFileMatchSearch,
+	GameFileResponse, GameKind, GameListResponse, GameMatchCandidateResponse, GameMatchDatResponse,
+	GameMatchResult, GameMatchType, GameNameSearchResult, GameReferenceResponse, GameResponse,
+	GameSerialResponse, GameSetIdentifyResponse, GameSetMatch, GameSetSearch, GameSetStatus,
+	ManualMatchMode, MatchType, MetadataProvider, PlatformResponse,
 };
-use utoipa::OpenApi;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::{Modify, OpenApi};
 
 #[derive(OpenApi)]
 #[openapi(
+	modifiers(&SecurityAddon),
 	paths(
 		health,
 		ready,
 		identify,
+		identify_by_serial,
+		identify_batch,
+		identify_file,
+		identify_archive,
+		identify_archive_by_entries,
+		identify_game_set,
 		get_game_by_id,
 		get_games_by_ids,
 		search_game_by_name,
@@ -62,13 +90,52 @@ use utoipa::OpenApi;
 		get_all_companies,
 		get_company_by_id,
 		get_all_platforms,
-		get_platform_by_id
+		get_platform_by_id,
+		get_games,
+		search_games,
+		get_game_with_details_by_id,
+		get_dat_file_imports_by_id,
+		get_dat_file_import_changes_by_id,
+		upload_dat,
+		get_dat_import_failures
 	),
 	components(schemas(
 		GameMatchResult,
+		GameMatchCandidateResponse,
+		GameFileMatchSearch,
+		FileIdentifyForm,
+		FileIdentifyResponse,
+		FileHashesResponse,
+		ArchiveEntrySearch,
+		ArchiveEntriesSearch,
+		ArchiveIdentifyResponse,
+		ArchiveEntryMatch,
+		GameSetMatch,
+		GameSetStatus,
+		GameDetailsResponse,
+		GameMatchDatResponse,
+		GameSetSearch,
+		GameSetIdentifyResponse,
+		GameDiscGroup,
+		GameDisc,
+		GameSerialResponse,
+		GameFileResponse,
 		CompanyResponse,
 		PlatformResponse,
+		GameListResponse,
+		GameResponse,
+		GameReferenceResponse,
+		GameNameSearchResult,
+		DatFileImportResponse,
+		DatFileImportChangeResponse,
+		DatFileImportChangeType,
+		DatFileImportResultResponse,
+		DatFileImportFailureResponse,
+		DatUploadResponse,
+		DatUploadFailure,
+		DatUploadForm,
 		GameMatchType,
+		GameKind,
 		ExternalMetadata,
 		MatchType,
 		ManualMatchMode,
@@ -152,3 +219,16 @@ use utoipa::OpenApi;
 	))
 )]
 pub struct ApiDoc;
+
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+	fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+		if let Some(components) = openapi.components.as_mut() {
+			components.add_security_scheme(
+				"admin_api_key",
+				SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Api-Key"))),
+			);
+		}
+	}
+}
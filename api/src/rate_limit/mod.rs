@@ -0,0 +1,52 @@
+use crate::error::Error;
+use actix_web::HttpRequest;
+use governor::clock::{Clock, DefaultClock};
+use governor::{DefaultKeyedRateLimiter, Quota, RateLimiter};
+use std::net::IpAddr;
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+/// Rate limiter for requests which cost more than a single request, like batch requests. The
+/// middleware of the `/api` scope only counts every request once, so the additional weight is
+/// charged here with the same quota per IP address.
+pub struct WeightedRateLimiter {
+	limiter: DefaultKeyedRateLimiter<IpAddr>,
+	clock: DefaultClock,
+	burst_size: u32,
+}
+
+impl WeightedRateLimiter {
+	pub fn new(replenish_interval: Duration, burst_size: u32) -> anyhow::Result<Self> {
+		let burst = NonZeroU32::new(burst_size)
+			.ok_or_else(|| anyhow::anyhow!("The burst size must not be zero"))?;
+		let quota = Quota::with_period(replenish_interval)
+			.ok_or_else(|| anyhow::anyhow!("The replenish interval must not be zero"))?
+			.allow_burst(burst);
+
+		Ok(Self {
+			limiter: RateLimiter::dashmap(quota),
+			clock: DefaultClock::default(),
+			burst_size,
+		})
+	}
+
+	/// Charges the weight, capped at the burst size, for the IP address of the request. Requests
+	/// without a peer address are not limited, same as in the middleware.
+	pub fn check(&self, req: &HttpRequest, weight: u32) -> Result<(), Error> {
+		let Some(ip) = req.peer_addr().map(|addr| addr.ip()) else {
+			return Ok(());
+		};
+
+		let Some(weight) = NonZeroU32::new(weight.min(self.burst_size)) else {
+			return Ok(());
+		};
+
+		match self.limiter.check_key_n(&ip, weight) {
+			Ok(Ok(_)) => Ok(()),
+			Ok(Err(not_until)) => Err(Error::TooManyRequests(
+				not_until.wait_time_from(self.clock.now()).as_secs().max(1),
+			)),
+			Err(_) => Err(Error::TooManyRequests(1)),
+		}
+	}
+}
@@ -0,0 +1,161 @@
+use crate::auth::Admin;
+use crate::error;
+use crate::error::Error;
+use actix_multipart::Multipart;
+use actix_web::web::Data;
+use actix_web::{get, post, HttpResponse, Responder};
+use futures_util::TryStreamExt;
+use log::warn;
+use sea_orm::DatabaseConnection;
+use service::dat::upload::{create_upload_dir, import_uploaded_dat};
+use service::dat_file::get_latest_dat_file_import_failures;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+
+const FILE_FIELD: &str = "file";
+const SIGNATURE_GROUP_FIELD: &str = "signatureGroup";
+const DEFAULT_FILE_NAME: &str = "upload.dat";
+const MAX_UPLOAD_SIZE: usize = 1024 * 1024 * 1024;
+const MAX_SIGNATURE_GROUP_LENGTH: usize = 256;
+
+/// Uploads a DAT (or an archive of DATs) and imports it into the given signature group, which is created if it does not exist yet.
+#[utoipa::path(
+	post,
+	context_path = "/api",
+	tag = "Admin",
+	request_body(content = DatUploadForm, content_type = "multipart/form-data"),
+	security(("admin_api_key" = [])),
+	responses(
+		(status = 200, description = "Returns the imports created from the upload", body = DatUploadResponse),
+		(status = 400, description = "Invalid upload or none of the DATs could be imported"),
+		(status = 401, description = "Missing or invalid api key")
+	)
+)]
+#[post("/admin/dats")]
+pub async fn upload_dat(
+	_: Admin,
+	payload: Multipart,
+	db_conn: Data<DatabaseConnection>,
+) -> error::Result<impl Responder> {
+	let upload_dir = create_upload_dir().await?;
+
+	let result = receive_and_import_dat(&upload_dir, payload, db_conn.get_ref()).await;
+
+	if let Err(e) = fs::remove_dir_all(&upload_dir).await {
+		warn!(
+			"Failed to remove upload directory {}: {}",
+			upload_dir.display(),
+			e
+		);
+	}
+
+	let response = result?;
+
+	if response.imports.is_empty() && !response.failed.is_empty() {
+		return Ok(HttpResponse::BadRequest().json(response));
+	}
+
+	Ok(HttpResponse::Ok().json(response))
+}
+
+/// Returns the latest 100 failed DAT imports, newest first. Failed imports are rolled back and retried on the next run.
+#[utoipa::path(
+	get,
+	context_path = "/api",
+	tag = "Admin",
+	security(("admin_api_key" = [])),
+	responses(
+		(status = 200, description = "Returns the latest failed DAT imports", body = Vec<DatFileImportFailureResponse>),
+		(status = 401, description = "Missing or invalid api key")
+	)
+)]
+#[get("/admin/dat-import-failures")]
+pub async fn get_dat_import_failures(
+	_: Admin,
+	db_conn: Data<DatabaseConnection>,
+) -> error::Result<impl Responder> {
+	let failures = get_latest_dat_file_import_failures(db_conn.get_ref()).await?;
+
+	Ok(HttpResponse::Ok().json(failures))
+}
+
+async fn receive_and_import_dat(
+	upload_dir: &Path,
+	mut payload: Multipart,
+	conn: &DatabaseConnection,
+) -> error::Result<service::model::DatUploadResponse> {
+	let mut file: Option<PathBuf> = None;
+	let mut signature_group: Option<String> = None;
+
+	while let Some(mut field) = payload
+		.try_next()
+		.await
+		.map_err(|e| Error::BadRequest(e.to_string()))?
+	{
+		match field.name() {
+			Some(FILE_FIELD) => {
+				let file_name = field
+					.content_disposition()
+					.and_then(|disposition| disposition.get_filename())
+					.and_then(|name| Path::new(name).file_name())
+					.and_then(|name| name.to_str())
+					.unwrap_or(DEFAULT_FILE_NAME)
+					.to_string();
+
+				let path = upload_dir.join(file_name);
+				let mut out = File::create(&path).await.map_err(anyhow::Error::from)?;
+				let mut size = 0;
+
+				while let Some(chunk) = field
+					.try_next()
+					.await
+					.map_err(|e| Error::BadRequest(e.to_string()))?
+				{
+					size += chunk.len();
+
+					if size > MAX_UPLOAD_SIZE {
+						return Err(Error::BadRequest(format!(
+							"file exceeds the maximum size of {} bytes",
+							MAX_UPLOAD_SIZE
+						)));
+					}
+
+					out.write_all(&chunk).await.map_err(anyhow::Error::from)?;
+				}
+
+				out.flush().await.map_err(anyhow::Error::from)?;
+				file = Some(path);
+			}
+			Some(SIGNATURE_GROUP_FIELD) => {
+				let mut value = Vec::new();
+
+				while let Some(chunk) = field
+					.try_next()
+					.await
+					.map_err(|e| Error::BadRequest(e.to_string()))?
+				{
+					value.extend_from_slice(&chunk);
+
+					if value.len() > MAX_SIGNATURE_GROUP_LENGTH {
+						return Err(Error::BadRequest("signatureGroup is too long".to_string()));
+					}
+				}
+
+				let value = String::from_utf8(value).map_err(|_| {
+					Error::BadRequest("signatureGroup is not valid UTF-8".to_string())
+				})?;
+
+				signature_group = Some(value.trim().to_string()).filter(|value| !value.is_empty());
+			}
+			_ => {}
+		}
+	}
+
+	let file = file.ok_or_else(|| Error::BadRequest("missing file field".to_string()))?;
+	let signature_group = signature_group
+		.ok_or_else(|| Error::BadRequest("missing signatureGroup field".to_string()))?;
+
+	Ok(import_uploaded_dat(&file, &signature_group, conn).await?)
+}
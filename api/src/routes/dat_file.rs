@@ -0,0 +1,54 @@
+use crate::error;
+use actix_web::web::{Data, Path};
+use actix_web::{get, HttpResponse, Responder};
+use sea_orm::DatabaseConnection;
+use service::dat_file::{get_dat_file_import_changes, get_dat_file_imports};
+use uuid::Uuid;
+
+/// Returns all imports (versions) of a DAT file, newest first.
+#[utoipa::path(
+	get,
+	context_path = "/api",
+	tag = "DAT",
+	responses(
+		(status = 200, description = "Returns all imports of a DAT file", body = Vec<DatFileImportResponse>),
+		(status = 404, description = "DAT file not found")
+	)
+)]
+#[get("/dat-files/{id}/imports")]
+pub async fn get_dat_file_imports_by_id(
+	id: Path<Uuid>,
+	db_conn: Data<DatabaseConnection>,
+) -> error::Result<impl Responder> {
+	let imports = get_dat_file_imports(id.into_inner(), db_conn.get_ref()).await?;
+
+	if let Some(imports) = imports {
+		Ok(HttpResponse::Ok().json(imports))
+	} else {
+		Ok(HttpResponse::NotFound().finish())
+	}
+}
+
+/// Returns what changed in a DAT import compared to the previous version: games added, removed and renamed and files whose hashes changed.
+#[utoipa::path(
+	get,
+	context_path = "/api",
+	tag = "DAT",
+	responses(
+		(status = 200, description = "Returns the changes of a DAT import", body = Vec<DatFileImportChangeResponse>),
+		(status = 404, description = "DAT import not found")
+	)
+)]
+#[get("/dat-imports/{id}/changes")]
+pub async fn get_dat_file_import_changes_by_id(
+	id: Path<Uuid>,
+	db_conn: Data<DatabaseConnection>,
+) -> error::Result<impl Responder> {
+	let changes = get_dat_file_import_changes(id.into_inner(), db_conn.get_ref()).await?;
+
+	if let Some(changes) = changes {
+		Ok(HttpResponse::Ok().json(changes))
+	} else {
+		Ok(HttpResponse::NotFound().finish())
+	}
+}
@@ -0,0 +1,76 @@
+use crate::error;
+use actix_web::web::{Data, Path, Query};
+use actix_web::{get, HttpResponse, Responder};
+use sea_orm::DatabaseConnection;
+use service::game::catalogue::{find_games, get_game_by_id, search_games_by_name};
+use service::model::{GameListQuery, GameNameSearch};
+use uuid::Uuid;
+
+/// Returns a page of the games from all imported DATs, ordered by name and filtered by platform, DAT, signature group, category, match status and name.
+#[utoipa::path(
+	get,
+	context_path = "/api",
+	tag = "Game",
+	params(GameListQuery),
+	responses(
+		(status = 200, description = "Returns a page of the games matching the filters", body = GameListResponse)
+	)
+)]
+#[get("/games")]
+pub async fn get_games(
+	query: Query<GameListQuery>,
+	db_conn: Data<DatabaseConnection>,
+) -> error::Result<impl Responder> {
+	let games = find_games(query.into_inner(), db_conn.get_ref()).await?;
+
+	Ok(HttpResponse::Ok().json(games))
+}
+
+/// Searches games by name with trigram similarity and full text search, best match first. Tags like (USA) or (Rev 1) are ignored, so it can be used as a fallback if a file can't be identified by its hashes or file name and size.
+#[utoipa::path(
+	get,
+	context_path = "/api",
+	tag = "Game",
+	params(GameNameSearch),
+	responses(
+		(status = 200, description = "Returns the games with a similar name, most similar first", body = Vec<GameNameSearchResult>),
+		(status = 400, description = "The name is empty")
+	)
+)]
+#[get("/games/search")]
+pub async fn search_games(
+	query: Query<GameNameSearch>,
+	db_conn: Data<DatabaseConnection>,
+) -> error::Result<impl Responder> {
+	if query.name.trim().is_empty() {
+		return Err(error::Error::BadRequest("The name is empty".to_string()));
+	}
+
+	let games = search_games_by_name(query.into_inner(), db_conn.get_ref()).await?;
+
+	Ok(HttpResponse::Ok().json(games))
+}
+
+/// Returns a game by id with its files, DAT, parent, clones, the game whose ROMs it uses (e.g. its BIOS) and metadata mappings.
+#[utoipa::path(
+	get,
+	context_path = "/api",
+	tag = "Game",
+	responses(
+		(status = 200, description = "Returns a Game with its files and relations", body = GameResponse),
+		(status = 404, description = "Game not found")
+	)
+)]
+#[get("/games/{id}")]
+pub async fn get_game_with_details_by_id(
+	id: Path<Uuid>,
+	db_conn: Data<DatabaseConnection>,
+) -> error::Result<impl Responder> {
+	let game = get_game_by_id(id.into_inner(), db_conn.get_ref()).await?;
+
+	if let Some(game) = game {
+		Ok(HttpResponse::Ok().json(game))
+	} else {
+		Ok(HttpResponse::NotFound().finish())
+	}
+}
@@ -1,30 +1,566 @@
 use crate::error;
-use actix_web::web::Data;
-use actix_web::{get, web, HttpResponse, Responder};
-use log::debug;
+use crate::error::Error;
+use crate::rate_limit::WeightedRateLimiter;
+use actix_multipart::Multipart;
+use actix_web::web::{Data, Json};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use futures_util::TryStreamExt;
+use log::{debug, warn};
 use sea_orm::DatabaseConnection;
-use service::game::match_game_if_possible;
-use service::model::GameFileMatchSearch;
+use service::dat::upload::create_upload_dir;
+use service::game::archive::{
+	hash_archive_entries, identify_archive_entries, identify_hashed_archive_entries,
+	is_supported_archive, list_archive_entries,
+};
+use service::game::expand::expand_game_match_results;
+use service::game::set::identify_set;
+use service::game::{
+	detect_search_header, find_games_by_serial, identify_hashed_file, match_game_if_possible,
+	match_games_in_batch,
+};
+use service::hash::{hash_file_without_given_header, BackgroundFileHasher, FileHashes};
+use service::header::DetectedHeader;
+use service::model::{
+	ArchiveEntriesSearch, ArchiveIdentifyResponse, FileIdentifyResponse, GameFileMatchSearch,
+	GameMatchExpand, GameMatchExpandQuery, GameSerialSearch, GameSetSearch,
+};
+use std::env;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
 use web::Query;
 
-/// Identify a game by its file hashes or filename and size, returning the matched metadata ids, goes in order sha256, sha1, md5 and filename + size (from most accurate to least accurate)
+const MAX_IDENTIFY_BATCH_SIZE: usize = 1000;
+
+/// Amount of batch entries counting as one request against the rate limit.
+const IDENTIFY_BATCH_ENTRIES_PER_REQUEST: usize = 50;
+
+const FILE_FIELD: &str = "file";
+const INCLUDE_RETIRED_FIELD: &str = "includeRetired";
+const MAX_INCLUDE_RETIRED_LENGTH: usize = "false".len();
+const DEFAULT_FILE_NAME: &str = "upload";
+/// The uploaded file and the files extracted from an uploaded archive are kept in separate
+/// directories of the upload directory, so the name of the upload can't collide with them.
+const UPLOAD_FILE_DIR: &str = "upload";
+const ARCHIVE_CONTENTS_DIR: &str = "contents";
+const MAX_IDENTIFY_UPLOAD_SIZE_ENV: &str = "MAX_IDENTIFY_UPLOAD_SIZE";
+const DEFAULT_MAX_IDENTIFY_UPLOAD_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
+/// Limits for uploads to identify, read from the environment once at startup.
+#[derive(Debug, Clone, Copy)]
+pub struct IdentifyUploadLimits {
+	/// The maximum size of an uploaded file in bytes, and of all files extracted from an uploaded
+	/// archive.
+	pub max_upload_size: u64,
+}
+
+impl IdentifyUploadLimits {
+	pub fn from_env() -> anyhow::Result<Self> {
+		let max_upload_size = match env::var(MAX_IDENTIFY_UPLOAD_SIZE_ENV) {
+			Ok(size) => size.trim().parse().map_err(|e| {
+				anyhow::anyhow!(
+					"{} must be a number of bytes: {}",
+					MAX_IDENTIFY_UPLOAD_SIZE_ENV,
+					e
+				)
+			})?,
+			Err(_) => DEFAULT_MAX_IDENTIFY_UPLOAD_SIZE,
+		};
+
+		Ok(IdentifyUploadLimits { max_upload_size })
+	}
+}
+
+/// Identify a game by its file hashes or filename and size, returning the matched metadata ids, goes in order sha256, sha1, md5, crc32 + size, serial and filename + size (from most accurate to least accurate). All games matching with the first successful match type are returned as candidates, best match first, and the result is flagged as ambiguous if there is more than one. Games removed from newer DAT versions are only matched with includeRetired
 #[utoipa::path(
 	get,
 	context_path = "/api",
 	tag = "Identify",
-	params(GameFileMatchSearch),
+	params(GameFileMatchSearch, GameMatchExpandQuery),
 	responses(
-		(status = 200, description = "Returns info about a possible match via hashes or filename and size", body = GameMatchResult)
+		(status = 200, description = "Returns info about a possible match via hashes or filename and size", body = GameMatchResult),
+		(status = 400, description = "The header is no valid hex or an expand value is unknown")
 	)
 )]
 #[get("/identify/ids")]
 pub async fn identify(
 	query: Query<GameFileMatchSearch>,
+	expand_query: Query<GameMatchExpandQuery>,
+	db_conn: Data<DatabaseConnection>,
+) -> error::Result<impl Responder> {
+	debug!("Received request: {:?}", query);
+
+	let expand = parse_expand(&expand_query)?;
+	let header =
+		detect_search_header(&query).map_err(|e| error::Error::BadRequest(e.to_string()))?;
+
+	let search = query.into_inner();
+	let mut response =
+		match_game_if_possible(search.clone(), header.clone(), db_conn.get_ref()).await?;
+
+	expand_game_match_results(
+		std::slice::from_mut(&mut response),
+		&[(&search, header.as_ref())],
+		expand,
+		db_conn.get_ref(),
+	)
+	.await?;
+
+	Ok(HttpResponse::Ok().json(response))
+}
+
+/// Returns all games and their files carrying a disc or cartridge serial, e.g. SLUS-00594. Notations like SLUS00594 or SLUS 00594 are treated as the same serial and multiple serials can be separated by commas. Games removed from newer DAT versions are only returned with includeRetired
+#[utoipa::path(
+	get,
+	context_path = "/api",
+	tag = "Identify",
+	params(GameSerialSearch),
+	responses(
+		(status = 200, description = "Returns all games carrying the serial, active games first", body = Vec<GameSerialResponse>),
+		(status = 400, description = "The serial is empty")
+	)
+)]
+#[get("/identify/serial")]
+pub async fn identify_by_serial(
+	query: Query<GameSerialSearch>,
 	db_conn: Data<DatabaseConnection>,
 ) -> error::Result<impl Responder> {
 	debug!("Received request: {:?}", query);
 
-	let response = match_game_if_possible(query.into_inner(), db_conn.get_ref()).await?;
+	if query.serial.trim().is_empty() {
+		return Err(error::Error::BadRequest("The serial is empty".to_string()));
+	}
+
+	let response = find_games_by_serial(query.into_inner(), db_conn.get_ref()).await?;
+
+	Ok(HttpResponse::Ok().json(response))
+}
+
+/// Identify up to 1000 game files at once, every entry is matched the same way as with /identify/ids. The results are returned in the order of the entries. Every 50 entries count as one request against the rate limit
+#[utoipa::path(
+	post,
+	context_path = "/api",
+	tag = "Identify",
+	params(GameMatchExpandQuery),
+	request_body = Vec<GameFileMatchSearch>,
+	responses(
+		(status = 200, description = "Returns info about a possible match for every entry, in the order of the entries", body = Vec<GameMatchResult>),
+		(status = 400, description = "The batch is empty, has too many entries, a header is no valid hex or an expand value is unknown"),
+		(status = 429, description = "The rate limit is exceeded")
+	)
+)]
+#[post("/identify/batch")]
+pub async fn identify_batch(
+	req: HttpRequest,
+	body: Json<Vec<GameFileMatchSearch>>,
+	expand_query: Query<GameMatchExpandQuery>,
+	rate_limiter: Data<WeightedRateLimiter>,
+	db_conn: Data<DatabaseConnection>,
+) -> error::Result<impl Responder> {
+	let searches = body.into_inner();
+	debug!("Received batch request with {} entries", searches.len());
+
+	let expand = parse_expand(&expand_query)?;
+	check_batch_size(&req, searches.len(), rate_limiter.get_ref())?;
+
+	let searches = detect_search_headers(searches)?;
+
+	let mut response = match_games_in_batch(searches.clone(), db_conn.get_ref()).await?;
+
+	expand_game_match_results(
+		&mut response,
+		&searches
+			.iter()
+			.map(|(search, header)| (search, header.as_ref()))
+			.collect::<Vec<_>>(),
+		expand,
+		db_conn.get_ref(),
+	)
+	.await?;
+
+	Ok(HttpResponse::Ok().json(response))
+}
+
+/// Identify an uploaded file, for clients which can't hash files themselves. The file is hashed (CRC32, MD5, SHA1 and SHA256) while it is received and matched the same way as with /identify/ids, its file name is used for the filename + size match. If a header is detected, the data without it is hashed and matched first. The maximum size is configured with MAX_IDENTIFY_UPLOAD_SIZE (4 GiB by default)
+#[utoipa::path(
+	post,
+	context_path = "/api",
+	tag = "Identify",
+	params(GameMatchExpandQuery),
+	request_body(content = FileIdentifyForm, content_type = "multipart/form-data"),
+	responses(
+		(status = 200, description = "Returns the hashes of the file and info about a possible match", body = FileIdentifyResponse),
+		(status = 400, description = "Invalid upload, the file exceeds the maximum size or an expand value is unknown")
+	)
+)]
+#[post("/identify/file")]
+pub async fn identify_file(
+	payload: Multipart,
+	expand_query: Query<GameMatchExpandQuery>,
+	limits: Data<IdentifyUploadLimits>,
+	db_conn: Data<DatabaseConnection>,
+) -> error::Result<impl Responder> {
+	let expand = parse_expand(&expand_query)?;
+	let upload_dir = create_upload_dir().await?;
+
+	let result = identify_uploaded_file(
+		&upload_dir,
+		payload,
+		expand,
+		limits.get_ref(),
+		db_conn.get_ref(),
+	)
+	.await;
+
+	if let Err(e) = fs::remove_dir_all(&upload_dir).await {
+		warn!(
+			"Failed to remove upload directory {}: {}",
+			upload_dir.display(),
+			e
+		);
+	}
+
+	Ok(HttpResponse::Ok().json(result?))
+}
+
+/// Identify the entries of an uploaded archive (zip, 7z, tar, tar.gz or gz). Every entry is hashed and matched the same way as with /identify/file, folders in the archive are ignored. The games the entries belong to are compared with the whole archive, which is a complete set if it contains all files of a game and nothing else. Archives in the archive are not extracted but identified as files. Archives with more than 1000 entries or which are larger than MAX_IDENTIFY_UPLOAD_SIZE when extracted are rejected before extracting them, every 50 entries count as one request against the rate limit
+#[utoipa::path(
+	post,
+	context_path = "/api",
+	tag = "Identify",
+	request_body(content = FileIdentifyForm, content_type = "multipart/form-data"),
+	responses(
+		(status = 200, description = "Returns the match of every entry and the games they belong to", body = ArchiveIdentifyResponse),
+		(status = 400, description = "Invalid upload, no supported archive or too many entries"),
+		(status = 429, description = "The rate limit is exceeded")
+	)
+)]
+#[post("/identify/archive")]
+pub async fn identify_archive(
+	req: HttpRequest,
+	payload: Multipart,
+	rate_limiter: Data<WeightedRateLimiter>,
+	limits: Data<IdentifyUploadLimits>,
+	db_conn: Data<DatabaseConnection>,
+) -> error::Result<impl Responder> {
+	let upload_dir = create_upload_dir().await?;
+
+	let result = identify_uploaded_archive(
+		&req,
+		&upload_dir,
+		payload,
+		rate_limiter.get_ref(),
+		limits.get_ref(),
+		db_conn.get_ref(),
+	)
+	.await;
+
+	if let Err(e) = fs::remove_dir_all(&upload_dir).await {
+		warn!(
+			"Failed to remove upload directory {}: {}",
+			upload_dir.display(),
+			e
+		);
+	}
+
+	Ok(HttpResponse::Ok().json(result?))
+}
+
+/// Identify the entries of an archive by the name, size and CRC32 from its central directory, without uploading it. Every entry is matched by CRC32 + size and filename + size, folders in the archive are ignored. The games the entries belong to are compared with the whole archive, which is a complete set if it contains all files of a game and nothing else. Up to 1000 entries are accepted, every 50 entries count as one request against the rate limit
+#[utoipa::path(
+	post,
+	context_path = "/api",
+	tag = "Identify",
+	request_body = ArchiveEntriesSearch,
+	responses(
+		(status = 200, description = "Returns the match of every entry and the games they belong to", body = ArchiveIdentifyResponse),
+		(status = 400, description = "The archive has no or too many entries"),
+		(status = 429, description = "The rate limit is exceeded")
+	)
+)]
+#[post("/identify/archive/entries")]
+pub async fn identify_archive_by_entries(
+	req: HttpRequest,
+	body: Json<ArchiveEntriesSearch>,
+	rate_limiter: Data<WeightedRateLimiter>,
+	db_conn: Data<DatabaseConnection>,
+) -> error::Result<impl Responder> {
+	let search = body.into_inner();
+	debug!("Received archive with {} entries", search.entries.len());
+
+	check_batch_size(&req, search.entries.len(), rate_limiter.get_ref())?;
+
+	let response = identify_archive_entries(search, db_conn.get_ref()).await?;
+
+	Ok(HttpResponse::Ok().json(response))
+}
+
+/// Identify a set of files by their hashes, e.g. the cue and bin tracks of a disc or the files in a folder. Every file is matched the same way as with /identify/ids. The games the files belong to are compared with the whole set: Complete if the set contains all files of a game and nothing else, Superset if it contains additional files and Partial if files are missing. Games which are a disc of a title with multiple discs are grouped with the other discs of the title. Up to 1000 files are accepted, every 50 files count as one request against the rate limit
+#[utoipa::path(
+	post,
+	context_path = "/api",
+	tag = "Identify",
+	request_body = GameSetSearch,
+	responses(
+		(status = 200, description = "Returns the match of every file, the games they belong to and their discs", body = GameSetIdentifyResponse),
+		(status = 400, description = "The set has no or too many files or a header is no valid hex"),
+		(status = 429, description = "The rate limit is exceeded")
+	)
+)]
+#[post("/identify/set")]
+pub async fn identify_game_set(
+	req: HttpRequest,
+	body: Json<GameSetSearch>,
+	rate_limiter: Data<WeightedRateLimiter>,
+	db_conn: Data<DatabaseConnection>,
+) -> error::Result<impl Responder> {
+	let search = body.into_inner();
+	debug!("Received set with {} files", search.files.len());
+
+	check_batch_size(&req, search.files.len(), rate_limiter.get_ref())?;
+
+	let files = detect_search_headers(search.files)?;
+
+	let response = identify_set(files, search.include_retired, db_conn.get_ref()).await?;
 
 	Ok(HttpResponse::Ok().json(response))
 }
+
+/// Detects the headers of the searches of a batch, an invalid header is reported with the index
+/// of its search.
+fn detect_search_headers(
+	searches: Vec<GameFileMatchSearch>,
+) -> error::Result<Vec<(GameFileMatchSearch, Option<DetectedHeader>)>> {
+	searches
+		.into_iter()
+		.enumerate()
+		.map(|(index, search)| {
+			detect_search_header(&search)
+				.map(|header| (search, header))
+				.map_err(|e| Error::BadRequest(format!("Entry {}: {}", index, e)))
+		})
+		.collect()
+}
+
+fn parse_expand(query: &GameMatchExpandQuery) -> error::Result<GameMatchExpand> {
+	query
+		.expand
+		.as_deref()
+		.unwrap_or_default()
+		.parse()
+		.map_err(|e: anyhow::Error| Error::BadRequest(e.to_string()))
+}
+
+/// Rejects empty and too large batches and charges the batch against the rate limit.
+fn check_batch_size(
+	req: &HttpRequest,
+	size: usize,
+	rate_limiter: &WeightedRateLimiter,
+) -> error::Result<()> {
+	if size == 0 {
+		return Err(Error::BadRequest("The batch is empty".to_string()));
+	}
+
+	if size > MAX_IDENTIFY_BATCH_SIZE {
+		return Err(Error::BadRequest(format!(
+			"The batch has more than {} entries",
+			MAX_IDENTIFY_BATCH_SIZE
+		)));
+	}
+
+	let weight = size.div_ceil(IDENTIFY_BATCH_ENTRIES_PER_REQUEST) as u32;
+	rate_limiter.check(req, weight)
+}
+
+async fn identify_uploaded_file(
+	upload_dir: &Path,
+	payload: Multipart,
+	expand: GameMatchExpand,
+	limits: &IdentifyUploadLimits,
+	conn: &DatabaseConnection,
+) -> error::Result<FileIdentifyResponse> {
+	let upload = receive_identify_upload(upload_dir, payload, true, limits).await?;
+	let (hashes, header) = upload
+		.hashes
+		.ok_or_else(|| anyhow::anyhow!("The upload was not hashed"))?;
+
+	// the header can only be detected once the size is known, so the file is read again for it
+	let header = match header {
+		Some(header) => {
+			let headerless_hashes = hash_file_without_given_header(&upload.path, &header).await?;
+			Some((header, headerless_hashes))
+		}
+		None => None,
+	};
+
+	Ok(identify_hashed_file(
+		upload.file_name,
+		hashes,
+		header,
+		upload.include_retired,
+		expand,
+		conn,
+	)
+	.await?)
+}
+
+async fn identify_uploaded_archive(
+	req: &HttpRequest,
+	upload_dir: &Path,
+	payload: Multipart,
+	rate_limiter: &WeightedRateLimiter,
+	limits: &IdentifyUploadLimits,
+	conn: &DatabaseConnection,
+) -> error::Result<ArchiveIdentifyResponse> {
+	let upload = receive_identify_upload(upload_dir, payload, false, limits).await?;
+
+	if !is_supported_archive(&upload.file_name) {
+		return Err(Error::BadRequest(format!(
+			"{} is no supported archive",
+			upload.file_name
+		)));
+	}
+
+	// the limits are checked with the listing of the archive before anything is extracted, the
+	// extraction enforces them as well in case the listing lies
+	let listing = list_archive_entries(&upload.path)
+		.await
+		.map_err(|e| Error::BadRequest(format!("Failed to read the archive: {}", e)))?;
+
+	check_batch_size(req, listing.files, rate_limiter)?;
+
+	if listing.size > limits.max_upload_size {
+		return Err(Error::BadRequest(format!(
+			"The archive exceeds the maximum extracted size of {} bytes",
+			limits.max_upload_size
+		)));
+	}
+
+	let entries = hash_archive_entries(
+		&upload.path,
+		&upload_dir.join(ARCHIVE_CONTENTS_DIR),
+		limits.max_upload_size,
+		MAX_IDENTIFY_BATCH_SIZE,
+	)
+	.await
+	.map_err(|e| Error::BadRequest(format!("Failed to extract the archive: {}", e)))?;
+
+	Ok(identify_hashed_archive_entries(entries, upload.include_retired, conn).await?)
+}
+
+/// A file uploaded to identify, stored in the upload directory.
+struct IdentifyUpload {
+	path: PathBuf,
+	file_name: String,
+	include_retired: Option<bool>,
+
+	/// Only set if the file was hashed while it was received.
+	hashes: Option<(FileHashes, Option<DetectedHeader>)>,
+}
+
+async fn receive_identify_upload(
+	upload_dir: &Path,
+	mut payload: Multipart,
+	hash: bool,
+	limits: &IdentifyUploadLimits,
+) -> error::Result<IdentifyUpload> {
+	let mut file: Option<(PathBuf, String, Option<BackgroundFileHasher<_>>)> = None;
+	let mut include_retired: Option<bool> = None;
+
+	while let Some(mut field) = payload
+		.try_next()
+		.await
+		.map_err(|e| Error::BadRequest(e.to_string()))?
+	{
+		match field.name() {
+			Some(FILE_FIELD) => {
+				let file_name = field
+					.content_disposition()
+					.and_then(|disposition| disposition.get_filename())
+					.and_then(|name| Path::new(name).file_name())
+					.and_then(|name| name.to_str())
+					.unwrap_or(DEFAULT_FILE_NAME)
+					.to_string();
+
+				// hashing gigabytes takes a while, so the chunks are hashed in a blocking task
+				// while they are written, extracting an archive is done afterwards
+				let file_dir = upload_dir.join(UPLOAD_FILE_DIR);
+				fs::create_dir_all(&file_dir)
+					.await
+					.map_err(anyhow::Error::from)?;
+
+				let path = file_dir.join(&file_name);
+				let mut out = File::create(&path).await.map_err(anyhow::Error::from)?;
+				let hasher = hash.then(BackgroundFileHasher::spawn);
+				let mut size = 0;
+
+				while let Some(chunk) = field
+					.try_next()
+					.await
+					.map_err(|e| Error::BadRequest(e.to_string()))?
+				{
+					size += chunk.len() as u64;
+
+					if size > limits.max_upload_size {
+						return Err(Error::BadRequest(format!(
+							"file exceeds the maximum size of {} bytes",
+							limits.max_upload_size
+						)));
+					}
+
+					if let Some(hasher) = &hasher {
+						hasher.update(chunk.clone()).await?;
+					}
+
+					out.write_all(&chunk).await.map_err(anyhow::Error::from)?;
+				}
+
+				out.flush().await.map_err(anyhow::Error::from)?;
+				file = Some((path, file_name, hasher));
+			}
+			Some(INCLUDE_RETIRED_FIELD) => {
+				let mut value = Vec::new();
+
+				while let Some(chunk) = field
+					.try_next()
+					.await
+					.map_err(|e| Error::BadRequest(e.to_string()))?
+				{
+					value.extend_from_slice(&chunk);
+
+					if value.len() > MAX_INCLUDE_RETIRED_LENGTH {
+						return Err(Error::BadRequest(
+							"includeRetired is no boolean".to_string(),
+						));
+					}
+				}
+
+				include_retired = Some(
+					String::from_utf8_lossy(&value)
+						.trim()
+						.parse()
+						.map_err(|_| {
+							Error::BadRequest("includeRetired is no boolean".to_string())
+						})?,
+				);
+			}
+			_ => {}
+		}
+	}
+
+	let (path, file_name, hasher) =
+		file.ok_or_else(|| Error::BadRequest("missing file field".to_string()))?;
+
+	let hashes = match hasher {
+		Some(hasher) => Some(hasher.finalize().await?),
+		None => None,
+	};
+
+	Ok(IdentifyUpload {
+		path,
+		file_name,
+		include_retired,
+		hashes,
+	})
+}
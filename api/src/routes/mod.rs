@@ -1,4 +1,7 @@
+pub mod admin;
 pub mod company;
+pub mod dat_file;
+pub mod game;
 pub mod health;
 pub mod identify;
 pub mod igdb;
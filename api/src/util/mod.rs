@@ -3,8 +3,10 @@ use reqwest::Client;
 use sea_orm::DbConn;
 use serde::de::DeserializeOwned;
 use service::dat::download_and_parse_dats;
+use service::dat::drop_in::watch_drop_in_dats;
 use service::metadata::igdb::IgdbClient;
 use service::r#match::igdb::match_db_to_igdb_entities;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::task::JoinHandle;
 
@@ -19,6 +21,12 @@ pub async fn wrap_download_and_parse_dats(client: Arc<Client>, conn: Arc<DbConn>
 	}
 }
 
+pub async fn wrap_watch_drop_in_dats(path: PathBuf, conn: Arc<DbConn>) {
+	if let Err(e) = watch_drop_in_dats(path, conn.as_ref().clone()).await {
+		error!("Failed to watch DAT drop-in directory: {}", e);
+	}
+}
+
 pub async fn wrap_match_db_to_igdb_entities(igdb_client: Arc<IgdbClient>, conn: Arc<DbConn>) {
 	match match_db_to_igdb_entities(igdb_client, &conn).await {
 		Ok(()) => {
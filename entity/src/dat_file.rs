@@ -15,6 +15,8 @@ pub struct Model {
 	pub signature_group_id: Uuid,
 	pub tags: Option<Vec<String>>,
 	pub subset: Option<String>,
+	#[sea_orm(column_type = "Text", nullable)]
+	pub header_detector_name: Option<String>,
 	pub created_at: DateTimeWithTimeZone,
 	pub updated_at: DateTimeWithTimeZone,
 }
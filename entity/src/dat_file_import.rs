@@ -27,6 +27,8 @@ pub enum Relation {
 		on_delete = "Cascade"
 	)]
 	DatFile,
+	#[sea_orm(has_many = "super::dat_file_import_change::Entity")]
+	DatFileImportChange,
 	#[sea_orm(has_many = "super::game::Entity")]
 	Game,
 }
@@ -37,6 +39,12 @@ impl Related<super::dat_file::Entity> for Entity {
 	}
 }
 
+impl Related<super::dat_file_import_change::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::DatFileImportChange.def()
+	}
+}
+
 impl Related<super::game::Entity> for Entity {
 	fn to() -> RelationDef {
 		Relation::Game.def()
@@ -1,5 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15
 
+use super::sea_orm_active_enums::GameKindEnum;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -18,10 +19,18 @@ pub struct Model {
 	pub updated_at: DateTimeWithTimeZone,
 	#[sea_orm(column_type = "Text", nullable)]
 	pub signature_group_internal_clone_of_id: Option<String>,
+	#[sea_orm(column_type = "Text", nullable)]
+	pub signature_group_internal_rom_of_id: Option<String>,
+	pub kind: GameKindEnum,
+	pub retired_at: Option<DateTimeWithTimeZone>,
+	pub retired_by_dat_file_import_id: Option<Uuid>,
+	pub rom_of: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+	#[sea_orm(has_many = "super::dat_file_import_change::Entity")]
+	DatFileImportChange,
 	#[sea_orm(
 		belongs_to = "super::dat_file_import::Entity",
 		from = "Column::DatFileImportId",
@@ -44,6 +53,12 @@ pub enum Relation {
 	SignatureMetadataMapping,
 }
 
+impl Related<super::dat_file_import_change::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::DatFileImportChange.def()
+	}
+}
+
 impl Related<super::dat_file_import::Entity> for Entity {
 	fn to() -> RelationDef {
 		Relation::DatFileImport.def()
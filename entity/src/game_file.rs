@@ -17,6 +17,7 @@ pub struct Model {
 	pub sha256: Option<String>,
 	pub status: Option<String>,
 	pub serial: Option<String>,
+	pub normalized_serials: Option<Vec<String>>,
 	pub created_at: DateTimeWithTimeZone,
 	pub updated_at: DateTimeWithTimeZone,
 }
@@ -5,6 +5,8 @@ pub mod prelude;
 pub mod company;
 pub mod dat_file;
 pub mod dat_file_import;
+pub mod dat_file_import_change;
+pub mod dat_file_import_failure;
 pub mod game;
 pub mod game_file;
 pub mod platform;
@@ -3,6 +3,8 @@
 pub use super::company::Entity as Company;
 pub use super::dat_file::Entity as DatFile;
 pub use super::dat_file_import::Entity as DatFileImport;
+pub use super::dat_file_import_change::Entity as DatFileImportChange;
+pub use super::dat_file_import_failure::Entity as DatFileImportFailure;
 pub use super::game::Entity as Game;
 pub use super::game_file::Entity as GameFile;
 pub use super::platform::Entity as Platform;
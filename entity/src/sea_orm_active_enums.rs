@@ -20,6 +20,26 @@ pub enum AutomaticMatchReasonEnum {
 	ViaParent,
 }
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(
+	rs_type = "String",
+	db_type = "Enum",
+	enum_name = "dat_file_import_change_type_enum"
+)]
+pub enum DatFileImportChangeTypeEnum {
+	#[sea_orm(string_value = "file_added")]
+	FileAdded,
+	#[sea_orm(string_value = "file_changed")]
+	FileChanged,
+	#[sea_orm(string_value = "file_removed")]
+	FileRemoved,
+	#[sea_orm(string_value = "game_added")]
+	GameAdded,
+	#[sea_orm(string_value = "game_removed")]
+	GameRemoved,
+	#[sea_orm(string_value = "game_renamed")]
+	GameRenamed,
+}
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
 #[sea_orm(
 	rs_type = "String",
 	db_type = "Enum",
@@ -32,6 +52,18 @@ pub enum FailedMatchReasonEnum {
 	TooManyMatches,
 }
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "game_kind_enum")]
+pub enum GameKindEnum {
+	#[sea_orm(string_value = "application")]
+	Application,
+	#[sea_orm(string_value = "bios")]
+	Bios,
+	#[sea_orm(string_value = "firmware")]
+	Firmware,
+	#[sea_orm(string_value = "game")]
+	Game,
+}
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
 #[sea_orm(
 	rs_type = "String",
 	db_type = "Enum",
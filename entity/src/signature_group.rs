@@ -19,6 +19,8 @@ pub struct Model {
 pub enum Relation {
 	#[sea_orm(has_many = "super::dat_file::Entity")]
 	DatFile,
+	#[sea_orm(has_many = "super::dat_file_import_failure::Entity")]
+	DatFileImportFailure,
 }
 
 impl Related<super::dat_file::Entity> for Entity {
@@ -27,4 +29,10 @@ impl Related<super::dat_file::Entity> for Entity {
 	}
 }
 
+impl Related<super::dat_file_import_failure::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::DatFileImportFailure.def()
+	}
+}
+
 impl ActiveModelBehavior for ActiveModel {}
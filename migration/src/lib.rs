@@ -10,6 +10,17 @@ mod m20240819_194749_add_parent_and_sibling_automatic_match_reason;
 mod m20240820_154703_add_signature_group_internal_clone_of_id;
 mod m20240823_145438_add_signature_metadata_mapping_unique_indexes;
 mod m20240827_153244_fix_signature_metadata_mapping_unique_indexes_to_take_provider_into_account;
+mod m20261018_101500_add_game_signature_group_internal_rom_of_id;
+mod m20261018_120000_add_game_kind;
+mod m20261018_130000_add_game_retired;
+mod m20261018_140000_create_dat_file_import_change;
+mod m20261018_150000_create_dat_file_import_failure;
+mod m20261018_160000_add_dat_file_header_detector_name;
+mod m20261018_170000_add_game_file_normalized_serials;
+mod m20261018_180000_normalize_game_file_crc;
+mod m20261018_190000_add_game_name_trigram_index;
+mod m20261018_200000_add_game_rom_of_relation;
+mod m20261018_210000_add_game_name_full_text_index;
 
 pub struct Migrator;
 
@@ -27,6 +38,17 @@ impl MigratorTrait for Migrator {
 			Box::new(m20240820_154703_add_signature_group_internal_clone_of_id::Migration),
 			Box::new(m20240823_145438_add_signature_metadata_mapping_unique_indexes::Migration),
 			Box::new(m20240827_153244_fix_signature_metadata_mapping_unique_indexes_to_take_provider_into_account::Migration),
+			Box::new(m20261018_101500_add_game_signature_group_internal_rom_of_id::Migration),
+			Box::new(m20261018_120000_add_game_kind::Migration),
+			Box::new(m20261018_130000_add_game_retired::Migration),
+			Box::new(m20261018_140000_create_dat_file_import_change::Migration),
+			Box::new(m20261018_150000_create_dat_file_import_failure::Migration),
+			Box::new(m20261018_160000_add_dat_file_header_detector_name::Migration),
+			Box::new(m20261018_170000_add_game_file_normalized_serials::Migration),
+			Box::new(m20261018_180000_normalize_game_file_crc::Migration),
+			Box::new(m20261018_190000_add_game_name_trigram_index::Migration),
+			Box::new(m20261018_200000_add_game_rom_of_relation::Migration),
+			Box::new(m20261018_210000_add_game_name_full_text_index::Migration),
 		]
 	}
 }
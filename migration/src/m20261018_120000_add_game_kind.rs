@@ -0,0 +1,67 @@
+use crate::extension::postgres::Type;
+use crate::sea_orm::{EnumIter, Iterable};
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+struct GameKindEnum;
+
+#[derive(DeriveIden, EnumIter)]
+pub enum GameKind {
+	Game,
+	Bios,
+	Firmware,
+	Application,
+}
+
+#[derive(Iden)]
+enum Game {
+	Table,
+	Kind,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_type(
+				Type::create()
+					.as_enum(GameKindEnum)
+					.values(GameKind::iter())
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Game::Table)
+					.add_column(
+						ColumnDef::new(Game::Kind)
+							.enumeration(GameKindEnum, GameKind::iter())
+							.not_null()
+							.default(GameKind::Game.to_string())
+							.to_owned(),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				Table::alter()
+					.table(Game::Table)
+					.drop_column(Game::Kind)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.drop_type(Type::drop().name(GameKindEnum).to_owned())
+			.await
+	}
+}
@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Game {
+	Table,
+	RetiredAt,
+	RetiredByDatFileImportId,
+}
+
+#[derive(Iden)]
+enum DatFileImport {
+	Table,
+	Id,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				TableAlterStatement::new()
+					.table(Game::Table)
+					.add_column(
+						ColumnDef::new(Game::RetiredAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.add_column(ColumnDef::new(Game::RetiredByDatFileImportId).uuid().null())
+					.add_foreign_key(
+						TableForeignKey::new()
+							.name("fk-game-retired_by_dat_file_import_id")
+							.from_tbl(Game::Table)
+							.from_col(Game::RetiredByDatFileImportId)
+							.to_tbl(DatFileImport::Table)
+							.to_col(DatFileImport::Id)
+							.on_delete(ForeignKeyAction::SetNull),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				TableAlterStatement::new()
+					.table(Game::Table)
+					.drop_foreign_key(Alias::new("fk-game-retired_by_dat_file_import_id"))
+					.drop_column(Game::RetiredByDatFileImportId)
+					.drop_column(Game::RetiredAt)
+					.to_owned(),
+			)
+			.await
+	}
+}
@@ -0,0 +1,158 @@
+use crate::extension::postgres::Type;
+use crate::sea_orm::{EnumIter, Iterable};
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+struct DatFileImportChangeTypeEnum;
+
+#[derive(DeriveIden, EnumIter)]
+pub enum DatFileImportChangeType {
+	GameAdded,
+	GameRemoved,
+	GameRenamed,
+	FileAdded,
+	FileRemoved,
+	FileChanged,
+}
+
+#[derive(Iden)]
+enum DatFileImportChange {
+	Table,
+	Id,
+	DatFileImportId,
+	ChangeType,
+	GameId,
+	GameName,
+	PreviousGameName,
+	FileName,
+	PreviousCrc,
+	Crc,
+	PreviousSha1,
+	Sha1,
+	CreatedAt,
+}
+
+#[derive(Iden)]
+enum DatFileImport {
+	Table,
+	Id,
+}
+
+#[derive(Iden)]
+enum Game {
+	Table,
+	Id,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_type(
+				Type::create()
+					.as_enum(DatFileImportChangeTypeEnum)
+					.values(DatFileImportChangeType::iter())
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_table(
+				Table::create()
+					.table(DatFileImportChange::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(DatFileImportChange::Id)
+							.uuid()
+							.not_null()
+							.primary_key()
+							.extra("DEFAULT gen_random_uuid()"),
+					)
+					.col(
+						ColumnDef::new(DatFileImportChange::DatFileImportId)
+							.uuid()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(DatFileImportChange::ChangeType)
+							.enumeration(
+								DatFileImportChangeTypeEnum,
+								DatFileImportChangeType::iter(),
+							)
+							.not_null(),
+					)
+					.col(ColumnDef::new(DatFileImportChange::GameId).uuid().null())
+					.col(
+						ColumnDef::new(DatFileImportChange::GameName)
+							.text()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(DatFileImportChange::PreviousGameName)
+							.text()
+							.null(),
+					)
+					.col(ColumnDef::new(DatFileImportChange::FileName).text().null())
+					.col(
+						ColumnDef::new(DatFileImportChange::PreviousCrc)
+							.string()
+							.null(),
+					)
+					.col(ColumnDef::new(DatFileImportChange::Crc).string().null())
+					.col(
+						ColumnDef::new(DatFileImportChange::PreviousSha1)
+							.string()
+							.null(),
+					)
+					.col(ColumnDef::new(DatFileImportChange::Sha1).string().null())
+					.col(
+						ColumnDef::new(DatFileImportChange::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.name("fk-dat_file_import_change-dat_file_import_id")
+							.from(
+								DatFileImportChange::Table,
+								DatFileImportChange::DatFileImportId,
+							)
+							.to(DatFileImport::Table, DatFileImport::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.name("fk-dat_file_import_change-game_id")
+							.from(DatFileImportChange::Table, DatFileImportChange::GameId)
+							.to(Game::Table, Game::Id)
+							.on_delete(ForeignKeyAction::SetNull),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_dat_file_import_change_dat_file_import_id")
+					.table(DatFileImportChange::Table)
+					.col(DatFileImportChange::DatFileImportId)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(DatFileImportChange::Table).to_owned())
+			.await?;
+
+		manager
+			.drop_type(Type::drop().name(DatFileImportChangeTypeEnum).to_owned())
+			.await
+	}
+}
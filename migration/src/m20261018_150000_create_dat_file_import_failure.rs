@@ -0,0 +1,84 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum DatFileImportFailure {
+	Table,
+	Id,
+	SignatureGroupId,
+	FileName,
+	Md5Hash,
+	Error,
+	FailedAt,
+}
+
+#[derive(Iden)]
+enum SignatureGroup {
+	Table,
+	Id,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(DatFileImportFailure::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(DatFileImportFailure::Id)
+							.uuid()
+							.not_null()
+							.primary_key()
+							.extra("DEFAULT gen_random_uuid()"),
+					)
+					.col(
+						ColumnDef::new(DatFileImportFailure::SignatureGroupId)
+							.uuid()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(DatFileImportFailure::FileName)
+							.text()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(DatFileImportFailure::Md5Hash)
+							.string()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(DatFileImportFailure::Error)
+							.text()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(DatFileImportFailure::FailedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.name("fk-dat_file_import_failure-signature_group_id")
+							.from(
+								DatFileImportFailure::Table,
+								DatFileImportFailure::SignatureGroupId,
+							)
+							.to(SignatureGroup::Table, SignatureGroup::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(DatFileImportFailure::Table).to_owned())
+			.await
+	}
+}
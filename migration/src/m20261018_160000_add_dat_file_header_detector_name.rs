@@ -0,0 +1,35 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum DatFile {
+	Table,
+	HeaderDetectorName,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				TableAlterStatement::new()
+					.table(DatFile::Table)
+					.add_column(ColumnDef::new(DatFile::HeaderDetectorName).text().null())
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				TableAlterStatement::new()
+					.table(DatFile::Table)
+					.drop_column(DatFile::HeaderDetectorName)
+					.to_owned(),
+			)
+			.await
+	}
+}
@@ -0,0 +1,52 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		let conn = manager.get_connection();
+
+		// DATs use different notations for the same serial (SLUS-00594, SLUS 00594, SLUS_005.94)
+		// and can list multiple serials in one field, so only the letters and digits of every
+		// serial are kept
+		let create_function_sql = r#"
+        CREATE OR REPLACE FUNCTION normalize_serials(serial text)
+        RETURNS text[] AS $$
+            SELECT coalesce(array_agg(DISTINCT normalized), '{}')
+            FROM (
+                SELECT upper(regexp_replace(part, '[^A-Za-z0-9]', '', 'g')) AS normalized
+                FROM regexp_split_to_table(serial, '[,;/]') AS part
+            ) AS parts
+            WHERE normalized <> '';
+        $$ LANGUAGE sql IMMUTABLE STRICT PARALLEL SAFE;
+        "#;
+		conn.execute_unprepared(create_function_sql).await?;
+
+		// generated, so imports and existing rows never get out of sync with the function
+		let add_column_sql = r#"
+        ALTER TABLE game_file
+        ADD COLUMN normalized_serials text[] GENERATED ALWAYS AS (normalize_serials(serial)) STORED;
+        "#;
+		conn.execute_unprepared(add_column_sql).await?;
+
+		let create_index_sql = r#"
+        CREATE INDEX idx_game_file_normalized_serials ON game_file USING gin (normalized_serials);
+        "#;
+		conn.execute_unprepared(create_index_sql).await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		let conn = manager.get_connection();
+
+		conn.execute_unprepared("ALTER TABLE game_file DROP COLUMN normalized_serials;")
+			.await?;
+		conn.execute_unprepared("DROP FUNCTION normalize_serials(text);")
+			.await?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,35 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		let conn = manager.get_connection();
+
+		// CRCs are imported as 8 lowercase hex digits from now on, existing ones are brought into
+		// the same format so they can be matched and compared with newer imports
+		let normalize_crc_sql = r#"
+        UPDATE game_file
+        SET crc = normalized.crc
+        FROM (
+            SELECT id, CASE
+                WHEN crc = '' THEN NULL
+                WHEN length(crc) < 8 THEN lpad(crc, 8, '0')
+                ELSE crc
+            END AS crc
+            FROM (SELECT id, lower(trim(crc)) AS crc FROM game_file WHERE crc IS NOT NULL) AS trimmed
+        ) AS normalized
+        WHERE game_file.id = normalized.id AND game_file.crc IS DISTINCT FROM normalized.crc;
+        "#;
+		conn.execute_unprepared(normalize_crc_sql).await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+		// the original notation is gone, normalized CRCs are valid either way
+		Ok(())
+	}
+}
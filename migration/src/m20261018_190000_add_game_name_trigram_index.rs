@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		let conn = manager.get_connection();
+
+		conn.execute_unprepared("CREATE EXTENSION IF NOT EXISTS pg_trgm;")
+			.await?;
+
+		// the same rules as clean_name of the IGDB matcher, tags like (USA) or (Rev 1) would
+		// otherwise make up most of the trigrams of short names
+		let create_function_sql = r#"
+        CREATE OR REPLACE FUNCTION clean_game_name(name text)
+        RETURNS text AS $$
+            SELECT regexp_replace(name, '\s*\([^)]*\)', '', 'g');
+        $$ LANGUAGE sql IMMUTABLE STRICT PARALLEL SAFE;
+        "#;
+		conn.execute_unprepared(create_function_sql).await?;
+
+		let create_index_sql = r#"
+        CREATE INDEX idx_game_clean_name_trgm ON game USING gin (clean_game_name(name) gin_trgm_ops);
+        "#;
+		conn.execute_unprepared(create_index_sql).await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		let conn = manager.get_connection();
+
+		conn.execute_unprepared("DROP INDEX idx_game_clean_name_trgm;")
+			.await?;
+		conn.execute_unprepared("DROP FUNCTION clean_game_name(text);")
+			.await?;
+
+		Ok(())
+	}
+}
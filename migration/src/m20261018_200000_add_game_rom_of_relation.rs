@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Game {
+	Table,
+	Id,
+	RomOf,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				TableAlterStatement::new()
+					.table(Game::Table)
+					.add_column(ColumnDef::new(Game::RomOf).uuid().null())
+					.add_foreign_key(
+						TableForeignKey::new()
+							.name("fk-game-rom_of")
+							.from_tbl(Game::Table)
+							.from_col(Game::RomOf)
+							.to_tbl(Game::Table)
+							.to_col(Game::Id)
+							.on_delete(ForeignKeyAction::SetNull),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_game_rom_of")
+					.table(Game::Table)
+					.col(Game::RomOf)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_index(
+				Index::drop()
+					.name("idx_game_rom_of")
+					.table(Game::Table)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.alter_table(
+				TableAlterStatement::new()
+					.table(Game::Table)
+					.drop_foreign_key(Alias::new("fk-game-rom_of"))
+					.drop_column(Game::RomOf)
+					.to_owned(),
+			)
+			.await
+	}
+}
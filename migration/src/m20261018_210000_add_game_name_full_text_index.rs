@@ -0,0 +1,28 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		let conn = manager.get_connection();
+
+		// the simple configuration doesn't stem or drop words, names are no prose
+		let create_index_sql = r#"
+        CREATE INDEX idx_game_clean_name_tsvector ON game USING gin (to_tsvector('simple', clean_game_name(name)));
+        "#;
+		conn.execute_unprepared(create_index_sql).await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		let conn = manager.get_connection();
+
+		conn.execute_unprepared("DROP INDEX idx_game_clean_name_tsvector;")
+			.await?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,501 @@
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, bail};
+use flate2::read::MultiGzDecoder;
+use log::{debug, warn};
+use sevenz_rust::{Password, SevenZReader};
+use tar::EntryType;
+use zip::ZipArchive;
+
+/// Upper bound for the uncompressed size of an archive including all nested archives, protects
+/// against archive bombs.
+const MAX_EXTRACTED_SIZE: u64 = 16 * 1024 * 1024 * 1024;
+
+/// Upper bound for the number of files in an archive including all nested archives.
+const MAX_EXTRACTED_ENTRIES: usize = 100_000;
+
+/// Archives in archives are extracted up to this depth, e.g. a zip of 7z packs is depth 2.
+const MAX_NESTING_DEPTH: usize = 3;
+
+/// Limits for extracting an archive, protect against archive bombs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtractionLimits {
+	/// Upper bound for the uncompressed size of all extracted files.
+	pub max_size: u64,
+
+	/// Upper bound for the number of extracted files.
+	pub max_entries: usize,
+
+	/// Whether archives in the archive are extracted as well, otherwise they are kept as files.
+	pub extract_nested: bool,
+}
+
+impl ExtractionLimits {
+	/// DATs are often distributed as packs of archives, so nested archives are extracted.
+	pub const DAT: ExtractionLimits = ExtractionLimits {
+		max_size: MAX_EXTRACTED_SIZE,
+		max_entries: MAX_EXTRACTED_ENTRIES,
+		extract_nested: true,
+	};
+}
+
+/// The files of an archive as listed in its central directory or headers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArchiveListing {
+	/// The number of files, directories and links are not counted.
+	pub files: usize,
+
+	/// The uncompressed size of all files as declared by the archive.
+	pub size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+	Zip,
+	SevenZip,
+	Tar,
+	TarGz,
+	Gzip,
+}
+
+impl ArchiveKind {
+	/// Detects the archive kind by the file extension, `None` if the file is no (supported)
+	/// archive.
+	pub fn from_path(path: &Path) -> Option<Self> {
+		let file_name = path.file_name()?.to_str()?.to_lowercase();
+
+		if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+			Some(ArchiveKind::TarGz)
+		} else if file_name.ends_with(".tar") {
+			Some(ArchiveKind::Tar)
+		} else if file_name.ends_with(".gz") {
+			Some(ArchiveKind::Gzip)
+		} else if file_name.ends_with(".7z") {
+			Some(ArchiveKind::SevenZip)
+		} else if file_name.ends_with(".zip") {
+			Some(ArchiveKind::Zip)
+		} else {
+			None
+		}
+	}
+
+	/// Gzip only compresses a single file and has no folder structure of its own.
+	pub fn is_single_file(&self) -> bool {
+		*self == ArchiveKind::Gzip
+	}
+
+	fn extension_len(&self) -> usize {
+		match self {
+			ArchiveKind::Zip | ArchiveKind::Tar => 4,
+			ArchiveKind::SevenZip => 3,
+			ArchiveKind::TarGz => 7,
+			ArchiveKind::Gzip => 3,
+		}
+	}
+
+	/// Returns the file name without the archive extension, e.g. `pack` for `pack.tar.gz`.
+	pub fn strip_extension(&self, path: &Path) -> Option<String> {
+		let file_name = path.file_name()?.to_str()?;
+
+		let len = if *self == ArchiveKind::TarGz && file_name.to_lowercase().ends_with(".tgz") {
+			4
+		} else {
+			self.extension_len()
+		};
+
+		file_name
+			.get(..file_name.len().saturating_sub(len))
+			.filter(|name| !name.is_empty())
+			.map(ToString::to_string)
+	}
+}
+
+pub fn is_archive(path: &Path) -> bool {
+	ArchiveKind::from_path(path).is_some()
+}
+
+/// Extracts an archive into the given directory, archives inside the archive are extracted next
+/// to themselves and removed afterward. Entries which would end up outside of `out_dir` as well
+/// as links are skipped.
+pub fn extract_archive_to_directory(path: &Path, out_dir: &Path) -> anyhow::Result<()> {
+	extract_archive_to_directory_with_limits(path, out_dir, ExtractionLimits::DAT)
+}
+
+/// Extracts an archive like [`extract_archive_to_directory`], but with the given limits.
+pub fn extract_archive_to_directory_with_limits(
+	path: &Path,
+	out_dir: &Path,
+	limits: ExtractionLimits,
+) -> anyhow::Result<()> {
+	let mut extraction = Extraction {
+		limits,
+		size: 0,
+		entries: 0,
+	};
+	extraction.extract(path, out_dir, 1)
+}
+
+/// Reads the number of files in an archive and their size without writing anything. Zip and 7z
+/// archives list them in their central directory, tar archives are read through for the headers
+/// of their entries. The size of a gzip file is only known once it is decompressed, so it is
+/// listed with a size of 0. Archives in the archive are counted as a single file.
+pub fn read_archive_listing(path: &Path) -> anyhow::Result<ArchiveListing> {
+	let kind = ArchiveKind::from_path(path)
+		.ok_or_else(|| anyhow!("Unsupported archive: {}", path.display()))?;
+
+	match kind {
+		ArchiveKind::Zip => read_zip_listing(path),
+		ArchiveKind::SevenZip => read_7z_listing(path),
+		ArchiveKind::Tar => read_tar_listing(BufReader::new(File::open(path)?)),
+		ArchiveKind::TarGz => {
+			read_tar_listing(MultiGzDecoder::new(BufReader::new(File::open(path)?)))
+		}
+		ArchiveKind::Gzip => Ok(ArchiveListing { files: 1, size: 0 }),
+	}
+}
+
+fn read_zip_listing(path: &Path) -> anyhow::Result<ArchiveListing> {
+	let mut archive = ZipArchive::new(File::open(path)?)?;
+	let mut listing = ArchiveListing::default();
+
+	for i in 0..archive.len() {
+		let entry = archive.by_index_raw(i)?;
+
+		if entry.is_dir() || entry.is_symlink() {
+			continue;
+		}
+
+		listing.files += 1;
+		listing.size = listing.size.saturating_add(entry.size());
+	}
+
+	Ok(listing)
+}
+
+fn read_7z_listing(path: &Path) -> anyhow::Result<ArchiveListing> {
+	let archive = SevenZReader::open(path, Password::empty())?;
+	let mut listing = ArchiveListing::default();
+
+	for entry in archive.archive().files.iter() {
+		if entry.is_directory() {
+			continue;
+		}
+
+		listing.files += 1;
+		listing.size = listing.size.saturating_add(entry.size());
+	}
+
+	Ok(listing)
+}
+
+fn read_tar_listing(reader: impl Read) -> anyhow::Result<ArchiveListing> {
+	let mut archive = tar::Archive::new(reader);
+	let mut listing = ArchiveListing::default();
+
+	// the data of the entries is skipped while iterating
+	for entry in archive.entries()? {
+		let entry = entry?;
+
+		if matches!(
+			entry.header().entry_type(),
+			EntryType::Regular | EntryType::Continuous
+		) {
+			listing.files += 1;
+			listing.size = listing.size.saturating_add(entry.header().size()?);
+		}
+	}
+
+	Ok(listing)
+}
+
+struct Extraction {
+	limits: ExtractionLimits,
+	size: u64,
+	entries: usize,
+}
+
+impl Extraction {
+	fn extract(&mut self, path: &Path, out_dir: &Path, depth: usize) -> anyhow::Result<()> {
+		let kind = ArchiveKind::from_path(path)
+			.ok_or_else(|| anyhow!("Unsupported archive: {}", path.display()))?;
+
+		if depth > MAX_NESTING_DEPTH {
+			bail!(
+				"Archive {} is nested deeper than {} levels",
+				path.display(),
+				MAX_NESTING_DEPTH
+			);
+		}
+
+		debug!("Extracting {:?} archive {:?} to {:?}", kind, path, out_dir);
+		fs::create_dir_all(out_dir)?;
+
+		let files = match kind {
+			ArchiveKind::Zip => self.extract_zip(path, out_dir)?,
+			ArchiveKind::SevenZip => self.extract_7z(path, out_dir)?,
+			ArchiveKind::Tar => self.extract_tar(BufReader::new(File::open(path)?), out_dir)?,
+			ArchiveKind::TarGz => self.extract_tar(
+				MultiGzDecoder::new(BufReader::new(File::open(path)?)),
+				out_dir,
+			)?,
+			ArchiveKind::Gzip => self.extract_gzip(path, out_dir)?,
+		};
+
+		if !self.limits.extract_nested {
+			return Ok(());
+		}
+
+		for file in files {
+			let Some(nested_kind) = ArchiveKind::from_path(&file) else {
+				continue;
+			};
+
+			let nested_out_dir = if nested_kind.is_single_file() {
+				file.parent().unwrap_or(out_dir).to_path_buf()
+			} else {
+				let name = nested_kind
+					.strip_extension(&file)
+					.ok_or_else(|| anyhow!("Invalid archive name: {}", file.display()))?;
+				file.with_file_name(name)
+			};
+
+			self.extract(&file, &nested_out_dir, depth + 1)?;
+			fs::remove_file(&file)?;
+		}
+
+		Ok(())
+	}
+
+	fn extract_zip(&mut self, path: &Path, out_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+		let mut archive = ZipArchive::new(File::open(path)?)?;
+		let mut files = Vec::new();
+
+		for i in 0..archive.len() {
+			let mut entry = archive.by_index(i)?;
+
+			if entry.is_dir() {
+				continue;
+			}
+
+			if entry.is_symlink() {
+				warn!("Skipping symlink in archive: {}", entry.name());
+				continue;
+			}
+
+			let Some(name) = entry.enclosed_name() else {
+				warn!("Skipping unsafe path in archive: {}", entry.name());
+				continue;
+			};
+
+			if let Some(file) = self.write_entry(out_dir, &name, &mut entry)? {
+				files.push(file);
+			}
+		}
+
+		Ok(files)
+	}
+
+	fn extract_7z(&mut self, path: &Path, out_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+		let mut archive = SevenZReader::open(path, Password::empty())?;
+		let mut files = Vec::new();
+		let mut error = None;
+
+		// the callback can only return 7z errors, so ours are passed out separately
+		archive.for_each_entries(|entry, reader| {
+			if error.is_some() {
+				return Ok(false);
+			}
+
+			if entry.is_directory() {
+				return Ok(true);
+			}
+
+			match self.write_entry(out_dir, Path::new(entry.name()), reader) {
+				Ok(Some(file)) => files.push(file),
+				Ok(None) => {
+					// entries of solid archives depend on the previous ones, so the skipped data
+					// still has to be decoded
+					io::copy(reader, &mut io::sink())?;
+				}
+				Err(e) => {
+					error = Some(e);
+					return Ok(false);
+				}
+			}
+
+			Ok(true)
+		})?;
+
+		match error {
+			Some(e) => Err(e),
+			None => Ok(files),
+		}
+	}
+
+	fn extract_tar(&mut self, reader: impl Read, out_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+		let mut archive = tar::Archive::new(reader);
+		let mut files = Vec::new();
+
+		for entry in archive.entries()? {
+			let mut entry = entry?;
+			let entry_type = entry.header().entry_type();
+			let name = entry.path()?.into_owned();
+
+			match entry_type {
+				EntryType::Regular | EntryType::Continuous => {}
+				EntryType::Directory => continue,
+				_ => {
+					warn!(
+						"Skipping {:?} entry in archive: {}",
+						entry_type,
+						name.display()
+					);
+					continue;
+				}
+			}
+
+			if let Some(file) = self.write_entry(out_dir, &name, &mut entry)? {
+				files.push(file);
+			}
+		}
+
+		Ok(files)
+	}
+
+	fn extract_gzip(&mut self, path: &Path, out_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+		let name = ArchiveKind::Gzip
+			.strip_extension(path)
+			.ok_or_else(|| anyhow!("Invalid archive name: {}", path.display()))?;
+		let mut reader = MultiGzDecoder::new(BufReader::new(File::open(path)?));
+
+		Ok(self
+			.write_entry(out_dir, Path::new(&name), &mut reader)?
+			.into_iter()
+			.collect())
+	}
+
+	/// Writes a single archive entry, returns `None` if the entry has been skipped because its
+	/// path is not safe.
+	fn write_entry(
+		&mut self,
+		out_dir: &Path,
+		name: &Path,
+		reader: &mut dyn Read,
+	) -> anyhow::Result<Option<PathBuf>> {
+		let Some(relative_path) = sanitize_entry_path(name) else {
+			warn!("Skipping unsafe path in archive: {}", name.display());
+			return Ok(None);
+		};
+
+		self.entries += 1;
+		if self.entries > self.limits.max_entries {
+			bail!(
+				"Archive contains more than {} files",
+				self.limits.max_entries
+			);
+		}
+
+		let out_path = out_dir.join(relative_path);
+		if let Some(parent) = out_path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		let remaining = self.limits.max_size - self.size;
+		let mut out_file = File::create(&out_path)?;
+		let written = io::copy(&mut reader.take(remaining + 1), &mut out_file)?;
+
+		if written > remaining {
+			bail!(
+				"Archive is larger than {} bytes when extracted",
+				self.limits.max_size
+			);
+		}
+
+		self.size += written;
+
+		Ok(Some(out_path))
+	}
+}
+
+/// Only keeps plain relative paths, anything absolute or pointing to a parent directory could
+/// escape the output directory. Archives created on Windows can use backslashes and drive prefixes
+/// like `C:`, they are treated the same on every platform.
+fn sanitize_entry_path(name: &Path) -> Option<PathBuf> {
+	let name = name.to_string_lossy().replace('\\', "/");
+	let mut path = PathBuf::new();
+
+	for component in Path::new(&name).components() {
+		match component {
+			Component::Normal(part) if is_drive_prefix(part) => return None,
+			Component::Normal(part) => path.push(part),
+			Component::CurDir => {}
+			Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+		}
+	}
+
+	if path.as_os_str().is_empty() {
+		None
+	} else {
+		Some(path)
+	}
+}
+
+/// Checks for a drive prefix like `C:`, which only is a prefix component on Windows.
+fn is_drive_prefix(part: &OsStr) -> bool {
+	let part = part.as_encoded_bytes();
+
+	part.len() == 2 && part[0].is_ascii_alphabetic() && part[1] == b':'
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn sanitize(name: &str) -> Option<PathBuf> {
+		sanitize_entry_path(Path::new(name))
+	}
+
+	#[test]
+	fn keeps_relative_paths() {
+		assert_eq!(
+			sanitize("Tetris (World).gb"),
+			Some(PathBuf::from("Tetris (World).gb"))
+		);
+		assert_eq!(
+			sanitize("./No-Intro/Nintendo - Game Boy.dat"),
+			Some(PathBuf::from("No-Intro/Nintendo - Game Boy.dat"))
+		);
+		assert_eq!(
+			sanitize("No-Intro\\Nintendo - Game Boy.dat"),
+			Some(PathBuf::from("No-Intro/Nintendo - Game Boy.dat"))
+		);
+	}
+
+	#[test]
+	fn rejects_parent_directories() {
+		assert_eq!(sanitize("../evil.dat"), None);
+		assert_eq!(sanitize("dats/../../evil.dat"), None);
+		assert_eq!(sanitize("dats\\..\\..\\evil.dat"), None);
+	}
+
+	#[test]
+	fn rejects_absolute_paths() {
+		assert_eq!(sanitize("/etc/passwd"), None);
+		assert_eq!(sanitize("\\Windows\\evil.dat"), None);
+	}
+
+	#[test]
+	fn rejects_drive_prefixes() {
+		assert_eq!(sanitize("C:\\Windows\\evil.dat"), None);
+		assert_eq!(sanitize("c:/evil.dat"), None);
+	}
+
+	#[test]
+	fn rejects_empty_paths() {
+		assert_eq!(sanitize(""), None);
+		assert_eq!(sanitize("./"), None);
+	}
+}
@@ -0,0 +1,177 @@
+use crate::archive::is_archive;
+use crate::dat::shared::archive::extract_if_archived;
+use crate::dat::{import_dat_file_if_new, DatImportOutcome, DAT_IMPORT_LOCK};
+use crate::db::signature_group::create_or_find_signature_group_by_name;
+use crate::fs::read_files_recursive;
+use crate::hash::calculate_md5;
+use crate::r#match::clone::populate_all_clone_of_ids;
+use crate::util::random_sized_string;
+use anyhow::anyhow;
+use log::{debug, error, info, warn};
+use notify::event::{CreateKind, ModifyKind};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use sea_orm::DbConn;
+use std::collections::HashSet;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs;
+use tokio::sync::mpsc::unbounded_channel;
+use tokio::time::timeout;
+
+const DAT_DROP_IN_PATH_ENV: &str = "DAT_DROP_IN_PATH";
+const DROP_IN_TMP_NAME: &str = "playmatch-drop-in";
+
+/// Files are only imported once no new events came in for this long, so we don't read files which
+/// are still being written.
+const DEBOUNCE_DURATION: Duration = Duration::from_secs(2);
+
+/// Returns the configured drop-in directory, if any.
+pub fn drop_in_path() -> Option<PathBuf> {
+	env::var(DAT_DROP_IN_PATH_ENV)
+		.ok()
+		.filter(|path| !path.is_empty())
+		.map(PathBuf::from)
+}
+
+/// Imports all DATs (or archives of DATs) in the drop-in directory and then watches it for new files.
+/// Every subfolder is named after the signature group its DATs belong to, e.g.
+/// `{drop-in}/No-Intro/Nintendo - Game Boy.dat`.
+pub async fn watch_drop_in_dats(path: PathBuf, conn: DbConn) -> anyhow::Result<()> {
+	fs::create_dir_all(&path).await?;
+	let path = path.canonicalize()?;
+
+	let (tx, mut rx) = unbounded_channel();
+
+	let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+		match event {
+			Ok(event) => {
+				if matches!(
+					event.kind,
+					EventKind::Create(CreateKind::File | CreateKind::Any)
+						| EventKind::Modify(
+							ModifyKind::Data(_) | ModifyKind::Name(_) | ModifyKind::Any
+						)
+				) {
+					for path in event.paths {
+						// the receiver only goes away when the watcher is dropped
+						let _ = tx.send(path);
+					}
+				}
+			}
+			Err(e) => error!("Error while watching DAT drop-in directory: {:?}", e),
+		}
+	})?;
+	watcher.watch(&path, RecursiveMode::Recursive)?;
+
+	info!("Watching DAT drop-in directory: {}", path.display());
+
+	let existing_files = read_files_recursive(&path).await?;
+	import_drop_in_files(&path, existing_files, &conn).await;
+
+	let mut pending = HashSet::new();
+
+	loop {
+		match timeout(DEBOUNCE_DURATION, rx.recv()).await {
+			Ok(Some(file)) => {
+				pending.insert(file);
+			}
+			Ok(None) => return Err(anyhow!("DAT drop-in watcher stopped unexpectedly")),
+			Err(_) => {
+				if !pending.is_empty() {
+					let files = pending.drain().collect::<Vec<PathBuf>>();
+					import_drop_in_files(&path, files, &conn).await;
+				}
+			}
+		}
+	}
+}
+
+async fn import_drop_in_files(root: &Path, files: Vec<PathBuf>, conn: &DbConn) {
+	let _lock = DAT_IMPORT_LOCK.lock().await;
+
+	let mut imported_any = false;
+
+	for file in files {
+		if !file.is_file() {
+			continue;
+		}
+
+		match import_drop_in_file(root, &file, conn).await {
+			Ok(imported) => imported_any |= imported,
+			Err(e) => error!(
+				"Failed to import dropped in DAT {}: {:?}",
+				file.display(),
+				e
+			),
+		}
+	}
+
+	if imported_any {
+		if let Err(e) = populate_all_clone_of_ids(conn).await {
+			error!("Failed to populate clone_of relationships: {:?}", e);
+		}
+	}
+}
+
+/// Imports a single dropped in file, archives are extracted into a temporary directory so the
+/// drop-in directory itself is never modified. Returns whether a new DAT was imported.
+async fn import_drop_in_file(root: &Path, file: &Path, conn: &DbConn) -> anyhow::Result<bool> {
+	let Some(signature_group_name) = signature_group_name_of_file(root, file) else {
+		warn!(
+			"Skipping {}, DATs have to be put into a folder named after their signature group",
+			file.display()
+		);
+		return Ok(false);
+	};
+
+	let signature_group =
+		create_or_find_signature_group_by_name(&signature_group_name, conn).await?;
+
+	if !is_archive(file) {
+		let md5_hash = calculate_md5(file).await?;
+		let outcome = import_dat_file_if_new(file, &md5_hash, signature_group.id, conn).await?;
+
+		return Ok(matches!(outcome, DatImportOutcome::Imported(_)));
+	}
+
+	let tmp_dir = env::temp_dir()
+		.join(DROP_IN_TMP_NAME)
+		.join(random_sized_string(16));
+	fs::create_dir_all(&tmp_dir).await?;
+
+	let result = async {
+		let archive = tmp_dir.join(file.file_name().unwrap_or_default());
+		fs::copy(file, &archive).await?;
+		extract_if_archived(&archive).await?;
+
+		let mut imported_any = false;
+
+		for dat in read_files_recursive(&tmp_dir).await? {
+			let md5_hash = calculate_md5(&dat).await?;
+			imported_any |= matches!(
+				import_dat_file_if_new(&dat, &md5_hash, signature_group.id, conn).await?,
+				DatImportOutcome::Imported(_)
+			);
+		}
+
+		Ok::<bool, anyhow::Error>(imported_any)
+	}
+	.await;
+
+	debug!("Removing drop-in tmp dir: {:?}", tmp_dir);
+	fs::remove_dir_all(&tmp_dir).await?;
+
+	result
+}
+
+fn signature_group_name_of_file(root: &Path, file: &Path) -> Option<String> {
+	let relative = file.strip_prefix(root).ok()?;
+	let mut components = relative.components();
+	let first = components.next()?;
+
+	// a file directly in the drop-in directory has no signature group folder
+	components.next()?;
+
+	first.as_os_str().to_str().map(ToString::to_string)
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Machine {
+	pub name: String,
+
+	pub sourcefile: Option<String>,
+
+	pub isbios: Option<String>,
+
+	pub isdevice: Option<String>,
+
+	pub ismechanical: Option<String>,
+
+	pub runnable: Option<String>,
+
+	pub cloneof: Option<String>,
+
+	pub romof: Option<String>,
+
+	pub description: Option<String>,
+
+	pub year: Option<String>,
+
+	pub manufacturer: Option<String>,
+
+	#[serde(default)]
+	pub rom: Vec<MameRom>,
+
+	#[serde(default)]
+	pub disk: Vec<Disk>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MameRom {
+	/// Software lists contain nameless roms for `loadflag="continue"` and similar entries.
+	pub name: Option<String>,
+
+	pub size: Option<String>,
+
+	pub crc: Option<String>,
+
+	pub sha1: Option<String>,
+
+	pub merge: Option<String>,
+
+	pub region: Option<String>,
+
+	pub offset: Option<String>,
+
+	pub status: Option<String>,
+
+	pub optional: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Disk {
+	pub name: String,
+
+	pub sha1: Option<String>,
+
+	pub merge: Option<String>,
+
+	pub region: Option<String>,
+
+	pub index: Option<String>,
+
+	pub writable: Option<String>,
+
+	pub status: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Software {
+	pub name: String,
+
+	pub cloneof: Option<String>,
+
+	pub supported: Option<String>,
+
+	pub description: Option<String>,
+
+	pub year: Option<String>,
+
+	pub publisher: Option<String>,
+
+	#[serde(default)]
+	pub part: Vec<Part>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Part {
+	pub name: String,
+
+	pub interface: Option<String>,
+
+	#[serde(default)]
+	pub dataarea: Vec<DataArea>,
+
+	#[serde(default)]
+	pub diskarea: Vec<DiskArea>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DataArea {
+	pub name: String,
+
+	pub size: Option<String>,
+
+	#[serde(default)]
+	pub rom: Vec<MameRom>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DiskArea {
+	pub name: String,
+
+	#[serde(default)]
+	pub disk: Vec<Disk>,
+}
@@ -0,0 +1,294 @@
+use crate::dat::mame::model::{Disk, Machine, MameRom, Software};
+use crate::dat::shared::import::ParsedDat;
+use crate::dat::shared::model::{Game, Header, RomElement, Status, UNKNOWN_DAT_VERSION};
+use crate::dat::shared::stream::DatSink;
+use crate::dat::shared::xml::XmlElementReader;
+use anyhow::anyhow;
+use std::io::BufRead;
+
+const MAME_NAME: &str = "MAME";
+const MAME_URL: &str = "https://mamedev.org/";
+const ARCADE_PLATFORM: &str = "Arcade";
+const SOFTWARE_LIST_TAG: &str = "Software List";
+const SOFTWARE_LIST_PLATFORM: &str = "MAME Software List";
+const DISK_FILE_EXTENSION: &str = "chd";
+const BADDUMP_STATUS: &str = "baddump";
+const MACHINE_ELEMENT: &str = "machine";
+const SOFTWARE_ELEMENT: &str = "software";
+const BUILD_ATTRIBUTE: &str = "build";
+const NAME_ATTRIBUTE: &str = "name";
+const DESCRIPTION_ATTRIBUTE: &str = "description";
+
+/// Software lists of cartridge systems with the company and platform names the No-Intro DATs of
+/// the same system use, so their games end up on the same platform.
+const SOFTWARE_LIST_PLATFORMS: [(&str, &str, &str); 30] = [
+	("nes", "Nintendo", "Nintendo Entertainment System"),
+	("famicom_flop", "Nintendo", "Family Computer Disk System"),
+	("snes", "Nintendo", "Super Nintendo Entertainment System"),
+	("n64", "Nintendo", "Nintendo 64"),
+	("gameboy", "Nintendo", "Game Boy"),
+	("gbcolor", "Nintendo", "Game Boy Color"),
+	("gba", "Nintendo", "Game Boy Advance"),
+	("vboy", "Nintendo", "Virtual Boy"),
+	("pokemini", "Nintendo", "Pokemon Mini"),
+	("megadriv", "Sega", "Mega Drive - Genesis"),
+	("genesis", "Sega", "Mega Drive - Genesis"),
+	("megadrij", "Sega", "Mega Drive - Genesis"),
+	("sms", "Sega", "Master System - Mark III"),
+	("gamegear", "Sega", "Game Gear"),
+	("sg1000", "Sega", "SG-1000"),
+	("32x", "Sega", "32X"),
+	("pce", "NEC", "PC Engine - TurboGrafx-16"),
+	("tg16", "NEC", "PC Engine - TurboGrafx-16"),
+	("sgx", "NEC", "PC Engine SuperGrafx"),
+	("a2600", "Atari", "2600"),
+	("a5200", "Atari", "5200"),
+	("a7800", "Atari", "7800"),
+	("lynx", "Atari", "Lynx"),
+	("jaguar", "Atari", "Jaguar"),
+	("coleco", "Coleco", "ColecoVision"),
+	("intv", "Mattel", "Intellivision"),
+	("vectrex", "GCE", "Vectrex"),
+	("ngp", "SNK", "NeoGeo Pocket"),
+	("ngpc", "SNK", "NeoGeo Pocket Color"),
+	("wswan", "Bandai", "WonderSwan"),
+];
+
+/// Streams the output of `mame -listxml`, every machine is deserialized on its own.
+pub fn stream_mame_list_xml(reader: impl BufRead, sink: &mut DatSink) -> anyhow::Result<()> {
+	let mut xml = XmlElementReader::new(reader);
+	let root = xml.read_root()?;
+
+	// builds look like "0.268 (mame0268)"
+	let version = root
+		.get(BUILD_ATTRIBUTE)
+		.and_then(|build| build.split_whitespace().next())
+		.unwrap_or(UNKNOWN_DAT_VERSION)
+		.to_string();
+
+	sink.header(ParsedDat {
+		header: mame_header(MAME_NAME.to_string(), MAME_NAME.to_string(), version),
+		company: None,
+		platform: ARCADE_PLATFORM.to_string(),
+		tags: Vec::new(),
+		name: Some(MAME_NAME.to_string()),
+	})?;
+
+	while let Some((name, element)) = xml.next_child()? {
+		if name != MACHINE_ELEMENT {
+			continue;
+		}
+
+		let machine: Machine = serde_xml_rs::from_reader(element.as_slice())?;
+
+		if let Some(game) = machine_to_game(machine) {
+			sink.game(game)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Streams a MAME software list, every software is deserialized on its own.
+pub fn stream_mame_software_list(reader: impl BufRead, sink: &mut DatSink) -> anyhow::Result<()> {
+	let mut xml = XmlElementReader::new(reader);
+	let mut root = xml.read_root()?;
+
+	let list_name = root
+		.remove(NAME_ATTRIBUTE)
+		.ok_or_else(|| anyhow!("Software list has no name"))?;
+	let description = root
+		.remove(DESCRIPTION_ATTRIBUTE)
+		.unwrap_or(list_name.clone());
+	let (company, platform) = software_list_platform(&list_name);
+
+	sink.header(ParsedDat {
+		header: mame_header(
+			list_name.clone(),
+			description,
+			UNKNOWN_DAT_VERSION.to_string(),
+		),
+		company,
+		platform,
+		tags: vec![SOFTWARE_LIST_TAG.to_string(), list_name.clone()],
+		name: Some(list_name),
+	})?;
+
+	while let Some((name, element)) = xml.next_child()? {
+		if name != SOFTWARE_ELEMENT {
+			continue;
+		}
+
+		let software: Software = serde_xml_rs::from_reader(element.as_slice())?;
+
+		if let Some(game) = software_to_game(software) {
+			sink.game(game)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// The description of a list (e.g. "Nintendo Game Boy cartridges") doesn't line up with the
+/// platforms of other DATs, lists we don't know share one platform and keep their name as a tag.
+fn software_list_platform(list_name: &str) -> (Option<String>, String) {
+	SOFTWARE_LIST_PLATFORMS
+		.iter()
+		.find(|(name, _, _)| *name == list_name)
+		.map(|(_, company, platform)| (Some(company.to_string()), platform.to_string()))
+		.unwrap_or((None, SOFTWARE_LIST_PLATFORM.to_string()))
+}
+
+fn mame_header(name: String, description: String, version: String) -> Header {
+	Header {
+		id: None,
+		name,
+		description,
+		version,
+		subset: None,
+		author: None,
+		category: Some(MAME_NAME.to_string()),
+		homepage: Some(MAME_NAME.to_string()),
+		url: Some(MAME_URL.to_string()),
+		clrmamepro: None,
+	}
+}
+
+/// Converts a machine into a game, roms and disks with a `merge` attribute are skipped as they belong
+/// to the parent (or BIOS) machine, which gives us a split set without duplicate hashes.
+fn machine_to_game(machine: Machine) -> Option<Game> {
+	let mut rom = machine
+		.rom
+		.into_iter()
+		.filter(|rom| rom.merge.is_none())
+		.filter_map(mame_rom_to_rom_element)
+		.collect::<Vec<RomElement>>();
+
+	rom.extend(
+		machine
+			.disk
+			.into_iter()
+			.filter(|disk| disk.merge.is_none())
+			.filter_map(disk_to_rom_element),
+	);
+
+	if rom.is_empty() {
+		return None;
+	}
+
+	Some(Game {
+		description: machine.description,
+		game_id: None,
+		rom,
+		name: machine.name.clone(),
+		id: Some(machine.name),
+		cloneofid: machine.cloneof,
+		romof: machine.romof,
+		isbios: machine.isbios,
+		category: None,
+	})
+}
+
+fn software_to_game(software: Software) -> Option<Game> {
+	let mut rom = Vec::new();
+
+	for part in software.part {
+		for data_area in part.dataarea {
+			rom.extend(
+				data_area
+					.rom
+					.into_iter()
+					.filter_map(mame_rom_to_rom_element),
+			);
+		}
+
+		for disk_area in part.diskarea {
+			rom.extend(disk_area.disk.into_iter().filter_map(disk_to_rom_element));
+		}
+	}
+
+	if rom.is_empty() {
+		return None;
+	}
+
+	Some(Game {
+		description: software.description,
+		game_id: None,
+		rom,
+		name: software.name.clone(),
+		id: Some(software.name),
+		cloneofid: software.cloneof,
+		romof: None,
+		isbios: None,
+		category: None,
+	})
+}
+
+fn mame_rom_to_rom_element(rom: MameRom) -> Option<RomElement> {
+	let name = rom.name?;
+
+	// roms with status "nodump" have no hashes we could ever match against
+	if rom.crc.is_none() && rom.sha1.is_none() {
+		return None;
+	}
+
+	Some(RomElement {
+		name,
+		size: rom.size,
+		crc: rom.crc,
+		md5: None,
+		sha1: rom.sha1,
+		sha256: None,
+		serial: None,
+		status: mame_status(rom.status.as_deref()),
+		mia: None,
+	})
+}
+
+fn disk_to_rom_element(disk: Disk) -> Option<RomElement> {
+	let sha1 = disk.sha1?;
+
+	Some(RomElement {
+		name: format!("{}.{}", disk.name, DISK_FILE_EXTENSION),
+		size: None,
+		crc: None,
+		md5: None,
+		sha1: Some(sha1),
+		sha256: None,
+		serial: None,
+		status: mame_status(disk.status.as_deref()),
+		mia: None,
+	})
+}
+
+fn mame_status(status: Option<&str>) -> Option<Status> {
+	match status {
+		Some(BADDUMP_STATUS) => Some(Status::Baddump),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn software_lists_use_the_platform_of_other_dats() {
+		assert_eq!(
+			software_list_platform("gameboy"),
+			(Some("Nintendo".to_string()), "Game Boy".to_string())
+		);
+		assert_eq!(
+			software_list_platform("megadriv"),
+			software_list_platform("genesis")
+		);
+	}
+
+	#[test]
+	fn unknown_software_lists_share_one_platform() {
+		assert_eq!(
+			software_list_platform("amiga_flop"),
+			(None, SOFTWARE_LIST_PLATFORM.to_string())
+		);
+	}
+}
@@ -1,41 +1,115 @@
 use crate::constants::PARALLELISM;
-use crate::dat::no_intro::download::download_no_intro_dats;
-use crate::dat::redump::download::download_redump_dats;
-use crate::dat::shared::import::parse_and_import_dat_file;
+use crate::dat::shared::download::delete_old_and_move_new_files;
+use crate::dat::shared::import::{parse_and_import_dat_file, ImportedDat};
+use crate::dat::source::state::{read_dat_source_state, write_dat_source_state, DatSourceState};
+use crate::dat::source::{load_dat_sources, DatSource, DatSourceFetch};
 use crate::db::dat_file_import::is_dat_already_in_history;
-use crate::db::signature_group::find_signature_group_by_name;
+use crate::db::signature_group::create_or_find_signature_group_by_name;
 use crate::fs;
-use crate::fs::calculate_md5;
+use crate::hash::calculate_md5;
 use crate::r#match::clone::populate_all_clone_of_ids;
-use anyhow::anyhow;
+use anyhow::bail;
+use chrono::Utc;
 use fs::read_files_recursive;
+use lazy_static::lazy_static;
 use log::{debug, error, info};
 use reqwest::Client;
+use sea_orm::prelude::Uuid;
 use sea_orm::DbConn;
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
 
-mod no_intro;
-mod redump;
+pub mod drop_in;
+mod mame;
 pub mod shared;
+pub mod source;
+mod tosec;
+pub mod upload;
+
+lazy_static! {
+	/// Imports of the daily download and the drop-in folder must not run at the same time.
+	pub(crate) static ref DAT_IMPORT_LOCK: Mutex<()> = Mutex::new(());
+}
 
 const DATS_PATH: &str = "dats";
 const TMP_PATH: &str = "tmp";
+/// Source names can not start with a dot, so this never collides with a source folder.
+const STATE_PATH: &str = ".state";
+/// Interrupted downloads are kept in `dats/.state/partial` to resume them on the next run.
+const PARTIAL_PATH: &str = "partial";
 
 pub async fn download_and_parse_dats(client: &Client, conn: &DbConn) -> anyhow::Result<()> {
 	let current_dir = std::env::current_dir()?;
-	let tmp_dir = current_dir.join(DATS_PATH).join(TMP_PATH);
+	let dat_dir = current_dir.join(DATS_PATH);
+	let tmp_dir = dat_dir.join(TMP_PATH);
+	let state_dir = dat_dir.join(STATE_PATH);
 	tokio::fs::create_dir_all(&tmp_dir).await?;
+	tokio::fs::create_dir_all(&state_dir).await?;
+
+	let sources = load_dat_sources()?;
+	let mut states = Vec::with_capacity(sources.len());
+	let mut files = Vec::new();
 
-	info!("Starting to download No-Intro DATs.");
-	download_no_intro_dats(client).await?;
-	info!("Successfully downloaded No-Intro DATs");
-	info!("Starting to download Redump DATs.");
-	download_redump_dats(client).await?;
-	info!("Successfully downloaded Redump DATs");
+	for (source_index, source) in sources.iter().enumerate() {
+		let source_dir = dat_dir.join(source.name());
+		let source_dir_exists = tokio::fs::try_exists(&source_dir).await.unwrap_or(false);
+
+		// the state is only valid as long as the DATs it belongs to are still there
+		let previous_state = if source_dir_exists {
+			read_dat_source_state(&state_dir, source.name())
+				.await
+				.filter(|state| state.location == source.location())
+		} else {
+			None
+		};
+
+		info!("Starting to fetch {} DATs.", source.name());
+		let state = match fetch_dat_source(
+			source.as_ref(),
+			client,
+			&dat_dir,
+			previous_state.as_ref(),
+		)
+		.await
+		{
+			Ok(state) => {
+				info!("Successfully fetched {} DATs", source.name());
+				Some(state)
+			}
+			Err(e) => {
+				error!("Failed to fetch {} DATs: {:?}", source.name(), e);
+				previous_state
+			}
+		};
+
+		// sources can bring their own signature groups, so we create them if needed
+		let signature_group_id =
+			create_or_find_signature_group_by_name(source.signature_group(), conn)
+				.await?
+				.id;
+
+		let already_imported = state.as_ref().is_some_and(|state| state.imported);
+		states.push(state);
+
+		if already_imported {
+			info!(
+				"{} DATs did not change since the last import, skipping them",
+				source.name()
+			);
+			continue;
+		}
+
+		if tokio::fs::try_exists(&source_dir).await.unwrap_or(false) {
+			for file in read_files_recursive(&source_dir).await? {
+				files.push((file, signature_group_id, source_index));
+			}
+		}
+	}
 
 	tokio::fs::remove_dir_all(&tmp_dir).await?;
 
-	let files = read_files_recursive(&PathBuf::from(DATS_PATH)).await?;
+	let _lock = DAT_IMPORT_LOCK.lock().await;
 
 	let mut file_hashes = Vec::with_capacity(files.len());
 
@@ -43,103 +117,180 @@ pub async fn download_and_parse_dats(client: &Client, conn: &DbConn) -> anyhow::
 	for file_chunk in files.chunks(*PARALLELISM) {
 		let mut futures = vec![];
 
-		for file in file_chunk {
+		for (file, signature_group_id, source_index) in file_chunk {
 			let file = file.to_owned();
+			let signature_group_id = *signature_group_id;
+			let source_index = *source_index;
 			futures.push(tokio::spawn(async move {
 				let md5_hash = calculate_md5(&file).await?;
 
 				debug!("Calculated MD5 hash for file: {:?}", file);
 
-				Ok::<(String, PathBuf), anyhow::Error>((md5_hash, file))
+				Ok::<(String, PathBuf, Uuid, usize), anyhow::Error>((
+					md5_hash,
+					file,
+					signature_group_id,
+					source_index,
+				))
 			}));
 		}
 
 		for future in futures {
-			let output = future.await??;
-
-			file_hashes.push((output.0, output.1));
+			file_hashes.push(future.await??);
 		}
 	}
 	info!("Finished calculating MD5 hashes for DAT files");
 
-	for (hash, file) in file_hashes {
-		let file_name = file
-			.file_name()
-			.unwrap_or_default()
-			.to_str()
-			.unwrap_or_default();
-
-		let extension = file
-			.extension()
-			.unwrap_or_default()
-			.to_str()
-			.unwrap_or_default();
-
-		if extension != "dat" || file_name.contains("BIOS") {
-			debug!(
-				"Skipping file: {:?}, either has no .dat file extension or contains BIOS",
-				file_name
-			);
-			continue;
-		}
+	let mut failed_sources = HashSet::new();
 
-		let already_imported = is_dat_already_in_history(&hash, conn).await?;
+	for (hash, file, signature_group_id, source_index) in file_hashes {
+		if matches!(
+			import_dat_file_if_new(&file, &hash, signature_group_id, conn).await?,
+			DatImportOutcome::Failed(_)
+		) {
+			failed_sources.insert(source_index);
+		}
+	}
+	info!("Finished importing all DAT files");
 
-		if already_imported {
-			debug!("Dat file already imported: {:?}", file);
-			continue;
+	// sources with failed imports are imported again on the next run, even if nothing changed
+	for (source_index, (source, state)) in sources.iter().zip(states).enumerate() {
+		if let Some(mut state) = state {
+			if !state.imported && !failed_sources.contains(&source_index) {
+				state.imported = true;
+				write_dat_source_state(&state_dir, source.name(), &state).await?;
+			}
 		}
+	}
 
-		let path_canonical = file.canonicalize()?;
+	populate_all_clone_of_ids(conn).await?;
+	info!("Finished populating all clone_of relationships");
 
-		let parent = path_canonical.to_str().unwrap_or_default();
+	Ok(())
+}
 
-		let mut signature_group = None;
+/// Extensions of the files which are imported as DATs, other files are skipped.
+pub(crate) const DAT_FILE_EXTENSIONS: [&str; 2] = ["dat", "xml"];
 
-		if parent.contains("no-intro") {
-			signature_group = Some("No-Intro");
-		}
+/// What happened to a file passed to [`import_dat_file_if_new`].
+#[derive(Debug)]
+pub(crate) enum DatImportOutcome {
+	/// The DAT was imported.
+	Imported(ImportedDat),
 
-		if parent.contains("redump") {
-			signature_group = Some("Redump");
-		}
+	/// The file has no DAT extension or the exact same file was imported before.
+	Skipped,
 
-		if parent.contains("tosec") {
-			signature_group = Some("TOSEC");
-		}
+	/// The import failed, the failure is recorded and the DAT retried on the next run.
+	Failed(anyhow::Error),
+}
 
-		if parent.contains("mame") {
-			signature_group = Some("MAME");
-		}
+/// Imports a DAT file unless it has no DAT extension or the exact same file was imported before.
+pub(crate) async fn import_dat_file_if_new(
+	file: &Path,
+	md5_hash: &str,
+	signature_group_id: Uuid,
+	conn: &DbConn,
+) -> anyhow::Result<DatImportOutcome> {
+	let file_name = file
+		.file_name()
+		.unwrap_or_default()
+		.to_str()
+		.unwrap_or_default();
 
-		let signature_group_entity = match signature_group {
-			None => {
-				return Err(anyhow!("Signature Group not found"));
-			}
-			Some(signature_group_name) => {
-				match find_signature_group_by_name(signature_group_name, conn).await? {
-					Some(sg) => sg,
-					None => {
-						return Err(anyhow!(
-							"Signature Group not found in database (are all migrations applied?)"
-						));
-					}
-				}
-			}
-		};
+	let extension = file
+		.extension()
+		.unwrap_or_default()
+		.to_str()
+		.unwrap_or_default();
 
-		debug!("Importing DAT file: {:?}", file);
-		if let Err(e) =
-			parse_and_import_dat_file(&file, signature_group_entity.id, &hash, conn).await
-		{
+	if !DAT_FILE_EXTENSIONS.contains(&extension) {
+		debug!(
+			"Skipping file: {:?}, has no .dat or .xml file extension",
+			file_name
+		);
+		return Ok(DatImportOutcome::Skipped);
+	}
+
+	let already_imported = is_dat_already_in_history(md5_hash, conn).await?;
+
+	if already_imported {
+		debug!("Dat file already imported: {:?}", file);
+		return Ok(DatImportOutcome::Skipped);
+	}
+
+	debug!("Importing DAT file: {:?}", file);
+	match parse_and_import_dat_file(file, signature_group_id, md5_hash, conn).await {
+		Ok(imported) => {
+			info!("Imported DAT file: {}", file.display());
+			Ok(DatImportOutcome::Imported(imported))
+		}
+		Err(e) => {
 			error!("Failed to parse and import dat file: {:?}, {}", file, e);
+			Ok(DatImportOutcome::Failed(e))
 		}
-		info!("Imported DAT file: {}", file.display());
 	}
-	info!("Finished importing all DAT files");
+}
 
-	populate_all_clone_of_ids(conn).await?;
-	info!("Finished populating all clone_of relationships");
+/// Fetches a source into a temporary folder first, so the DATs on disk are only replaced once
+/// fetching was successful. Returns the new state of the source, which has been written already.
+async fn fetch_dat_source(
+	source: &dyn DatSource,
+	client: &Client,
+	dat_dir: &Path,
+	previous_state: Option<&DatSourceState>,
+) -> anyhow::Result<DatSourceState> {
+	let source_tmp_dir = dat_dir.join(TMP_PATH).join(source.name());
+	let source_dir = dat_dir.join(source.name());
+	let state_dir = dat_dir.join(STATE_PATH);
+	let partial_dir = state_dir.join(PARTIAL_PATH);
+	let partial_path = partial_dir.join(format!("{}.part", source.name()));
 
-	Ok(())
+	if tokio::fs::try_exists(&source_tmp_dir)
+		.await
+		.unwrap_or(false)
+	{
+		tokio::fs::remove_dir_all(&source_tmp_dir).await?;
+	}
+	tokio::fs::create_dir_all(&source_tmp_dir).await?;
+	tokio::fs::create_dir_all(&partial_dir).await?;
+
+	let fetch = source
+		.fetch(
+			client,
+			&source_tmp_dir,
+			&partial_path,
+			previous_state.map(|state| &state.validators),
+		)
+		.await?;
+	let now = Utc::now();
+
+	let state = match (fetch, previous_state) {
+		(DatSourceFetch::Unchanged, Some(previous_state)) => DatSourceState {
+			checked_at: now,
+			..previous_state.clone()
+		},
+		(DatSourceFetch::Unchanged, None) => {
+			bail!(
+				"{} reported unchanged DATs without a previous fetch",
+				source.name()
+			)
+		}
+		(DatSourceFetch::Fetched(validators), _) => {
+			delete_old_and_move_new_files(&source_dir, &source_tmp_dir, source.keep_subfolders())
+				.await?;
+
+			DatSourceState {
+				location: source.location(),
+				validators,
+				fetched_at: now,
+				checked_at: now,
+				imported: false,
+			}
+		}
+	};
+
+	write_dat_source_state(&state_dir, source.name(), &state).await?;
+
+	Ok(state)
 }
@@ -0,0 +1,37 @@
+use crate::archive::{extract_archive_to_directory, ArchiveKind};
+use anyhow::anyhow;
+use log::debug;
+use std::path::Path;
+use tokio::{fs, task};
+
+/// Extracts an archive of DATs next to itself and removes the archive afterward, other files are
+/// left untouched. Single file archives (gzip) are extracted into the same folder, all others into
+/// a folder named after the archive.
+pub async fn extract_if_archived(path: &Path) -> anyhow::Result<()> {
+	let Some(kind) = ArchiveKind::from_path(path) else {
+		return Ok(());
+	};
+
+	let parent = path
+		.parent()
+		.ok_or_else(|| anyhow!("Archive has no parent directory: {}", path.display()))?;
+
+	let out = if kind.is_single_file() {
+		parent.to_path_buf()
+	} else {
+		let name = kind
+			.strip_extension(path)
+			.ok_or_else(|| anyhow!("Invalid archive name: {}", path.display()))?;
+		parent.join(name)
+	};
+
+	debug!("Found {:?} archive, extracting DAT(s) to: {:?}", kind, &out);
+	let path_owned = path.to_owned();
+	task::spawn_blocking(move || extract_archive_to_directory(&path_owned, &out)).await??;
+
+	debug!("Removing archive: {:?}", path);
+	fs::remove_file(path).await?;
+	debug!("Removed archive");
+
+	Ok(())
+}
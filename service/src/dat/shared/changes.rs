@@ -0,0 +1,227 @@
+use crate::db::dat_file_import_change::DatFileImportChangeInput;
+use crate::db::game_file::get_game_files_from_game_ids;
+use entity::game_file;
+use entity::sea_orm_active_enums::DatFileImportChangeTypeEnum;
+use sea_orm::prelude::Uuid;
+use sea_orm::ConnectionTrait;
+use std::collections::{HashMap, HashSet};
+
+const FILE_LOOKUP_CHUNK_SIZE: usize = 1000;
+
+/// Size, CRC and SHA1 of all files of a game, used to detect renamed games.
+type FileSignature = Vec<(Option<i64>, Option<String>, Option<String>)>;
+
+pub fn game_added_change((id, name): &(Uuid, String)) -> DatFileImportChangeInput {
+	game_change(DatFileImportChangeTypeEnum::GameAdded, *id, name.clone())
+}
+
+pub fn game_removed_change((id, name): &(Uuid, String)) -> DatFileImportChangeInput {
+	game_change(DatFileImportChangeTypeEnum::GameRemoved, *id, name.clone())
+}
+
+/// Compares the removed and added files of a game, a file which got removed and added again under
+/// the same name changed its hashes.
+pub fn file_changes(
+	(game_id, game_name): &(Uuid, String),
+	removed_files: &[&game_file::Model],
+	added_files: &[&game_file::Model],
+) -> Vec<DatFileImportChangeInput> {
+	let added_by_name = added_files
+		.iter()
+		.map(|file| (file.file_name.as_str(), *file))
+		.collect::<HashMap<&str, &game_file::Model>>();
+	let removed_names = removed_files
+		.iter()
+		.map(|file| file.file_name.as_str())
+		.collect::<HashSet<&str>>();
+
+	let mut changes = vec![];
+
+	for file in removed_files {
+		let change = match added_by_name.get(file.file_name.as_str()) {
+			Some(added) => DatFileImportChangeInput {
+				file_name: Some(file.file_name.clone()),
+				previous_crc: file.crc.clone(),
+				crc: added.crc.clone(),
+				previous_sha1: file.sha1.clone(),
+				sha1: added.sha1.clone(),
+				..game_change(
+					DatFileImportChangeTypeEnum::FileChanged,
+					*game_id,
+					game_name.clone(),
+				)
+			},
+			None => DatFileImportChangeInput {
+				file_name: Some(file.file_name.clone()),
+				previous_crc: file.crc.clone(),
+				previous_sha1: file.sha1.clone(),
+				..game_change(
+					DatFileImportChangeTypeEnum::FileRemoved,
+					*game_id,
+					game_name.clone(),
+				)
+			},
+		};
+
+		changes.push(change);
+	}
+
+	for file in added_files
+		.iter()
+		.filter(|file| !removed_names.contains(file.file_name.as_str()))
+	{
+		changes.push(DatFileImportChangeInput {
+			file_name: Some(file.file_name.clone()),
+			crc: file.crc.clone(),
+			sha1: file.sha1.clone(),
+			..game_change(
+				DatFileImportChangeTypeEnum::FileAdded,
+				*game_id,
+				game_name.clone(),
+			)
+		});
+	}
+
+	changes
+}
+
+/// Replaces a removed and an added game with a rename if both contain exactly the same files.
+/// Only file sets which are unique on both sides are considered, so we never guess.
+pub async fn detect_renamed_games(
+	changes: Vec<DatFileImportChangeInput>,
+	conn: &impl ConnectionTrait,
+) -> anyhow::Result<Vec<DatFileImportChangeInput>> {
+	let added_ids = game_ids_of_change_type(&changes, DatFileImportChangeTypeEnum::GameAdded);
+	let removed_ids = game_ids_of_change_type(&changes, DatFileImportChangeTypeEnum::GameRemoved);
+
+	if added_ids.is_empty() || removed_ids.is_empty() {
+		return Ok(changes);
+	}
+
+	let mut signatures: HashMap<Uuid, FileSignature> = HashMap::new();
+
+	let all_ids = added_ids
+		.iter()
+		.chain(removed_ids.iter())
+		.copied()
+		.collect::<Vec<Uuid>>();
+
+	// Postgres limits the amount of bind parameters, so we chunk the lookups
+	for chunk in all_ids.chunks(FILE_LOOKUP_CHUNK_SIZE) {
+		for file in get_game_files_from_game_ids(chunk.to_vec(), conn).await? {
+			signatures.entry(file.game_id).or_default().push((
+				file.file_size_in_bytes,
+				file.crc.map(|crc| crc.to_lowercase()),
+				file.sha1.map(|sha1| sha1.to_lowercase()),
+			));
+		}
+	}
+
+	for signature in signatures.values_mut() {
+		signature.sort();
+	}
+
+	let added_by_signature = unique_signatures(&added_ids, &signatures);
+	let removed_by_signature = unique_signatures(&removed_ids, &signatures);
+
+	// added game id -> removed game id
+	let renamed = added_by_signature
+		.iter()
+		.filter_map(|(signature, added_id)| {
+			removed_by_signature
+				.get(signature)
+				.map(|removed_id| (*added_id, *removed_id))
+		})
+		.collect::<HashMap<Uuid, Uuid>>();
+
+	if renamed.is_empty() {
+		return Ok(changes);
+	}
+
+	let renamed_removed_ids = renamed.values().copied().collect::<HashSet<Uuid>>();
+	let previous_names = changes
+		.iter()
+		.filter(|change| change.change_type == DatFileImportChangeTypeEnum::GameRemoved)
+		.filter_map(|change| {
+			change
+				.game_id
+				.filter(|id| renamed_removed_ids.contains(id))
+				.map(|id| (id, change.game_name.clone()))
+		})
+		.collect::<HashMap<Uuid, String>>();
+
+	Ok(changes
+		.into_iter()
+		.filter_map(|change| {
+			let game_id = change.game_id?;
+
+			match change.change_type {
+				DatFileImportChangeTypeEnum::GameRemoved
+					if renamed_removed_ids.contains(&game_id) =>
+				{
+					None
+				}
+				DatFileImportChangeTypeEnum::GameAdded => match renamed.get(&game_id) {
+					Some(removed_id) => Some(DatFileImportChangeInput {
+						previous_game_name: previous_names.get(removed_id).cloned(),
+						..game_change(
+							DatFileImportChangeTypeEnum::GameRenamed,
+							game_id,
+							change.game_name,
+						)
+					}),
+					None => Some(change),
+				},
+				_ => Some(change),
+			}
+		})
+		.collect())
+}
+
+fn game_change(
+	change_type: DatFileImportChangeTypeEnum,
+	game_id: Uuid,
+	game_name: String,
+) -> DatFileImportChangeInput {
+	DatFileImportChangeInput {
+		change_type,
+		game_id: Some(game_id),
+		game_name,
+		previous_game_name: None,
+		file_name: None,
+		previous_crc: None,
+		crc: None,
+		previous_sha1: None,
+		sha1: None,
+	}
+}
+
+fn game_ids_of_change_type(
+	changes: &[DatFileImportChangeInput],
+	change_type: DatFileImportChangeTypeEnum,
+) -> Vec<Uuid> {
+	changes
+		.iter()
+		.filter(|change| change.change_type == change_type)
+		.filter_map(|change| change.game_id)
+		.collect()
+}
+
+fn unique_signatures<'a>(
+	game_ids: &[Uuid],
+	signatures: &'a HashMap<Uuid, FileSignature>,
+) -> HashMap<&'a FileSignature, Uuid> {
+	let mut counts: HashMap<&FileSignature, usize> = HashMap::new();
+	let mut by_signature = HashMap::new();
+
+	for game_id in game_ids {
+		if let Some(signature) = signatures.get(game_id).filter(|s| !s.is_empty()) {
+			*counts.entry(signature).or_default() += 1;
+			by_signature.insert(signature, *game_id);
+		}
+	}
+
+	by_signature.retain(|signature, _| counts.get(signature) == Some(&1));
+
+	by_signature
+}
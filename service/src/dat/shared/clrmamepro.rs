@@ -0,0 +1,484 @@
+use crate::dat::shared::import::parse_datafile_header;
+use crate::dat::shared::model::{
+	ClrMameProHeader, Game, Header, RomElement, Status, UNKNOWN_DAT_VERSION,
+};
+use crate::dat::shared::stream::DatSink;
+use anyhow::{anyhow, bail};
+use std::io::BufRead;
+
+const HEADER_BLOCK: &str = "clrmamepro";
+const GAME_BLOCKS: [&str; 2] = ["game", "machine"];
+const RESOURCE_BLOCK: &str = "resource";
+const DISK_FILE_EXTENSION: &str = "chd";
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+#[derive(Debug)]
+enum Token {
+	Open,
+	Close,
+	Word(String),
+}
+
+#[derive(Debug)]
+enum Value {
+	Text(String),
+	Block(Vec<(String, Value)>),
+}
+
+/// A minimal tokenizer for the ClrMamePro text format, words are either quoted or separated by
+/// whitespace and parentheses.
+struct Tokenizer<R: BufRead> {
+	reader: R,
+	position: usize,
+}
+
+impl<R: BufRead> Tokenizer<R> {
+	fn new(mut reader: R) -> Self {
+		let mut position = 0;
+
+		// skip the UTF-8 BOM if there is one, read errors show up again with the first token
+		if let Ok(true) = reader.fill_buf().map(|buf| buf.starts_with(UTF8_BOM)) {
+			reader.consume(UTF8_BOM.len());
+			position = UTF8_BOM.len();
+		}
+
+		Self { reader, position }
+	}
+
+	fn peek(&mut self) -> anyhow::Result<Option<u8>> {
+		Ok(self.reader.fill_buf()?.first().copied())
+	}
+
+	fn advance(&mut self) {
+		self.reader.consume(1);
+		self.position += 1;
+	}
+
+	fn next_token(&mut self) -> anyhow::Result<Option<Token>> {
+		while let Some(byte) = self.peek()? {
+			if !byte.is_ascii_whitespace() {
+				break;
+			}
+
+			self.advance();
+		}
+
+		let Some(byte) = self.peek()? else {
+			return Ok(None);
+		};
+
+		match byte {
+			b'(' => {
+				self.advance();
+				Ok(Some(Token::Open))
+			}
+			b')' => {
+				self.advance();
+				Ok(Some(Token::Close))
+			}
+			b'"' => {
+				let start = self.position;
+				self.advance();
+
+				let mut word = Vec::new();
+
+				loop {
+					match self.peek()? {
+						Some(b'"') => break,
+						Some(byte) => {
+							word.push(byte);
+							self.advance();
+						}
+						None => bail!("Unterminated string at byte {}", start),
+					}
+				}
+
+				self.advance();
+
+				Ok(Some(Token::Word(
+					String::from_utf8_lossy(&word).to_string(),
+				)))
+			}
+			_ => {
+				let mut word = Vec::new();
+
+				while let Some(byte) = self.peek()? {
+					if byte.is_ascii_whitespace() || byte == b'(' || byte == b')' {
+						break;
+					}
+
+					word.push(byte);
+					self.advance();
+				}
+
+				Ok(Some(Token::Word(
+					String::from_utf8_lossy(&word).to_string(),
+				)))
+			}
+		}
+	}
+}
+
+/// Checks if the content starts like a ClrMamePro DAT, e.g. `clrmamepro (` or `game (`.
+pub fn is_clrmamepro_dat(content: &[u8]) -> bool {
+	let mut tokenizer = Tokenizer::new(content);
+
+	match tokenizer.next_token() {
+		Ok(Some(Token::Word(word))) => {
+			word == HEADER_BLOCK || word == RESOURCE_BLOCK || GAME_BLOCKS.contains(&word.as_str())
+		}
+		_ => false,
+	}
+}
+
+/// Streams a ClrMamePro DAT block by block, the header block has to come before any game.
+pub fn stream_clrmamepro_dat(reader: impl BufRead, sink: &mut DatSink) -> anyhow::Result<()> {
+	let mut tokenizer = Tokenizer::new(reader);
+
+	while let Some(token) = tokenizer.next_token()? {
+		let block_name = match token {
+			Token::Word(word) => word,
+			other => bail!("Expected a block name, found {:?}", other),
+		};
+
+		match tokenizer.next_token()? {
+			Some(Token::Open) => {}
+			other => bail!("Expected \"(\" after {}, found {:?}", block_name, other),
+		}
+
+		let entries = parse_block(&mut tokenizer)?;
+
+		match block_name.as_str() {
+			HEADER_BLOCK => sink.header(parse_datafile_header(entries_to_header(entries)?)?)?,
+			RESOURCE_BLOCK => sink.game(entries_to_game(entries, true)?)?,
+			name if GAME_BLOCKS.contains(&name) => sink.game(entries_to_game(entries, false)?)?,
+			// other blocks (e.g. "emulator") don't contain anything we import
+			_ => {}
+		}
+	}
+
+	Ok(())
+}
+
+/// Parses the entries of a block, the opening parenthesis has to be consumed already.
+fn parse_block<R: BufRead>(tokenizer: &mut Tokenizer<R>) -> anyhow::Result<Vec<(String, Value)>> {
+	let mut entries = Vec::new();
+
+	loop {
+		let key = match tokenizer.next_token()? {
+			Some(Token::Close) => return Ok(entries),
+			Some(Token::Word(key)) => key,
+			Some(Token::Open) => bail!("Expected a key, found \"(\""),
+			None => bail!("Unexpected end of file, missing \")\""),
+		};
+
+		let value = match tokenizer.next_token()? {
+			Some(Token::Open) => Value::Block(parse_block(tokenizer)?),
+			Some(Token::Word(value)) => Value::Text(value),
+			Some(Token::Close) | None => bail!("Missing value for key {}", key),
+		};
+
+		entries.push((key, value));
+	}
+}
+
+fn entries_to_header(entries: Vec<(String, Value)>) -> anyhow::Result<Header> {
+	let mut header = Header {
+		id: None,
+		name: String::new(),
+		description: String::new(),
+		version: UNKNOWN_DAT_VERSION.to_string(),
+		subset: None,
+		author: None,
+		category: None,
+		homepage: None,
+		url: None,
+		clrmamepro: None,
+	};
+
+	for (key, value) in entries {
+		let Value::Text(value) = value else {
+			continue;
+		};
+
+		match key.as_str() {
+			"name" => header.name = value,
+			"description" => header.description = value,
+			"version" => header.version = value,
+			"author" => header.author = Some(value),
+			"category" => header.category = Some(value),
+			"homepage" => header.homepage = Some(value),
+			"url" => header.url = Some(value),
+			"header" => {
+				header.clrmamepro = Some(ClrMameProHeader {
+					header: Some(value),
+				})
+			}
+			_ => {}
+		}
+	}
+
+	if header.name.is_empty() {
+		bail!("clrmamepro header has no name");
+	}
+
+	if header.description.is_empty() {
+		header.description = header.name.clone();
+	}
+
+	Ok(header)
+}
+
+fn entries_to_game(entries: Vec<(String, Value)>, is_resource: bool) -> anyhow::Result<Game> {
+	let mut name = None;
+	let mut description = None;
+	let mut cloneof = None;
+	let mut romof = None;
+	let mut serial = None;
+	let mut category = None;
+	let mut roms = Vec::new();
+
+	for (key, value) in entries {
+		match (key.as_str(), value) {
+			("name", Value::Text(value)) => name = Some(value),
+			("description", Value::Text(value)) => description = Some(value),
+			("cloneof", Value::Text(value)) => cloneof = Some(value),
+			("romof", Value::Text(value)) => romof = Some(value),
+			("serial", Value::Text(value)) => serial = Some(value),
+			("category", Value::Text(value)) => category = Some(vec![value]),
+			("rom", Value::Block(rom)) => roms.push(entries_to_rom_element(rom, false)?),
+			("disk", Value::Block(disk)) => roms.push(entries_to_rom_element(disk, true)?),
+			_ => {}
+		}
+	}
+
+	let name = name.ok_or_else(|| anyhow!("Game without a name found"))?;
+
+	// libretro DATs put the serial on the game instead of the rom
+	if serial.is_some() {
+		for rom in roms.iter_mut().filter(|rom| rom.serial.is_none()) {
+			rom.serial.clone_from(&serial);
+		}
+	}
+
+	Ok(Game {
+		description,
+		game_id: None,
+		rom: roms,
+		id: Some(name.clone()),
+		name,
+		cloneofid: cloneof,
+		romof,
+		isbios: if is_resource {
+			Some("yes".to_string())
+		} else {
+			None
+		},
+		category,
+	})
+}
+
+fn entries_to_rom_element(
+	entries: Vec<(String, Value)>,
+	is_disk: bool,
+) -> anyhow::Result<RomElement> {
+	let mut rom = RomElement {
+		name: String::new(),
+		size: None,
+		crc: None,
+		md5: None,
+		sha1: None,
+		sha256: None,
+		serial: None,
+		status: None,
+		mia: None,
+	};
+
+	for (key, value) in entries {
+		let Value::Text(value) = value else {
+			continue;
+		};
+
+		match key.as_str() {
+			"name" => rom.name = value,
+			"size" => rom.size = Some(value),
+			"crc" => rom.crc = Some(value),
+			"md5" => rom.md5 = Some(value),
+			"sha1" => rom.sha1 = Some(value),
+			"sha256" => rom.sha256 = Some(value),
+			"serial" => rom.serial = Some(value),
+			"flags" | "status" => {
+				rom.status = match value.as_str() {
+					"baddump" => Some(Status::Baddump),
+					"verified" => Some(Status::Verified),
+					_ => None,
+				}
+			}
+			_ => {}
+		}
+	}
+
+	if rom.name.is_empty() {
+		bail!("Rom without a name found");
+	}
+
+	if is_disk {
+		rom.name = format!("{}.{}", rom.name, DISK_FILE_EXTENSION);
+	}
+
+	Ok(rom)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Parses the first block of the content like [`stream_clrmamepro_dat`] does.
+	fn parse_first_block(content: &str) -> (String, Vec<(String, Value)>) {
+		let mut tokenizer = Tokenizer::new(content.as_bytes());
+
+		let Some(Token::Word(block_name)) = tokenizer.next_token().unwrap() else {
+			panic!("expected a block name");
+		};
+		assert!(matches!(tokenizer.next_token().unwrap(), Some(Token::Open)));
+
+		(block_name, parse_block(&mut tokenizer).unwrap())
+	}
+
+	fn parse_game(content: &str) -> Game {
+		let (block_name, entries) = parse_first_block(content);
+
+		entries_to_game(entries, block_name == RESOURCE_BLOCK).unwrap()
+	}
+
+	#[test]
+	fn tokenizes_quoted_strings_with_whitespace_and_parentheses() {
+		let mut tokenizer =
+			Tokenizer::new("\u{feff}name \"Tetris (World) (Rev 1)\"\n\tsize 1024)".as_bytes());
+		let mut words = Vec::new();
+
+		while let Some(token) = tokenizer.next_token().unwrap() {
+			words.push(match token {
+				Token::Open => "(".to_string(),
+				Token::Close => ")".to_string(),
+				Token::Word(word) => word,
+			});
+		}
+
+		assert_eq!(
+			words,
+			vec!["name", "Tetris (World) (Rev 1)", "size", "1024", ")"]
+		);
+	}
+
+	#[test]
+	fn fails_on_unterminated_strings() {
+		let mut tokenizer = Tokenizer::new("name \"Tetris".as_bytes());
+
+		tokenizer.next_token().unwrap();
+		assert!(tokenizer.next_token().is_err());
+	}
+
+	#[test]
+	fn parses_games_with_nested_roms() {
+		let game = parse_game(
+			r#"game (
+				name "Tetris (World) (Rev 1)"
+				description "Tetris (World) (Rev 1)"
+				cloneof "Tetris (World)"
+				rom ( name "Tetris (World) (Rev 1).gb" size 32768 crc 46DF91AD md5 982ED5D2B12A0377EB14BCDC4123744E sha1 74591CC9501AF93873F9A5D3EB12DA12C0723BBC flags verified )
+				rom ( name "Tetris (World) (Rev 1).sav" size 8192 crc 00000000 flags baddump )
+			)"#,
+		);
+
+		assert_eq!(game.name, "Tetris (World) (Rev 1)");
+		assert_eq!(game.id.as_deref(), Some("Tetris (World) (Rev 1)"));
+		assert_eq!(game.cloneofid.as_deref(), Some("Tetris (World)"));
+		assert_eq!(game.isbios, None);
+		assert_eq!(game.rom.len(), 2);
+		assert_eq!(game.rom[0].name, "Tetris (World) (Rev 1).gb");
+		assert_eq!(game.rom[0].size.as_deref(), Some("32768"));
+		assert_eq!(game.rom[0].crc.as_deref(), Some("46DF91AD"));
+		assert_eq!(
+			game.rom[0].sha1.as_deref(),
+			Some("74591CC9501AF93873F9A5D3EB12DA12C0723BBC")
+		);
+		assert!(matches!(game.rom[0].status, Some(Status::Verified)));
+		assert!(matches!(game.rom[1].status, Some(Status::Baddump)));
+	}
+
+	#[test]
+	fn parses_resources_as_bios_and_disks_as_chd_files() {
+		let game = parse_game(
+			r#"resource (
+				name neogeo
+				romof bios
+				rom ( name sp-s2.sp1 size 131072 crc 9036d879 )
+				disk ( name "neo cd" sha1 4f5ed7105b7128794654ce82b51723e16e389543 )
+			)"#,
+		);
+
+		assert_eq!(game.name, "neogeo");
+		assert_eq!(game.romof.as_deref(), Some("bios"));
+		assert_eq!(game.isbios.as_deref(), Some("yes"));
+		assert_eq!(game.rom.len(), 2);
+		assert_eq!(game.rom[0].name, "sp-s2.sp1");
+		assert_eq!(game.rom[1].name, "neo cd.chd");
+		assert_eq!(game.rom[1].size, None);
+	}
+
+	#[test]
+	fn copies_the_game_serial_to_roms_without_one() {
+		let game = parse_game(
+			r#"game (
+				name "Crash Bandicoot (USA)"
+				serial "SCUS-94900"
+				rom ( name "Crash Bandicoot (USA).cue" size 100 crc 12345678 )
+				rom ( name "Crash Bandicoot (USA).bin" size 200 crc 87654321 serial "SCUS-94900A" )
+			)"#,
+		);
+
+		assert_eq!(game.rom[0].serial.as_deref(), Some("SCUS-94900"));
+		assert_eq!(game.rom[1].serial.as_deref(), Some("SCUS-94900A"));
+	}
+
+	#[test]
+	fn parses_the_header_block() {
+		let (block_name, entries) = parse_first_block(
+			r#"clrmamepro (
+				name "Nintendo - Nintendo Entertainment System"
+				version 20241018
+				header No-Intro_NES.xml
+			)"#,
+		);
+		let header = entries_to_header(entries).unwrap();
+
+		assert_eq!(block_name, HEADER_BLOCK);
+		assert_eq!(header.name, "Nintendo - Nintendo Entertainment System");
+		assert_eq!(header.description, header.name);
+		assert_eq!(header.version, "20241018");
+		assert_eq!(header.header_detector_name(), Some("No-Intro_NES.xml"));
+	}
+
+	#[test]
+	fn rejects_blocks_without_closing_parenthesis_or_name() {
+		let mut tokenizer = Tokenizer::new("game ( name \"Tetris\"".as_bytes());
+		tokenizer.next_token().unwrap();
+		tokenizer.next_token().unwrap();
+		assert!(parse_block(&mut tokenizer).is_err());
+
+		let (_, entries) = parse_first_block("game ( description Tetris )");
+		assert!(entries_to_game(entries, false).is_err());
+	}
+
+	#[test]
+	fn detects_clrmamepro_dats() {
+		assert!(is_clrmamepro_dat(
+			b"\xEF\xBB\xBFclrmamepro (\n\tname test\n)"
+		));
+		assert!(is_clrmamepro_dat(b"game ( name test )"));
+		assert!(is_clrmamepro_dat(b"  resource ( name test )"));
+		assert!(!is_clrmamepro_dat(b"<?xml version=\"1.0\"?><datafile/>"));
+		assert!(!is_clrmamepro_dat(b"emulator ( name test )"));
+	}
+}
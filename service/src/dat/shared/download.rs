@@ -1,20 +1,38 @@
+use crate::dat::DAT_FILE_EXTENSIONS;
 use crate::fs::{read_files_recursive, read_folders};
-use crate::http::download::{download_file, DownloadFileNameResult};
+use crate::http::download::{
+	download_file, CacheValidators, DownloadFileNameResult, DownloadResult,
+};
 use log::debug;
 use reqwest::Client;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 
-pub async fn download_dat(client: &Client, url: &str, path: &Path) -> anyhow::Result<PathBuf> {
+/// Downloads a DAT (or an archive of DATs), returns `None` if it did not change since the download
+/// the cached validators belong to.
+pub async fn download_dat(
+	client: &Client,
+	url: &str,
+	path: &Path,
+	partial_path: &Path,
+	cached: Option<&CacheValidators>,
+) -> anyhow::Result<Option<(PathBuf, CacheValidators)>> {
 	debug!("Downloading DAT from: {}", url);
 
-	let (name_source, path) = match download_file(client, url, path).await? {
-		DownloadFileNameResult::FromContentDisposition(path) => {
-			(Some("Content-Disposition Header"), path)
-		}
-		DownloadFileNameResult::FromUrl(path) => (Some("URL path"), path),
-		DownloadFileNameResult::Random(path) => (None, path),
-	};
+	let (name_source, path, validators) =
+		match download_file(client, url, path, partial_path, cached).await? {
+			DownloadResult::NotModified => {
+				debug!("DAT at {} has not changed since the last download", url);
+				return Ok(None);
+			}
+			DownloadResult::Downloaded { file, validators } => match file {
+				DownloadFileNameResult::FromContentDisposition(path) => {
+					(Some("Content-Disposition Header"), path, validators)
+				}
+				DownloadFileNameResult::FromUrl(path) => (Some("URL path"), path, validators),
+				DownloadFileNameResult::Random(path) => (None, path, validators),
+			},
+		};
 
 	let normalized_path = path.canonicalize()?;
 
@@ -25,7 +43,7 @@ pub async fn download_dat(client: &Client, url: &str, path: &Path) -> anyhow::Re
 		name_source.unwrap_or("None")
 	);
 
-	Ok(normalized_path)
+	Ok(Some((normalized_path, validators)))
 }
 
 pub async fn delete_old_and_move_new_files(
@@ -69,7 +87,7 @@ pub async fn delete_old_and_move_new_files(
 			.to_str()
 			.unwrap_or_default();
 
-		if extension == "dat" {
+		if DAT_FILE_EXTENSIONS.contains(&extension) {
 			debug!("Moving DAT file: {:?}", tmp_file);
 
 			let out = if should_keep_subfolders {
@@ -0,0 +1,140 @@
+use crate::dat::shared::clrmamepro::is_clrmamepro_dat;
+use anyhow::anyhow;
+
+/// The different DAT formats we know how to import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatFormat {
+	/// Logiqx XML as used by No-Intro, Redump and TOSEC.
+	Logiqx,
+
+	/// Output of `mame -listxml`.
+	MameListXml,
+
+	/// MAME software lists (`hash/*.xml`).
+	MameSoftwareList,
+
+	/// ClrMamePro text format, e.g. the libretro DATs.
+	ClrMamePro,
+}
+
+pub fn detect_dat_format(content: &[u8]) -> anyhow::Result<DatFormat> {
+	if is_clrmamepro_dat(content) {
+		return Ok(DatFormat::ClrMamePro);
+	}
+
+	match xml_root_element_name(content).as_deref() {
+		Some("datafile") => Ok(DatFormat::Logiqx),
+		Some("mame") => Ok(DatFormat::MameListXml),
+		Some("softwarelist") => Ok(DatFormat::MameSoftwareList),
+		Some(other) => Err(anyhow!("Unsupported DAT root element: {}", other)),
+		None => Err(anyhow!("Could not detect the DAT format")),
+	}
+}
+
+/// Returns the name of the first element, skipping the xml declaration, comments and the doctype
+/// (including its internal subset, which MAME uses heavily).
+fn xml_root_element_name(content: &[u8]) -> Option<String> {
+	let mut rest = content;
+
+	loop {
+		let start = rest.iter().position(|b| *b == b'<')?;
+		rest = &rest[start..];
+
+		if rest.starts_with(b"<?") {
+			rest = skip_past(rest, b"?>")?;
+		} else if rest.starts_with(b"<!--") {
+			rest = skip_past(rest, b"-->")?;
+		} else if rest.starts_with(b"<!") {
+			rest = skip_doctype(rest)?;
+		} else {
+			let name = rest[1..]
+				.iter()
+				.take_while(|b| !b.is_ascii_whitespace() && **b != b'>' && **b != b'/')
+				.copied()
+				.collect::<Vec<u8>>();
+
+			return String::from_utf8(name).ok();
+		}
+	}
+}
+
+fn skip_past<'a>(content: &'a [u8], needle: &[u8]) -> Option<&'a [u8]> {
+	content
+		.windows(needle.len())
+		.position(|window| window == needle)
+		.map(|position| &content[position + needle.len()..])
+}
+
+fn skip_doctype(content: &[u8]) -> Option<&[u8]> {
+	let mut depth = 0;
+
+	for (index, byte) in content.iter().enumerate() {
+		match byte {
+			b'[' => depth += 1,
+			b']' => depth -= 1,
+			b'>' if depth == 0 => return Some(&content[index + 1..]),
+			_ => {}
+		}
+	}
+
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn detects_logiqx() {
+		let content = br#"<?xml version="1.0"?>
+<!DOCTYPE datafile PUBLIC "-//Logiqx//DTD ROM Management Datafile//EN" "http://www.logiqx.com/Dats/datafile.dtd">
+<!-- exported by a DAT tool -->
+<datafile>
+	<header><name>Test</name></header>
+</datafile>"#;
+
+		assert_eq!(detect_dat_format(content).unwrap(), DatFormat::Logiqx);
+	}
+
+	#[test]
+	fn detects_clrmamepro() {
+		let content = b"clrmamepro (\n\tname \"Test\"\n)\n\ngame (\n\tname \"Tetris\"\n)";
+
+		assert_eq!(detect_dat_format(content).unwrap(), DatFormat::ClrMamePro);
+	}
+
+	#[test]
+	fn detects_mame_list_xml_with_internal_doctype_subset() {
+		let content = br#"<?xml version="1.0"?>
+<!DOCTYPE mame [
+<!ELEMENT mame (machine+)>
+	<!ATTLIST mame build CDATA #IMPLIED>
+<!ELEMENT machine (description, year?)>
+]>
+
+<mame build="0.268 (mame0268)">
+</mame>"#;
+
+		assert_eq!(detect_dat_format(content).unwrap(), DatFormat::MameListXml);
+	}
+
+	#[test]
+	fn detects_mame_software_lists() {
+		let content = br#"<?xml version="1.0"?>
+<!DOCTYPE softwarelist SYSTEM "softwarelist.dtd">
+<softwarelist name="gameboy" description="Nintendo Game Boy cartridges">
+</softwarelist>"#;
+
+		assert_eq!(
+			detect_dat_format(content).unwrap(),
+			DatFormat::MameSoftwareList
+		);
+	}
+
+	#[test]
+	fn rejects_unknown_formats() {
+		assert!(detect_dat_format(b"<?xml version=\"1.0\"?><html></html>").is_err());
+		assert!(detect_dat_format(b"just some text").is_err());
+		assert!(detect_dat_format(b"").is_err());
+	}
+}
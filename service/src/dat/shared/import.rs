@@ -1,35 +1,132 @@
-use crate::dat::shared::model::{Datafile, Game};
-use crate::dat::shared::regex::{DAT_NUMBER_REGEX, DAT_TAG_REGEX};
+use crate::dat::shared::changes::{
+	detect_renamed_games, file_changes, game_added_change, game_removed_change,
+};
+use crate::dat::shared::kind::{detect_dat_kind, detect_game_kind};
+use crate::dat::shared::model::Header;
+use crate::dat::shared::regex::{DAT_NUMBER_REGEX, DAT_TAG_REGEX, TOSEC_VERSION_REGEX};
+use crate::dat::shared::stream::{stream_dat_file, GameReceiver};
+use crate::dat::tosec::header::{is_tosec_dat, parse_tosec_company_and_platform};
 use crate::db::company::create_or_find_company_by_name;
 use crate::db::dat_file::{create_or_update_dat_file, DatFileCreateOrUpdateInput};
-use crate::db::dat_file_import::create_dat_file_import;
-use crate::db::game::{find_game_by_name_and_dat_file_id, insert_game};
-use crate::db::game_file::{get_game_files_from_game_id, insert_game_file_bulk};
+use crate::db::dat_file_import::{create_dat_file_import, has_other_dat_file_import};
+use crate::db::dat_file_import_change::{insert_dat_file_import_changes, DatFileImportChangeInput};
+use crate::db::dat_file_import_failure::create_dat_file_import_failure;
+use crate::db::game::find_game_ids_and_names_by_ids;
+use crate::db::game_staging::{
+	create_game_staging_tables, insert_game_staging_batch, merge_staged_games, GameMergeResult,
+	GameStagingBatch,
+};
 use crate::db::platform::create_or_find_platform_by_name;
-use entity::{company, dat_file_import, platform};
+use entity::{company, dat_file_import, game_file, platform};
+use log::{error, info, warn};
 use sea_orm::prelude::Uuid;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use crate::constants::PARALLELISM;
-use sea_orm::{ActiveModelTrait, DbConn, IntoActiveModel};
+use sea_orm::{ConnectionTrait, DatabaseTransaction, DbConn, TransactionTrait};
+use std::mem;
 use std::path::Path;
-use tokio::fs::File;
-use tokio::io::AsyncReadExt;
-use tokio::task;
-use tokio::task::JoinHandle;
+
+const GAME_LOOKUP_CHUNK_SIZE: usize = 1000;
+
+/// Amount of games written to the staging tables per statement.
+const STAGING_BATCH_SIZE: usize = 5000;
+const NON_PLATFORM_SEGMENTS: [&str; 3] = ["BIOS Images", "BIOS", "Firmware"];
+
+/// The result of importing a DAT file.
+#[derive(Debug)]
+pub struct ImportedDat {
+	pub import: dat_file_import::Model,
+
+	/// Amount of games in the DAT.
+	pub game_count: usize,
+
+	/// Amount of files of all games in the DAT.
+	pub file_count: usize,
+}
+
+/// The header of a DAT together with the company, platform and tags it belongs to, the games are
+/// streamed separately.
+pub struct ParsedDat {
+	pub header: Header,
+
+	pub company: Option<String>,
+
+	pub platform: String,
+
+	pub tags: Vec<String>,
+
+	/// Name of the DAT, if not set it is derived from the file name.
+	pub name: Option<String>,
+}
 
 pub async fn parse_and_import_dat_file(
 	path: &Path,
 	signature_group_id: Uuid,
 	md5_hash: &str,
 	conn: &DbConn,
-) -> anyhow::Result<()> {
-	let dat = parse_dat_file(path).await?;
+) -> anyhow::Result<ImportedDat> {
+	let result =
+		parse_and_import_dat_file_atomically(path, signature_group_id, md5_hash, conn).await;
+
+	if let Err(e) = &result {
+		let file_name = path
+			.file_name()
+			.unwrap_or_default()
+			.to_str()
+			.unwrap_or_default();
+
+		if let Err(record_error) = create_dat_file_import_failure(
+			signature_group_id,
+			file_name,
+			md5_hash,
+			&e.to_string(),
+			conn,
+		)
+		.await
+		{
+			error!(
+				"Failed to record failed import of DAT file {}: {}",
+				file_name, record_error
+			);
+		}
+	}
 
-	let (company, system, tags) = match parse_company_and_platform(&dat) {
-		Ok(value) => value,
-		Err(err) => return Err(err),
-	};
+	result
+}
+
+/// Imports the DAT in a single transaction together with its history entry, so a failed import
+/// leaves nothing behind and is retried on the next run.
+async fn parse_and_import_dat_file_atomically(
+	path: &Path,
+	signature_group_id: Uuid,
+	md5_hash: &str,
+	conn: &DbConn,
+) -> anyhow::Result<ImportedDat> {
+	let (parsed, games) = stream_dat_file(path).await?;
+
+	// dropping the transaction on error rolls it back
+	let txn = conn.begin().await?;
+	let imported = import_dat_file(path, parsed, games, signature_group_id, md5_hash, &txn).await?;
+	txn.commit().await?;
+
+	Ok(imported)
+}
+
+async fn import_dat_file(
+	path: &Path,
+	parsed: ParsedDat,
+	mut games: GameReceiver,
+	signature_group_id: Uuid,
+	md5_hash: &str,
+	conn: &DatabaseTransaction,
+) -> anyhow::Result<ImportedDat> {
+	let ParsedDat {
+		header,
+		company,
+		platform: system,
+		tags,
+		name,
+	} = parsed;
 
 	let file_name = path
 		.file_name()
@@ -43,17 +140,19 @@ pub async fn parse_and_import_dat_file(
 		.to_str()
 		.unwrap_or_default();
 
-	let sanitized_file_name =
-		sanitize_dat_string(file_name.to_string(), file_extension, &dat.header.version);
+	let sanitized_file_name = name.unwrap_or_else(|| {
+		sanitize_dat_string(file_name.to_string(), file_extension, &header.version)
+	});
 
 	let (company, platform) = insert_or_get_company_and_platform(company, &system, conn).await?;
 	let import = update_dat_file_and_insert_dat_file_import(
 		DatFileCreateOrUpdateInput {
 			signature_group_id,
 			sanitized_file_name,
-			current_version: dat.header.version.clone(),
+			current_version: header.version.clone(),
 			tags,
-			subset: dat.header.subset.clone(),
+			subset: header.subset.clone(),
+			header_detector_name: header.header_detector_name().map(ToString::to_string),
 			company_id: company.clone().map(|c| c.id),
 			platform_id: platform.id,
 		},
@@ -63,119 +162,111 @@ pub async fn parse_and_import_dat_file(
 	)
 	.await?;
 
-	if let Some(games) = dat.game {
-		let games_chunked = games
-			.chunks(*PARALLELISM)
-			.map(|x: &[Game]| x.to_vec())
-			.collect::<Vec<Vec<Game>>>();
-
-		for game_chunk in games_chunked {
-			let mut futures: Vec<JoinHandle<anyhow::Result<()>>> = vec![];
-
-			for game in game_chunk {
-				let conn = conn.clone();
-				let import = import.clone();
-				futures.push(task::spawn(async move {
-					let result =
-						find_game_by_name_and_dat_file_id(&game.name, import.dat_file_id, &conn)
-							.await?;
-
-					if let Some(existing_game) = result {
-						let existing_files =
-							get_game_files_from_game_id(existing_game.id, &conn).await?;
-						let existing_files_set: HashSet<_> = existing_files
-							.iter()
-							.map(|file| {
-								(
-									&file.file_name,
-									file.file_size_in_bytes,
-									&file.crc,
-									&file.md5,
-									&file.sha1,
-									&file.sha256,
-								)
-							})
-							.collect();
-
-						let new_files_set: HashSet<_> = game
-							.rom
-							.iter()
-							.map(|rom| {
-								(
-									&rom.name,
-									rom.size.as_ref().and_then(|s| s.parse::<i64>().ok()),
-									&rom.crc,
-									&rom.md5,
-									&rom.sha1,
-									&rom.sha256,
-								)
-							})
-							.collect();
-
-						// Delete existing files that are not in the new files
-						for file in existing_files.iter() {
-							let identifier = (
-								&file.file_name,
-								file.file_size_in_bytes,
-								&file.crc,
-								&file.md5,
-								&file.sha1,
-								&file.sha256,
-							);
-							if !new_files_set.contains(&identifier) {
-								file.clone().into_active_model().delete(&conn).await?;
-							}
-						}
-
-						let mut to_insert = vec![];
-
-						// Insert new files that are not in the existing files
-						for rom in game.rom.iter() {
-							let identifier = (
-								&rom.name,
-								rom.size.as_ref().and_then(|s| s.parse::<i64>().ok()),
-								&rom.crc,
-								&rom.md5,
-								&rom.sha1,
-								&rom.sha256,
-							);
-							if !existing_files_set.contains(&identifier) {
-								to_insert.push(rom.clone());
-							}
-						}
-
-						// When we insert too many sqlx-postgres panics, so we chunk the inserts
-						for chunk in to_insert.chunks(*PARALLELISM) {
-							insert_game_file_bulk(chunk.to_vec(), existing_game.id, &conn).await?;
-						}
-
-						return Ok(());
-					}
-
-					let game_release = insert_game(import.id, game.clone(), &conn).await?;
-
-					// When we insert too many sqlx-postgres panics, so we chunk the inserts
-					for chunk in game.rom.chunks(*PARALLELISM) {
-						insert_game_file_bulk(chunk.to_vec(), game_release.id, &conn).await?;
-					}
-
-					Ok(())
-				}));
-			}
+	let dat_kind = detect_dat_kind(file_name, &header.name);
+	let mut game_count = 0;
+	let mut file_count = 0;
+	let mut game_names = HashSet::new();
+	let mut batch = GameStagingBatch::default();
 
-			for future in futures {
-				future.await??;
-			}
+	create_game_staging_tables(conn).await?;
+
+	while let Some(game) = games.recv().await {
+		let game = game?;
+
+		if !game_names.insert(game.name.clone()) {
+			warn!(
+				"Skipping duplicate game {} in DAT file {}",
+				game.name, file_name
+			);
+			continue;
 		}
+
+		game_count += 1;
+		file_count += game.rom.len();
+
+		let kind = detect_game_kind(&game, &dat_kind);
+		batch.push(game, kind)?;
+
+		if batch.len() >= STAGING_BATCH_SIZE {
+			insert_game_staging_batch(mem::take(&mut batch), conn).await?;
+		}
+	}
+
+	insert_game_staging_batch(batch, conn).await?;
+
+	// the first import of a dat file has nothing to compare against
+	let track_changes = has_other_dat_file_import(import.dat_file_id, import.id, conn).await?;
+
+	let merge = merge_staged_games(import.dat_file_id, import.id, track_changes, conn).await?;
+
+	if !merge.retired_games.is_empty() {
+		info!(
+			"Retired {} games which are no longer part of dat file {}",
+			merge.retired_games.len(),
+			import.name
+		);
+	}
+
+	if track_changes {
+		let mut changes = merge
+			.added_games
+			.iter()
+			.map(game_added_change)
+			.collect::<Vec<DatFileImportChangeInput>>();
+
+		changes.extend(changed_file_changes(&merge, conn).await?);
+		changes.extend(merge.retired_games.iter().map(game_removed_change));
+
+		let changes = detect_renamed_games(changes, conn).await?;
+
+		insert_dat_file_import_changes(import.id, changes, conn).await?;
+	}
+
+	Ok(ImportedDat {
+		import,
+		game_count,
+		file_count,
+	})
+}
+
+/// Groups the removed and added files of games which were part of the dat file before by game.
+async fn changed_file_changes(
+	merge: &GameMergeResult,
+	conn: &impl ConnectionTrait,
+) -> anyhow::Result<Vec<DatFileImportChangeInput>> {
+	let mut files_by_game: HashMap<Uuid, (Vec<&game_file::Model>, Vec<&game_file::Model>)> =
+		HashMap::new();
+
+	for file in &merge.removed_files {
+		files_by_game.entry(file.game_id).or_default().0.push(file);
+	}
+
+	for file in &merge.added_files {
+		files_by_game.entry(file.game_id).or_default().1.push(file);
 	}
 
-	Ok(())
+	let game_ids = files_by_game.keys().copied().collect::<Vec<Uuid>>();
+	let mut changes = vec![];
+
+	// Postgres limits the amount of bind parameters, so we chunk the lookups
+	for chunk in game_ids.chunks(GAME_LOOKUP_CHUNK_SIZE) {
+		for game in find_game_ids_and_names_by_ids(chunk.to_vec(), conn).await? {
+			let (removed_files, added_files) = &files_by_game[&game.0];
+			changes.extend(file_changes(&game, removed_files, added_files));
+		}
+	}
+
+	Ok(changes)
 }
 
 fn parse_company_and_platform(
-	dat: &Datafile,
+	header: &Header,
 ) -> anyhow::Result<(Option<String>, String, Vec<String>)> {
-	let mut dat_header = dat.header.name.clone();
+	if is_tosec_dat(header) {
+		return parse_tosec_company_and_platform(header);
+	}
+
+	let mut dat_header = header.name.clone();
 
 	// remove Arcade - from the name as its not a company or system
 	dat_header = dat_header.replace("Arcade - ", "");
@@ -186,8 +277,8 @@ fn parse_company_and_platform(
 		return Err(anyhow::anyhow!("No company or system found"));
 	}
 
-	let subset = &dat.header.subset;
-	let version = &dat.header.version;
+	let subset = &header.subset;
+	let version = &header.version;
 	let mut tags = Vec::new();
 	let mut company = String::new();
 	let mut platform_parts = Vec::new();
@@ -200,6 +291,12 @@ fn parse_company_and_platform(
 			}
 		}
 
+		// e.g. "Sony - PlayStation - BIOS Images", the BIOS part is not a platform
+		if real_index > 0 && NON_PLATFORM_SEGMENTS.contains(&part) {
+			tags.push(part.to_string());
+			continue;
+		}
+
 		if real_index == 0 {
 			company = part.to_string();
 		} else {
@@ -238,6 +335,7 @@ fn parse_company_and_platform(
 
 pub fn sanitize_dat_string(mut file_name: String, file_extension: &str, version: &str) -> String {
 	file_name = file_name.replace(format!(" ({})", version).as_str(), "");
+	file_name = TOSEC_VERSION_REGEX.replace_all(&file_name, "").to_string();
 
 	for tag in DAT_NUMBER_REGEX.captures_iter(&file_name.clone()) {
 		let tag = tag.get(0).map(|x| x.as_str()).unwrap_or_default();
@@ -249,21 +347,23 @@ pub fn sanitize_dat_string(mut file_name: String, file_extension: &str, version:
 	file_name
 }
 
-pub async fn parse_dat_file(path: &Path) -> anyhow::Result<Datafile> {
-	let mut dat_file = File::open(path).await?;
-
-	let mut content = Vec::new();
-	dat_file.read_to_end(&mut content).await?;
-
-	let result: Datafile = serde_xml_rs::from_reader(content.as_slice())?;
+/// Datafiles (Logiqx and ClrMamePro) carry company and platform in their header name.
+pub fn parse_datafile_header(header: Header) -> anyhow::Result<ParsedDat> {
+	let (company, platform, tags) = parse_company_and_platform(&header)?;
 
-	Ok(result)
+	Ok(ParsedDat {
+		header,
+		company,
+		platform,
+		tags,
+		name: None,
+	})
 }
 
 pub async fn insert_or_get_company_and_platform(
 	company_name: Option<String>,
 	platform_name: &str,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> anyhow::Result<(Option<company::Model>, platform::Model)> {
 	let company = if let Some(company_name) = &company_name {
 		Some(create_or_find_company_by_name(company_name.as_str(), conn).await?)
@@ -281,7 +381,7 @@ pub async fn update_dat_file_and_insert_dat_file_import(
 	input: DatFileCreateOrUpdateInput,
 	original_file_name: &str,
 	md5_hash: &str,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> anyhow::Result<dat_file_import::Model> {
 	let current_version = input.current_version.clone();
 	let dat_file = create_or_update_dat_file(input, conn).await?;
@@ -0,0 +1,43 @@
+use crate::dat::shared::model::Game;
+use entity::sea_orm_active_enums::GameKindEnum;
+
+const BIOS: &str = "BIOS";
+const FIRMWARE: &str = "Firmware";
+const APPLICATIONS: &str = "Applications";
+const BIOS_NAME_PREFIX: &str = "[BIOS]";
+const IS_BIOS_VALUE: &str = "yes";
+
+/// Kind of all entries in a DAT, e.g. "Sony - PlayStation - BIOS Images" only contains BIOS files.
+pub fn detect_dat_kind(file_name: &str, header_name: &str) -> GameKindEnum {
+	if file_name.contains(BIOS) || header_name.contains(BIOS) {
+		GameKindEnum::Bios
+	} else if file_name.contains(FIRMWARE) || header_name.contains(FIRMWARE) {
+		GameKindEnum::Firmware
+	} else {
+		GameKindEnum::Game
+	}
+}
+
+/// Kind of a single entry, the `isbios` attribute, the category (No-Intro) and the `[BIOS]` name
+/// prefix take precedence over the kind of the DAT itself.
+pub fn detect_game_kind(game: &Game, dat_kind: &GameKindEnum) -> GameKindEnum {
+	if game.isbios.as_deref() == Some(IS_BIOS_VALUE) || game.name.starts_with(BIOS_NAME_PREFIX) {
+		return GameKindEnum::Bios;
+	}
+
+	let categories = game.category.as_deref().unwrap_or_default();
+
+	if categories.iter().any(|category| category == BIOS) {
+		return GameKindEnum::Bios;
+	}
+
+	if categories.iter().any(|category| category == FIRMWARE) {
+		return GameKindEnum::Firmware;
+	}
+
+	if categories.iter().any(|category| category == APPLICATIONS) {
+		return GameKindEnum::Application;
+	}
+
+	dat_kind.clone()
+}
@@ -0,0 +1,30 @@
+use crate::dat::shared::import::parse_datafile_header;
+use crate::dat::shared::model::{Game, Header};
+use crate::dat::shared::stream::DatSink;
+use crate::dat::shared::xml::XmlElementReader;
+use std::io::BufRead;
+
+const HEADER_ELEMENT: &str = "header";
+const GAME_ELEMENT: &str = "game";
+
+/// Streams a Logiqx XML DAT (No-Intro, Redump, TOSEC), every game is deserialized on its own.
+pub fn stream_logiqx_dat(reader: impl BufRead, sink: &mut DatSink) -> anyhow::Result<()> {
+	let mut xml = XmlElementReader::new(reader);
+	xml.read_root()?;
+
+	while let Some((name, element)) = xml.next_child()? {
+		match name.as_str() {
+			HEADER_ELEMENT => {
+				let header: Header = serde_xml_rs::from_reader(element.as_slice())?;
+				sink.header(parse_datafile_header(header)?)?;
+			}
+			GAME_ELEMENT => {
+				let game: Game = serde_xml_rs::from_reader(element.as_slice())?;
+				sink.game(game)?;
+			}
+			_ => {}
+		}
+	}
+
+	Ok(())
+}
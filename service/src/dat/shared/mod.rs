@@ -1,5 +1,12 @@
+pub mod archive;
+pub mod changes;
+pub mod clrmamepro;
 pub mod download;
+pub mod format;
 pub mod import;
+pub mod kind;
+pub mod logiqx;
 pub mod model;
-mod regex;
-pub mod zip;
+pub mod regex;
+pub mod stream;
+pub mod xml;
@@ -1,12 +1,8 @@
 use serde::{Deserialize, Serialize};
 use strum::Display;
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct Datafile {
-	pub header: Header,
-
-	pub game: Option<Vec<Game>>,
-}
+/// Version used when a DAT doesn't state one.
+pub const UNKNOWN_DAT_VERSION: &str = "unknown";
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Game {
@@ -22,6 +18,10 @@ pub struct Game {
 
 	pub cloneofid: Option<String>,
 
+	pub romof: Option<String>,
+
+	pub isbios: Option<String>,
+
 	pub category: Option<Vec<String>>,
 }
 
@@ -60,9 +60,29 @@ pub struct Header {
 
 	pub author: Option<String>,
 
-	pub homepage: String,
+	pub category: Option<String>,
+
+	pub homepage: Option<String>,
 
-	pub url: String,
+	pub url: Option<String>,
+
+	pub clrmamepro: Option<ClrMameProHeader>,
+}
+
+impl Header {
+	/// File name of the header detector the DAT references, e.g. `No-Intro_NES.xml`.
+	pub fn header_detector_name(&self) -> Option<&str> {
+		self.clrmamepro
+			.as_ref()
+			.and_then(|clrmamepro| clrmamepro.header.as_deref())
+			.filter(|header| !header.is_empty())
+	}
+}
+
+/// ClrMamePro specific settings of a Logiqx DAT.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ClrMameProHeader {
+	pub header: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Display)]
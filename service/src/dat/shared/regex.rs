@@ -4,4 +4,6 @@ use regex::Regex;
 lazy_static! {
 	pub static ref DAT_TAG_REGEX: Regex = Regex::new(r"\(([^)]+)\)").unwrap();
 	pub static ref DAT_NUMBER_REGEX: Regex = Regex::new(r"\(\d+\)").unwrap();
+	pub static ref TOSEC_VERSION_REGEX: Regex = Regex::new(r"\s*\(TOSEC-v[^)]*\)").unwrap();
+	pub static ref DAT_SQUARE_TAG_REGEX: Regex = Regex::new(r"\[([^\]]+)\]").unwrap();
 }
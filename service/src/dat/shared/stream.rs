@@ -0,0 +1,121 @@
+use crate::dat::mame::parse::{stream_mame_list_xml, stream_mame_software_list};
+use crate::dat::shared::clrmamepro::stream_clrmamepro_dat;
+use crate::dat::shared::format::{detect_dat_format, DatFormat};
+use crate::dat::shared::import::ParsedDat;
+use crate::dat::shared::logiqx::stream_logiqx_dat;
+use crate::dat::shared::model::Game;
+use anyhow::{anyhow, bail};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task;
+
+/// Amount of bytes read to detect the format, large enough to skip the DTD of MAME listxml.
+const FORMAT_DETECTION_SIZE: u64 = 256 * 1024;
+
+/// Amount of parsed games which may wait for the import before the parser blocks.
+const GAME_BUFFER_SIZE: usize = 256;
+
+pub type GameReceiver = mpsc::Receiver<anyhow::Result<Game>>;
+
+/// Receives the header and the games of a DAT while it is parsed, the header has to come first.
+pub struct DatSink {
+	header: Option<oneshot::Sender<anyhow::Result<ParsedDat>>>,
+	games: mpsc::Sender<anyhow::Result<Game>>,
+}
+
+impl DatSink {
+	pub fn header(&mut self, parsed: ParsedDat) -> anyhow::Result<()> {
+		let sender = self
+			.header
+			.take()
+			.ok_or_else(|| anyhow!("DAT contains more than one header"))?;
+
+		sender
+			.send(Ok(parsed))
+			.map_err(|_| anyhow!("DAT import was cancelled"))
+	}
+
+	/// Blocks until the import has room for another game.
+	pub fn game(&mut self, game: Game) -> anyhow::Result<()> {
+		if self.header.is_some() {
+			bail!("DAT contains games before its header");
+		}
+
+		self.games
+			.blocking_send(Ok(game))
+			.map_err(|_| anyhow!("DAT import was cancelled"))
+	}
+
+	fn finish(self, result: anyhow::Result<()>) {
+		let result = match (result, self.header.is_some()) {
+			(Ok(_), true) => Err(anyhow!("DAT has no header")),
+			(result, _) => result,
+		};
+
+		let Err(e) = result else {
+			return;
+		};
+
+		// nobody is listening anymore if the import was cancelled, so failing to send is fine
+		match self.header {
+			Some(header) => {
+				let _ = header.send(Err(e));
+			}
+			None => {
+				let _ = self.games.blocking_send(Err(e));
+			}
+		}
+	}
+}
+
+/// Parses a DAT on a blocking thread and returns its header as soon as it is read, the games are
+/// streamed through the receiver so memory stays bounded no matter how large the DAT is.
+pub async fn stream_dat_file(path: &Path) -> anyhow::Result<(ParsedDat, GameReceiver)> {
+	let format = detect_dat_format(&read_dat_file_start(path).await?)?;
+
+	let (header_sender, header_receiver) = oneshot::channel();
+	let (game_sender, game_receiver) = mpsc::channel(GAME_BUFFER_SIZE);
+	let path = path.to_path_buf();
+
+	task::spawn_blocking(move || {
+		let mut sink = DatSink {
+			header: Some(header_sender),
+			games: game_sender,
+		};
+
+		let result = parse_dat_file(&path, format, &mut sink);
+		sink.finish(result);
+	});
+
+	let parsed = header_receiver
+		.await
+		.map_err(|_| anyhow!("DAT parser stopped unexpectedly"))??;
+
+	Ok((parsed, game_receiver))
+}
+
+fn parse_dat_file(path: &PathBuf, format: DatFormat, sink: &mut DatSink) -> anyhow::Result<()> {
+	let reader = BufReader::new(std::fs::File::open(path)?);
+
+	match format {
+		DatFormat::Logiqx => stream_logiqx_dat(reader, sink),
+		DatFormat::ClrMamePro => stream_clrmamepro_dat(reader, sink),
+		DatFormat::MameListXml => stream_mame_list_xml(reader, sink),
+		DatFormat::MameSoftwareList => stream_mame_software_list(reader, sink),
+	}
+}
+
+async fn read_dat_file_start(path: &Path) -> anyhow::Result<Vec<u8>> {
+	let dat_file = File::open(path).await?;
+
+	let mut content = Vec::new();
+	dat_file
+		.take(FORMAT_DETECTION_SIZE)
+		.read_to_end(&mut content)
+		.await?;
+
+	Ok(content)
+}
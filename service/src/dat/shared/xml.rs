@@ -0,0 +1,184 @@
+use anyhow::bail;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, Writer};
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// Pull-parser which yields the children of the root element one at a time as raw XML, so they can
+/// be deserialized on their own without holding the whole document in memory.
+pub struct XmlElementReader<R: BufRead> {
+	reader: Reader<R>,
+	buf: Vec<u8>,
+	element_buf: Vec<u8>,
+	root_closed: bool,
+}
+
+impl<R: BufRead> XmlElementReader<R> {
+	pub fn new(reader: R) -> Self {
+		Self {
+			reader: Reader::from_reader(reader),
+			buf: Vec::new(),
+			element_buf: Vec::new(),
+			root_closed: false,
+		}
+	}
+
+	/// Skips everything up to the root element and returns its attributes.
+	pub fn read_root(&mut self) -> anyhow::Result<HashMap<String, String>> {
+		loop {
+			self.buf.clear();
+
+			match self.reader.read_event_into(&mut self.buf)? {
+				Event::Start(element) => return attributes(&element),
+				Event::Empty(element) => {
+					self.root_closed = true;
+					return attributes(&element);
+				}
+				Event::Eof => bail!("No root element found"),
+				_ => {}
+			}
+		}
+	}
+
+	/// Returns the name and the raw XML of the next child element of the root, `None` once the
+	/// root element is closed.
+	pub fn next_child(&mut self) -> anyhow::Result<Option<(String, Vec<u8>)>> {
+		if self.root_closed {
+			return Ok(None);
+		}
+
+		loop {
+			self.buf.clear();
+
+			match self.reader.read_event_into(&mut self.buf)? {
+				Event::Start(element) => {
+					let name = String::from_utf8_lossy(element.name().as_ref()).to_string();
+					let mut writer = Writer::new(Vec::new());
+					writer.write_event(Event::Start(element))?;
+
+					let mut depth = 1;
+
+					while depth > 0 {
+						self.element_buf.clear();
+						let event = self.reader.read_event_into(&mut self.element_buf)?;
+
+						match event {
+							Event::Start(_) => depth += 1,
+							Event::End(_) => depth -= 1,
+							Event::Eof => bail!("Unexpected end of file in element <{}>", name),
+							_ => {}
+						}
+
+						writer.write_event(event)?;
+					}
+
+					return Ok(Some((name, writer.into_inner())));
+				}
+				Event::Empty(element) => {
+					let name = String::from_utf8_lossy(element.name().as_ref()).to_string();
+					let mut writer = Writer::new(Vec::new());
+					writer.write_event(Event::Empty(element))?;
+
+					return Ok(Some((name, writer.into_inner())));
+				}
+				Event::End(_) => {
+					self.root_closed = true;
+					return Ok(None);
+				}
+				Event::Eof => bail!("Unexpected end of file, the root element is not closed"),
+				_ => {}
+			}
+		}
+	}
+}
+
+pub(crate) fn attributes(element: &BytesStart) -> anyhow::Result<HashMap<String, String>> {
+	let mut attributes = HashMap::new();
+
+	for attribute in element.attributes() {
+		let attribute = attribute?;
+
+		attributes.insert(
+			String::from_utf8_lossy(attribute.key.as_ref()).to_string(),
+			attribute.unescape_value()?.to_string(),
+		);
+	}
+
+	Ok(attributes)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn read_children(content: &str) -> anyhow::Result<Vec<(String, String)>> {
+		let mut reader = XmlElementReader::new(content.as_bytes());
+		reader.read_root()?;
+
+		let mut children = Vec::new();
+		while let Some((name, element)) = reader.next_child()? {
+			children.push((name, String::from_utf8(element)?));
+		}
+
+		Ok(children)
+	}
+
+	#[test]
+	fn reads_the_attributes_of_the_root() {
+		let mut reader = XmlElementReader::new(
+			r#"<?xml version="1.0"?>
+<!DOCTYPE mame [
+<!ELEMENT mame (machine+)>
+]>
+<mame build="0.268 &amp; more" debug="no"></mame>"#
+				.as_bytes(),
+		);
+
+		let attributes = reader.read_root().unwrap();
+
+		assert_eq!(attributes["build"], "0.268 & more");
+		assert_eq!(attributes["debug"], "no");
+		assert!(reader.next_child().unwrap().is_none());
+	}
+
+	#[test]
+	fn yields_every_child_with_its_nested_elements() {
+		let children = read_children(
+			r#"<datafile>
+	<header><name>Test</name></header>
+	<!-- a comment -->
+	<game name="Tetris"><rom name="Tetris.gb" size="32768"/><rom name="Tetris.sav"/></game>
+	<game name="Empty"/>
+</datafile>"#,
+		)
+		.unwrap();
+
+		assert_eq!(
+			children,
+			vec![
+				(
+					"header".to_string(),
+					"<header><name>Test</name></header>".to_string()
+				),
+				(
+					"game".to_string(),
+					r#"<game name="Tetris"><rom name="Tetris.gb" size="32768"/><rom name="Tetris.sav"/></game>"#
+						.to_string()
+				),
+				("game".to_string(), r#"<game name="Empty"/>"#.to_string()),
+			]
+		);
+	}
+
+	#[test]
+	fn yields_nothing_for_an_empty_root() {
+		assert!(read_children("<datafile/>").unwrap().is_empty());
+	}
+
+	#[test]
+	fn fails_on_truncated_documents() {
+		assert!(read_children("<datafile><game name=\"Tetris\"><rom/>").is_err());
+		assert!(read_children("<datafile><game name=\"Tetris\"/>").is_err());
+		assert!(read_children("").is_err());
+	}
+}
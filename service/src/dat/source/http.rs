@@ -0,0 +1,67 @@
+use crate::dat::shared::archive::extract_if_archived;
+use crate::dat::shared::download::download_dat;
+use crate::dat::source::{DatSource, DatSourceFetch};
+use crate::http::download::CacheValidators;
+use futures_util::future::BoxFuture;
+use log::error;
+use reqwest::Client;
+use std::path::Path;
+
+/// Downloads a DAT, or an archive of DATs, from a URL.
+pub struct HttpDatSource {
+	name: String,
+	signature_group: String,
+	url: String,
+	keep_subfolders: bool,
+}
+
+impl HttpDatSource {
+	pub fn new(name: String, signature_group: String, url: String, keep_subfolders: bool) -> Self {
+		Self {
+			name,
+			signature_group,
+			url,
+			keep_subfolders,
+		}
+	}
+}
+
+impl DatSource for HttpDatSource {
+	fn name(&self) -> &str {
+		&self.name
+	}
+
+	fn signature_group(&self) -> &str {
+		&self.signature_group
+	}
+
+	fn keep_subfolders(&self) -> bool {
+		self.keep_subfolders
+	}
+
+	fn location(&self) -> String {
+		self.url.clone()
+	}
+
+	fn fetch<'a>(
+		&'a self,
+		client: &'a Client,
+		tmp_dir: &'a Path,
+		partial_path: &'a Path,
+		previous: Option<&'a CacheValidators>,
+	) -> BoxFuture<'a, anyhow::Result<DatSourceFetch>> {
+		Box::pin(async move {
+			let Some((path, validators)) =
+				download_dat(client, &self.url, tmp_dir, partial_path, previous).await?
+			else {
+				return Ok(DatSourceFetch::Unchanged);
+			};
+
+			if let Err(e) = extract_if_archived(&path).await {
+				error!("Failed to extract DAT archive {} {:?}", path.display(), e);
+			}
+
+			Ok(DatSourceFetch::Fetched(validators))
+		})
+	}
+}
@@ -0,0 +1,124 @@
+use crate::dat::shared::archive::extract_if_archived;
+use crate::dat::source::{DatSource, DatSourceFetch};
+use crate::fs::read_files_recursive;
+use crate::http::download::CacheValidators;
+use futures_util::future::BoxFuture;
+use log::{debug, error};
+use md5::{Digest, Md5};
+use reqwest::Client;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tokio::fs;
+
+/// Copies DATs, or archives of DATs, from a local folder, e.g. a mounted mirror.
+pub struct LocalDatSource {
+	name: String,
+	signature_group: String,
+	path: PathBuf,
+	keep_subfolders: bool,
+}
+
+impl LocalDatSource {
+	pub fn new(
+		name: String,
+		signature_group: String,
+		path: PathBuf,
+		keep_subfolders: bool,
+	) -> Self {
+		Self {
+			name,
+			signature_group,
+			path,
+			keep_subfolders,
+		}
+	}
+}
+
+impl DatSource for LocalDatSource {
+	fn name(&self) -> &str {
+		&self.name
+	}
+
+	fn signature_group(&self) -> &str {
+		&self.signature_group
+	}
+
+	fn keep_subfolders(&self) -> bool {
+		self.keep_subfolders
+	}
+
+	fn location(&self) -> String {
+		self.path.display().to_string()
+	}
+
+	fn fetch<'a>(
+		&'a self,
+		_: &'a Client,
+		tmp_dir: &'a Path,
+		_: &'a Path,
+		previous: Option<&'a CacheValidators>,
+	) -> BoxFuture<'a, anyhow::Result<DatSourceFetch>> {
+		Box::pin(async move {
+			let files = read_files_recursive(&self.path).await?;
+			let fingerprint = fingerprint_files(&self.path, &files).await?;
+
+			if previous.and_then(|previous| previous.etag.as_ref()) == Some(&fingerprint) {
+				debug!("DATs in {:?} have not changed", self.path);
+				return Ok(DatSourceFetch::Unchanged);
+			}
+
+			debug!("Copying DATs from: {:?}", self.path);
+
+			for file in files {
+				let out = tmp_dir.join(file.strip_prefix(&self.path)?);
+
+				if let Some(parent) = out.parent() {
+					fs::create_dir_all(parent).await?;
+				}
+
+				fs::copy(&file, &out).await?;
+
+				if let Err(e) = extract_if_archived(&out).await {
+					error!("Failed to extract DAT archive {} {:?}", out.display(), e);
+				}
+			}
+
+			Ok(DatSourceFetch::Fetched(CacheValidators {
+				etag: Some(fingerprint),
+				last_modified: None,
+			}))
+		})
+	}
+}
+
+/// Local folders have no ETag, so one is derived from the path, size and modification time of
+/// every file instead of hashing their content.
+async fn fingerprint_files(root: &Path, files: &[PathBuf]) -> anyhow::Result<String> {
+	let mut entries = Vec::with_capacity(files.len());
+
+	for file in files {
+		let metadata = fs::metadata(file).await?;
+		let modified = metadata
+			.modified()?
+			.duration_since(UNIX_EPOCH)
+			.unwrap_or_default()
+			.as_nanos();
+
+		entries.push(format!(
+			"{}\0{}\0{}",
+			file.strip_prefix(root)?.display(),
+			metadata.len(),
+			modified
+		));
+	}
+
+	entries.sort();
+
+	let mut hasher = Md5::new();
+	for entry in entries {
+		hasher.update(entry.as_bytes());
+		hasher.update(b"\n");
+	}
+
+	Ok(format!("{:x}", hasher.finalize()))
+}
@@ -0,0 +1,190 @@
+use crate::dat::source::http::HttpDatSource;
+use crate::dat::source::local::LocalDatSource;
+use crate::dat::TMP_PATH;
+use crate::http::download::CacheValidators;
+use anyhow::{anyhow, bail};
+use futures_util::future::BoxFuture;
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::env;
+use std::path::{Path, PathBuf};
+
+pub mod http;
+pub mod local;
+pub mod state;
+
+const DAT_SOURCES_ENV: &str = "DAT_SOURCES";
+const DAT_SOURCES_FILE_ENV: &str = "DAT_SOURCES_FILE";
+
+/// A place DAT files are fetched from, every source gets its own folder in `dats/{name}` and all
+/// DATs in there are imported into its signature group.
+pub trait DatSource: Send + Sync {
+	/// Unique name of the source, also used as folder name.
+	fn name(&self) -> &str;
+
+	/// Name of the signature group the DATs of this source belong to (e.g. "No-Intro").
+	fn signature_group(&self) -> &str;
+
+	/// Whether the folder structure of the fetched DATs is kept, e.g. TOSEC splits its DATs into
+	/// TOSEC, TOSEC-ISO and TOSEC-PIX folders.
+	fn keep_subfolders(&self) -> bool;
+
+	/// Where the DATs are fetched from, e.g. the URL.
+	fn location(&self) -> String;
+
+	/// Fetches all DATs of this source into the (empty) temporary directory, archives are extracted.
+	/// If validators of the previous fetch are given and nothing changed since, nothing is fetched.
+	/// Interrupted downloads can be kept at `partial_path` to resume them on the next run.
+	fn fetch<'a>(
+		&'a self,
+		client: &'a Client,
+		tmp_dir: &'a Path,
+		partial_path: &'a Path,
+		previous: Option<&'a CacheValidators>,
+	) -> BoxFuture<'a, anyhow::Result<DatSourceFetch>>;
+}
+
+pub enum DatSourceFetch {
+	/// Nothing changed since the previous fetch, the DATs on disk are still up to date.
+	Unchanged,
+	/// New DATs have been fetched, the validators make the next fetch conditional.
+	Fetched(CacheValidators),
+}
+
+/// Configuration of a single DAT source, read from `DAT_SOURCES_FILE` or `DAT_SOURCES` as JSON.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+	tag = "type",
+	rename_all = "lowercase",
+	rename_all_fields = "camelCase"
+)]
+pub enum DatSourceConfig {
+	/// Downloads a single DAT or an archive of DATs from a URL.
+	Http {
+		name: String,
+		signature_group: String,
+		url: String,
+		#[serde(default)]
+		keep_subfolders: bool,
+	},
+
+	/// Copies DATs (or archives of DATs) from a local folder.
+	Local {
+		name: String,
+		signature_group: String,
+		path: PathBuf,
+		#[serde(default)]
+		keep_subfolders: bool,
+	},
+}
+
+impl DatSourceConfig {
+	fn name(&self) -> &str {
+		match self {
+			DatSourceConfig::Http { name, .. } | DatSourceConfig::Local { name, .. } => name,
+		}
+	}
+
+	fn into_dat_source(self) -> Box<dyn DatSource> {
+		match self {
+			DatSourceConfig::Http {
+				name,
+				signature_group,
+				url,
+				keep_subfolders,
+			} => Box::new(HttpDatSource::new(
+				name,
+				signature_group,
+				url,
+				keep_subfolders,
+			)),
+			DatSourceConfig::Local {
+				name,
+				signature_group,
+				path,
+				keep_subfolders,
+			} => Box::new(LocalDatSource::new(
+				name,
+				signature_group,
+				path,
+				keep_subfolders,
+			)),
+		}
+	}
+}
+
+/// Loads the configured DAT sources, `DAT_SOURCES_FILE` takes precedence over `DAT_SOURCES`, if
+/// neither is set the RetroRealm mirrors are used.
+pub fn load_dat_sources() -> anyhow::Result<Vec<Box<dyn DatSource>>> {
+	let configs = if let Ok(path) = env::var(DAT_SOURCES_FILE_ENV) {
+		let content = std::fs::read_to_string(&path)
+			.map_err(|e| anyhow!("Failed to read {} {}: {}", DAT_SOURCES_FILE_ENV, path, e))?;
+		serde_json::from_str(&content)?
+	} else if let Ok(content) = env::var(DAT_SOURCES_ENV) {
+		serde_json::from_str(&content)?
+	} else {
+		default_dat_source_configs()
+	};
+
+	validate_dat_source_configs(&configs)?;
+
+	Ok(configs
+		.into_iter()
+		.map(DatSourceConfig::into_dat_source)
+		.collect())
+}
+
+fn default_dat_source_configs() -> Vec<DatSourceConfig> {
+	vec![
+		DatSourceConfig::Http {
+			name: "no-intro".to_string(),
+			signature_group: "No-Intro".to_string(),
+			url: "https://dats.retrorealm.dev/no-intro/daily".to_string(),
+			keep_subfolders: true,
+		},
+		DatSourceConfig::Http {
+			name: "redump".to_string(),
+			signature_group: "Redump".to_string(),
+			url: "https://dats.retrorealm.dev/redump/daily".to_string(),
+			keep_subfolders: false,
+		},
+		DatSourceConfig::Http {
+			name: "tosec".to_string(),
+			signature_group: "TOSEC".to_string(),
+			url: "https://dats.retrorealm.dev/tosec/daily".to_string(),
+			keep_subfolders: true,
+		},
+		DatSourceConfig::Http {
+			name: "mame".to_string(),
+			signature_group: "MAME".to_string(),
+			url: "https://dats.retrorealm.dev/mame/daily".to_string(),
+			keep_subfolders: true,
+		},
+	]
+}
+
+/// Source names are used as folder names, so they have to be unique and safe to use in a path.
+fn validate_dat_source_configs(configs: &[DatSourceConfig]) -> anyhow::Result<()> {
+	let mut names = HashSet::new();
+
+	for config in configs {
+		let name = config.name();
+
+		if name.is_empty()
+			|| name == TMP_PATH
+			|| !name
+				.chars()
+				.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+			|| name.starts_with('.')
+		{
+			bail!("Invalid DAT source name: {:?}", name);
+		}
+
+		if !names.insert(name) {
+			bail!("Duplicate DAT source name: {:?}", name);
+		}
+	}
+
+	Ok(())
+}
@@ -0,0 +1,58 @@
+use crate::http::download::CacheValidators;
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Record of the last successful fetch of a DAT source, stored as `dats/.state/{name}.json` next
+/// to the fetched DATs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatSourceState {
+	/// Where the DATs have been fetched from, the state is discarded if the source changed.
+	pub location: String,
+	#[serde(flatten)]
+	pub validators: CacheValidators,
+	/// When the DATs on disk have been fetched.
+	pub fetched_at: DateTime<Utc>,
+	/// When the source has last been checked for changes.
+	pub checked_at: DateTime<Utc>,
+	/// Whether all DATs of the fetch have been imported, otherwise they are imported again on the
+	/// next run even if nothing changed.
+	pub imported: bool,
+}
+
+fn dat_source_state_path(state_dir: &Path, name: &str) -> PathBuf {
+	state_dir.join(format!("{}.json", name))
+}
+
+/// Reads the state of the last fetch, a missing or unreadable state is treated as never fetched.
+pub async fn read_dat_source_state(state_dir: &Path, name: &str) -> Option<DatSourceState> {
+	let content = fs::read(dat_source_state_path(state_dir, name))
+		.await
+		.ok()?;
+
+	match serde_json::from_slice(&content) {
+		Ok(state) => Some(state),
+		Err(e) => {
+			warn!("Ignoring invalid state of DAT source {}: {}", name, e);
+			None
+		}
+	}
+}
+
+pub async fn write_dat_source_state(
+	state_dir: &Path,
+	name: &str,
+	state: &DatSourceState,
+) -> anyhow::Result<()> {
+	let path = dat_source_state_path(state_dir, name);
+	let tmp_path = path.with_extension("json.tmp");
+
+	// written to a temporary file first, so a crash never leaves a half written state behind
+	fs::write(&tmp_path, serde_json::to_vec_pretty(state)?).await?;
+	fs::rename(&tmp_path, &path).await?;
+
+	Ok(())
+}
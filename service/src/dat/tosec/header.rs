@@ -0,0 +1,58 @@
+use crate::dat::shared::model::Header;
+use crate::dat::shared::regex::{DAT_SQUARE_TAG_REGEX, DAT_TAG_REGEX, TOSEC_VERSION_REGEX};
+
+const TOSEC_CATEGORY: &str = "TOSEC";
+
+pub fn is_tosec_dat(header: &Header) -> bool {
+	header.category.as_deref() == Some(TOSEC_CATEGORY)
+		|| TOSEC_VERSION_REGEX.is_match(&header.description)
+}
+
+/// TOSEC names its DATs "Company Platform - Category - Subcategory (TOSEC-vX)", so the first part
+/// holds both the company and the platform while every following part is a tag.
+pub fn parse_tosec_company_and_platform(
+	header: &Header,
+) -> anyhow::Result<(Option<String>, String, Vec<String>)> {
+	let dat_header = TOSEC_VERSION_REGEX
+		.replace_all(&header.name, "")
+		.to_string();
+
+	let mut split = dat_header.split(" - ");
+
+	let system = match split.next().map(str::trim) {
+		Some(system) if !system.is_empty() => system,
+		_ => return Err(anyhow::anyhow!("No company or system found")),
+	};
+
+	let (company, platform) = match system.split_once(' ') {
+		Some((company, platform)) => (Some(company.to_string()), platform.trim().to_string()),
+		None => (None, system.to_string()),
+	};
+
+	let mut tags = Vec::new();
+
+	for part in split {
+		let mut remainder = part.to_string();
+		let mut part_tags = Vec::new();
+
+		for tag in DAT_SQUARE_TAG_REGEX
+			.captures_iter(part)
+			.chain(DAT_TAG_REGEX.captures_iter(part))
+		{
+			let full = tag.get(0).map(|x| x.as_str()).unwrap_or_default();
+			let tag = tag.get(1).map(|x| x.as_str()).unwrap_or_default();
+			part_tags.push(tag.to_owned());
+			remainder = remainder.replace(full, "");
+		}
+
+		let remainder = remainder.trim();
+
+		if !remainder.is_empty() {
+			tags.push(remainder.to_owned());
+		}
+
+		tags.append(&mut part_tags);
+	}
+
+	Ok((company, platform, tags))
+}
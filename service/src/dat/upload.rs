@@ -0,0 +1,82 @@
+use crate::dat::shared::archive::extract_if_archived;
+use crate::dat::{import_dat_file_if_new, DatImportOutcome, DAT_IMPORT_LOCK};
+use crate::db::signature_group::create_or_find_signature_group_by_name;
+use crate::fs::read_files_recursive;
+use crate::hash::calculate_md5;
+use crate::model::{DatFileImportResultResponse, DatUploadFailure, DatUploadResponse};
+use crate::r#match::clone::populate_all_clone_of_ids;
+use crate::util::random_sized_string;
+use anyhow::anyhow;
+use sea_orm::DbConn;
+use std::env;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+const UPLOAD_TMP_DIR: &str = "playmatch-upload";
+
+/// Creates an empty temporary directory for a single upload, the caller has to remove it afterwards.
+pub async fn create_upload_dir() -> anyhow::Result<PathBuf> {
+	let dir = env::temp_dir()
+		.join(UPLOAD_TMP_DIR)
+		.join(random_sized_string(16));
+
+	fs::create_dir_all(&dir).await?;
+
+	Ok(dir)
+}
+
+/// Imports an uploaded DAT (or archive of DATs) into the given signature group. The file has to be in
+/// its own directory, archives are extracted next to it.
+pub async fn import_uploaded_dat(
+	file: &Path,
+	signature_group_name: &str,
+	conn: &DbConn,
+) -> anyhow::Result<DatUploadResponse> {
+	let upload_dir = file
+		.parent()
+		.ok_or_else(|| anyhow!("Uploaded file has no parent directory"))?;
+
+	extract_if_archived(file).await?;
+
+	let signature_group =
+		create_or_find_signature_group_by_name(signature_group_name, conn).await?;
+
+	let mut response = DatUploadResponse {
+		imports: vec![],
+		skipped: vec![],
+		failed: vec![],
+	};
+
+	let _lock = DAT_IMPORT_LOCK.lock().await;
+
+	for dat in read_files_recursive(upload_dir).await? {
+		let file_name = dat
+			.strip_prefix(upload_dir)
+			.unwrap_or(&dat)
+			.display()
+			.to_string();
+
+		let md5_hash = calculate_md5(&dat).await?;
+
+		match import_dat_file_if_new(&dat, &md5_hash, signature_group.id, conn).await? {
+			DatImportOutcome::Imported(imported) => {
+				response.imports.push(DatFileImportResultResponse {
+					import: imported.import.into(),
+					game_count: imported.game_count,
+					file_count: imported.file_count,
+				});
+			}
+			DatImportOutcome::Skipped => response.skipped.push(file_name),
+			DatImportOutcome::Failed(e) => response.failed.push(DatUploadFailure {
+				file_name,
+				error: e.to_string(),
+			}),
+		}
+	}
+
+	if !response.imports.is_empty() {
+		populate_all_clone_of_ids(conn).await?;
+	}
+
+	Ok(response)
+}
@@ -0,0 +1,53 @@
+use crate::db::dat_file::find_dat_file_by_id;
+use crate::db::dat_file_import::{
+	find_dat_file_import_by_id, find_dat_file_imports_by_dat_file_id,
+};
+use crate::db::dat_file_import_change::find_dat_file_import_changes_by_dat_file_import_id;
+use crate::db::dat_file_import_failure::find_latest_dat_file_import_failures;
+use crate::model::{
+	DatFileImportChangeResponse, DatFileImportFailureResponse, DatFileImportResponse,
+};
+use sea_orm::prelude::Uuid;
+use sea_orm::DbConn;
+
+const DAT_FILE_IMPORT_FAILURE_LIMIT: u64 = 100;
+
+pub async fn get_dat_file_imports(
+	dat_file_id: Uuid,
+	db_conn: &DbConn,
+) -> anyhow::Result<Option<Vec<DatFileImportResponse>>> {
+	if find_dat_file_by_id(dat_file_id, db_conn).await?.is_none() {
+		return Ok(None);
+	}
+
+	let imports = find_dat_file_imports_by_dat_file_id(dat_file_id, db_conn).await?;
+
+	Ok(Some(imports.into_iter().map(Into::into).collect()))
+}
+
+pub async fn get_dat_file_import_changes(
+	dat_file_import_id: Uuid,
+	db_conn: &DbConn,
+) -> anyhow::Result<Option<Vec<DatFileImportChangeResponse>>> {
+	if find_dat_file_import_by_id(dat_file_import_id, db_conn)
+		.await?
+		.is_none()
+	{
+		return Ok(None);
+	}
+
+	let changes =
+		find_dat_file_import_changes_by_dat_file_import_id(dat_file_import_id, db_conn).await?;
+
+	Ok(Some(changes.into_iter().map(Into::into).collect()))
+}
+
+/// Returns the latest failed DAT imports, newest first.
+pub async fn get_latest_dat_file_import_failures(
+	db_conn: &DbConn,
+) -> anyhow::Result<Vec<DatFileImportFailureResponse>> {
+	let failures =
+		find_latest_dat_file_import_failures(DAT_FILE_IMPORT_FAILURE_LIMIT, db_conn).await?;
+
+	Ok(failures.into_iter().map(Into::into).collect())
+}
@@ -5,13 +5,13 @@ use entity::{company, signature_metadata_mapping};
 use sea_orm::prelude::Uuid;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-	ActiveModelTrait, ColumnTrait, DbConn, DbErr, EntityTrait, ModelTrait, QueryFilter, QueryOrder,
-	QuerySelect, TryIntoModel,
+	ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, ModelTrait, QueryFilter,
+	QueryOrder, QuerySelect, TryIntoModel,
 };
 
 pub async fn get_by_id_and_join_signature_metadata_mappings(
 	id: Uuid,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> Result<Option<(company::Model, Vec<signature_metadata_mapping::Model>)>, DbErr> {
 	let company = company::Entity::find()
 		.filter(company::Column::Id.eq(id))
@@ -31,7 +31,7 @@ pub async fn get_by_id_and_join_signature_metadata_mappings(
 }
 
 pub async fn find_all_and_join_signature_metadata_mapping(
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> Result<Vec<(company::Model, Vec<signature_metadata_mapping::Model>)>, DbErr> {
 	let companies_with_mappings = company::Entity::find()
 		.find_with_related(signature_metadata_mapping::Entity)
@@ -43,7 +43,7 @@ pub async fn find_all_and_join_signature_metadata_mapping(
 
 pub async fn create_or_find_company_by_name(
 	name: &str,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> Result<company::Model, DbErr> {
 	let company = Company::find()
 		.filter(company::Column::Name.eq(name))
@@ -66,7 +66,7 @@ pub async fn create_or_find_company_by_name(
 
 pub async fn get_unmatched_companies_with_limit(
 	limit: u64,
-	db_conn: &DbConn,
+	db_conn: &impl ConnectionTrait,
 ) -> anyhow::Result<Option<Vec<company::Model>>> {
 	let found_companies = Company::find()
 		.left_join(signature_metadata_mapping::Entity)
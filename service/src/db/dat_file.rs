@@ -4,7 +4,8 @@ use entity::prelude::DatFile;
 use sea_orm::prelude::Uuid;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-	ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, IntoActiveModel, QueryFilter, TryIntoModel,
+	ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter,
+	TryIntoModel,
 };
 
 pub struct DatFileCreateOrUpdateInput {
@@ -13,17 +14,20 @@ pub struct DatFileCreateOrUpdateInput {
 	pub current_version: String,
 	pub tags: Vec<String>,
 	pub subset: Option<String>,
+	pub header_detector_name: Option<String>,
 	pub company_id: Option<Uuid>,
 	pub platform_id: Uuid,
 }
 
-pub async fn find_all_dat_files(conn: &DbConn) -> anyhow::Result<Vec<dat_file::Model>> {
+pub async fn find_all_dat_files(
+	conn: &impl ConnectionTrait,
+) -> anyhow::Result<Vec<dat_file::Model>> {
 	Ok(DatFile::find().all(conn).await?)
 }
 
 pub async fn create_or_update_dat_file(
 	input: DatFileCreateOrUpdateInput,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> anyhow::Result<dat_file::Model> {
 	let dat_file = DatFile::find()
 		.filter(dat_file::Column::SignatureGroupId.eq(input.signature_group_id))
@@ -34,9 +38,12 @@ pub async fn create_or_update_dat_file(
 		.await?;
 
 	if let Some(dat_file) = dat_file {
-		if dat_file.current_version != input.current_version {
+		if dat_file.current_version != input.current_version
+			|| dat_file.header_detector_name != input.header_detector_name
+		{
 			let mut active_model = dat_file.into_active_model();
 			active_model.current_version = Set(input.current_version.to_string());
+			active_model.header_detector_name = Set(input.header_detector_name);
 
 			return Ok(active_model.save(conn).await?.try_into_model()?);
 		}
@@ -56,8 +63,26 @@ pub async fn create_or_update_dat_file(
 			Some(input.tags)
 		}),
 		subset: Set(input.subset),
+		header_detector_name: Set(input.header_detector_name),
 		..Default::default()
 	};
 
 	Ok(dat_file.save(conn).await?.try_into_model()?)
 }
+
+pub async fn find_dat_file_by_id(
+	id: Uuid,
+	conn: &impl ConnectionTrait,
+) -> anyhow::Result<Option<dat_file::Model>> {
+	Ok(DatFile::find_by_id(id).one(conn).await?)
+}
+
+pub async fn find_dat_files_by_ids(
+	ids: Vec<Uuid>,
+	conn: &impl ConnectionTrait,
+) -> anyhow::Result<Vec<dat_file::Model>> {
+	Ok(DatFile::find()
+		.filter(dat_file::Column::Id.is_in(ids))
+		.all(conn)
+		.await?)
+}
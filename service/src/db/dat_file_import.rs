@@ -3,11 +3,14 @@ use entity::dat_file_import::Entity as DatFileImport;
 use sea_orm::prelude::Uuid;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-	ActiveModelTrait, ColumnTrait, DbConn, DbErr, EntityTrait, PaginatorTrait, QueryFilter,
-	TryIntoModel,
+	ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, PaginatorTrait,
+	QueryFilter, QueryOrder, TryIntoModel,
 };
 
-pub async fn is_dat_already_in_history(md5_hash: &str, conn: &DbConn) -> Result<bool, DbErr> {
+pub async fn is_dat_already_in_history(
+	md5_hash: &str,
+	conn: &impl ConnectionTrait,
+) -> Result<bool, DbErr> {
 	DatFileImport::find()
 		.filter(dat_file_import::Column::Md5Hash.eq(md5_hash))
 		.count(conn)
@@ -15,12 +18,26 @@ pub async fn is_dat_already_in_history(md5_hash: &str, conn: &DbConn) -> Result<
 		.map(|count| count > 0)
 }
 
+/// Checks if the DAT was imported before the given import, stops at the first other import.
+pub async fn has_other_dat_file_import(
+	dat_file_id: Uuid,
+	import_id: Uuid,
+	conn: &impl ConnectionTrait,
+) -> Result<bool, DbErr> {
+	DatFileImport::find()
+		.filter(dat_file_import::Column::DatFileId.eq(dat_file_id))
+		.filter(dat_file_import::Column::Id.ne(import_id))
+		.one(conn)
+		.await
+		.map(|import| import.is_some())
+}
+
 pub async fn create_dat_file_import(
 	file_name: &str,
 	md5_hash: &str,
 	version: &str,
 	dat_file_id: Uuid,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> Result<dat_file_import::Model, DbErr> {
 	let dat_file_import = dat_file_import::ActiveModel {
 		dat_file_id: Set(dat_file_id),
@@ -32,3 +49,21 @@ pub async fn create_dat_file_import(
 
 	dat_file_import.save(conn).await?.try_into_model()
 }
+
+pub async fn find_dat_file_import_by_id(
+	id: Uuid,
+	conn: &impl ConnectionTrait,
+) -> Result<Option<dat_file_import::Model>, DbErr> {
+	DatFileImport::find_by_id(id).one(conn).await
+}
+
+pub async fn find_dat_file_imports_by_dat_file_id(
+	dat_file_id: Uuid,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<dat_file_import::Model>, DbErr> {
+	DatFileImport::find()
+		.filter(dat_file_import::Column::DatFileId.eq(dat_file_id))
+		.order_by_desc(dat_file_import::Column::ImportedAt)
+		.all(conn)
+		.await
+}
@@ -0,0 +1,63 @@
+use entity::dat_file_import_change;
+use entity::sea_orm_active_enums::DatFileImportChangeTypeEnum;
+use sea_orm::prelude::Uuid;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder};
+
+#[derive(Debug, Clone)]
+pub struct DatFileImportChangeInput {
+	pub change_type: DatFileImportChangeTypeEnum,
+	pub game_id: Option<Uuid>,
+	pub game_name: String,
+	pub previous_game_name: Option<String>,
+	pub file_name: Option<String>,
+	pub previous_crc: Option<String>,
+	pub crc: Option<String>,
+	pub previous_sha1: Option<String>,
+	pub sha1: Option<String>,
+}
+
+pub async fn insert_dat_file_import_changes(
+	dat_file_import_id: Uuid,
+	changes: Vec<DatFileImportChangeInput>,
+	conn: &impl ConnectionTrait,
+) -> Result<(), DbErr> {
+	if changes.is_empty() {
+		return Ok(());
+	}
+
+	let to_insert = changes
+		.into_iter()
+		.map(|change| dat_file_import_change::ActiveModel {
+			dat_file_import_id: Set(dat_file_import_id),
+			change_type: Set(change.change_type),
+			game_id: Set(change.game_id),
+			game_name: Set(change.game_name),
+			previous_game_name: Set(change.previous_game_name),
+			file_name: Set(change.file_name),
+			previous_crc: Set(change.previous_crc),
+			crc: Set(change.crc),
+			previous_sha1: Set(change.previous_sha1),
+			sha1: Set(change.sha1),
+			..Default::default()
+		})
+		.collect::<Vec<dat_file_import_change::ActiveModel>>();
+
+	dat_file_import_change::Entity::insert_many(to_insert)
+		.exec(conn)
+		.await?;
+
+	Ok(())
+}
+
+pub async fn find_dat_file_import_changes_by_dat_file_import_id(
+	dat_file_import_id: Uuid,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<dat_file_import_change::Model>, DbErr> {
+	dat_file_import_change::Entity::find()
+		.filter(dat_file_import_change::Column::DatFileImportId.eq(dat_file_import_id))
+		.order_by_asc(dat_file_import_change::Column::GameName)
+		.order_by_asc(dat_file_import_change::Column::FileName)
+		.all(conn)
+		.await
+}
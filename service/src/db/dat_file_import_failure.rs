@@ -0,0 +1,33 @@
+use entity::dat_file_import_failure;
+use sea_orm::prelude::Uuid;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DbErr, EntityTrait, QueryOrder, QuerySelect};
+
+pub async fn create_dat_file_import_failure(
+	signature_group_id: Uuid,
+	file_name: &str,
+	md5_hash: &str,
+	error: &str,
+	conn: &impl ConnectionTrait,
+) -> Result<dat_file_import_failure::Model, DbErr> {
+	dat_file_import_failure::ActiveModel {
+		signature_group_id: Set(signature_group_id),
+		file_name: Set(file_name.to_string()),
+		md5_hash: Set(md5_hash.to_string()),
+		error: Set(error.to_string()),
+		..Default::default()
+	}
+	.insert(conn)
+	.await
+}
+
+pub async fn find_latest_dat_file_import_failures(
+	limit: u64,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<dat_file_import_failure::Model>, DbErr> {
+	dat_file_import_failure::Entity::find()
+		.order_by_desc(dat_file_import_failure::Column::FailedAt)
+		.limit(limit)
+		.all(conn)
+		.await
+}
@@ -1,126 +1,574 @@
-use crate::dat::shared::model;
+use crate::model::GameListQuery;
 use ::entity::{
-	game, game::Entity as Game, game_file, game_file::Entity as GameFile,
+	game, game::Entity as Game, game_file, game_file::Entity as GameFile, signature_group,
 	signature_metadata_mapping,
 };
-use entity::sea_orm_active_enums::MatchTypeEnum;
+use entity::sea_orm_active_enums::{GameKindEnum, MatchTypeEnum};
 use entity::{dat_file, dat_file_import, platform};
 use futures_util::future::BoxFuture;
 use sea_orm::prelude::Uuid;
-use sea_orm::sea_query::{Alias, Expr};
+use sea_orm::sea_query::extension::postgres::{PgBinOper, PgExpr};
+use sea_orm::sea_query::{Alias, Expr, Func, NullOrdering, Query};
 use sea_orm::{
-	sea_query::SimpleExpr, ActiveEnum, ActiveModelTrait, ActiveValue::Set, ColumnTrait, DbConn,
-	DbErr, EntityTrait, JoinType, Paginator, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
-	RelationTrait, SelectModel, TryIntoModel,
+	sea_query::SimpleExpr, ActiveEnum, ColumnTrait, Condition, ConnectionTrait, DbConn, DbErr,
+	EntityTrait, JoinType, Order, Paginator, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+	RelationTrait, SelectModel,
 };
+use std::collections::{HashMap, HashSet};
 
-pub async fn insert_game(
-	dat_file_import_id: Uuid,
-	game: model::Game,
-	conn: &DbConn,
-) -> Result<game::Model, DbErr> {
-	let game = game::ActiveModel {
-		dat_file_import_id: Set(dat_file_import_id),
-		signature_group_internal_id: Set(game.id),
-		signature_group_internal_clone_of_id: Set(game.cloneofid),
-		name: Set(game.name),
-		description: Set(game.description),
-		categories: Set(game.category),
-		..Default::default()
-	};
+/// Upper bound for the games returned for a single search, files like an empty save are part of
+/// countless games.
+pub(crate) const MAX_GAME_MATCH_CANDIDATES: u64 = 50;
 
-	game.save(conn).await?.try_into_model()
+/// A game with a file matching a search, together with where it comes from.
+#[derive(Debug, Clone)]
+pub struct GameMatchCandidate {
+	pub game: game::Model,
+	pub dat_file: dat_file::Model,
+	pub signature_group: signature_group::Model,
+	pub platform: platform::Model,
+	pub signature_metadata_mappings: Vec<signature_metadata_mapping::Model>,
 }
 
-pub async fn find_game_by_signature_group_internal_id_and_dat_file_id(
-	signature_group_internal_id: String,
-	dat_file_id: Uuid,
-	conn: &DbConn,
-) -> Result<Option<game::Model>, DbErr> {
+pub async fn find_game_ids_and_names_by_ids(
+	game_ids: Vec<Uuid>,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<(Uuid, String)>, DbErr> {
 	Game::find()
-		.filter(game::Column::SignatureGroupInternalId.eq(signature_group_internal_id))
-		.join(JoinType::InnerJoin, game::Relation::DatFileImport.def())
-		.filter(dat_file_import::Column::DatFileId.eq(dat_file_id))
-		.one(conn)
+		.select_only()
+		.column(game::Column::Id)
+		.column(game::Column::Name)
+		.filter(game::Column::Id.is_in(game_ids))
+		.into_tuple()
+		.all(conn)
+		.await
+}
+
+pub async fn find_games_by_ids(
+	game_ids: Vec<Uuid>,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<game::Model>, DbErr> {
+	Game::find()
+		.filter(game::Column::Id.is_in(game_ids))
+		.all(conn)
 		.await
 }
 
-pub async fn find_game_by_name_and_dat_file_id(
+pub async fn find_games_and_dat_file_ids_by_ids(
+	game_ids: Vec<Uuid>,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<(game::Model, Uuid)>, DbErr> {
+	Game::find()
+		.filter(game::Column::Id.is_in(game_ids))
+		.find_also_related(dat_file_import::Entity)
+		.all(conn)
+		.await
+		.map(|games| {
+			games
+				.into_iter()
+				.filter_map(|(game, dat_file_import)| {
+					dat_file_import.map(|dat_file_import| (game, dat_file_import.dat_file_id))
+				})
+				.collect()
+		})
+}
+
+/// Returns the games of a DAT whose name starts with the prefix, ordered by name.
+pub async fn find_games_by_name_prefix_and_dat_file_id(
+	prefix: &str,
+	dat_file_id: Uuid,
+	include_retired: bool,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<game::Model>, DbErr> {
+	let pattern = format!("{}%", escape_like_pattern(prefix));
+
+	let mut query = Game::find()
+		.filter(game::Column::Name.like(pattern))
+		.join(JoinType::InnerJoin, game::Relation::DatFileImport.def())
+		.filter(dat_file_import::Column::DatFileId.eq(dat_file_id));
+
+	if !include_retired {
+		query = query.filter(game::Column::RetiredAt.is_null());
+	}
+
+	query.order_by_asc(game::Column::Name).all(conn).await
+}
+
+/// Returns the IDs of one page of the games matching the filters, ordered by name, and the number
+/// of matching games on all pages. Pages start at 0.
+pub async fn find_game_ids_page_by_filters(
+	filters: &GameListQuery,
+	page: u64,
+	page_size: u64,
+	conn: &impl ConnectionTrait,
+) -> Result<(Vec<Uuid>, u64), DbErr> {
+	let mut query = Game::find()
+		.select_only()
+		.column(game::Column::Id)
+		.join(JoinType::InnerJoin, game::Relation::DatFileImport.def())
+		.join(
+			JoinType::InnerJoin,
+			dat_file_import::Relation::DatFile.def(),
+		);
+
+	if let Some(platform_id) = filters.platform_id {
+		query = query.filter(dat_file::Column::PlatformId.eq(platform_id));
+	}
+
+	if let Some(dat_file_id) = filters.dat_file_id {
+		query = query.filter(dat_file::Column::Id.eq(dat_file_id));
+	}
+
+	if let Some(signature_group_id) = filters.signature_group_id {
+		query = query.filter(dat_file::Column::SignatureGroupId.eq(signature_group_id));
+	}
+
+	if let Some(category) = &filters.category {
+		query = query.filter(Expr::cust_with_values(
+			r#"$1 = ANY("game"."categories")"#,
+			[category.clone()],
+		));
+	}
+
+	if let Some(match_status) = filters.match_status {
+		query = query.filter(match_status_filter(match_status.into()));
+	}
+
+	if let Some(name) = &filters.name {
+		query = query.filter(
+			Expr::col((game::Entity, game::Column::Name))
+				.ilike(format!("%{}%", escape_like_pattern(name))),
+		);
+	}
+
+	if !filters.include_retired.unwrap_or_default() {
+		query = query.filter(game::Column::RetiredAt.is_null());
+	}
+
+	let paginator = query
+		.order_by_asc(game::Column::Name)
+		.order_by_asc(game::Column::Id)
+		.into_tuple::<Uuid>()
+		.paginate(conn, page_size);
+
+	let total = paginator.num_items().await?;
+	let game_ids = paginator.fetch_page(page).await?;
+
+	Ok((game_ids, total))
+}
+
+/// Games without a mapping have never been matched, so they have the match status none.
+fn match_status_filter(match_type: MatchTypeEnum) -> SimpleExpr {
+	let game_ids_with_match_type = |condition: SimpleExpr| {
+		Query::select()
+			.column(signature_metadata_mapping::Column::GameId)
+			.from(signature_metadata_mapping::Entity)
+			.and_where(signature_metadata_mapping::Column::GameId.is_not_null())
+			.and_where(condition)
+			.to_owned()
+	};
+
+	match match_type {
+		MatchTypeEnum::None => game::Column::Id.not_in_subquery(game_ids_with_match_type(
+			Expr::col(signature_metadata_mapping::Column::MatchType)
+				.ne(MatchTypeEnum::None.as_enum()),
+		)),
+		match_type => game::Column::Id.in_subquery(game_ids_with_match_type(
+			Expr::col(signature_metadata_mapping::Column::MatchType).eq(match_type.as_enum()),
+		)),
+	}
+}
+
+/// Returns the IDs of the games whose name without tags contains a word similar to the name or
+/// any of the words of the full text query, with the word similarity of their name, best match
+/// first. Uses the trigram and full text indexes on clean_game_name.
+pub async fn find_game_ids_by_similar_name(
 	name: &str,
+	full_text_query: Option<&str>,
+	platform_id: Option<Uuid>,
+	include_retired: bool,
+	limit: u64,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<(Uuid, f32)>, DbErr> {
+	let word_similarity = Expr::cust_with_values(
+		r#"word_similarity($1, clean_game_name("game"."name"))"#,
+		[name],
+	);
+	let similar_name = Expr::cust_with_values(r#"$1 <% clean_game_name("game"."name")"#, [name]);
+
+	// a name containing most of the searched words ranks high, even if the words which aren't in
+	// it (e.g. the platform) push its word similarity below the threshold of <%
+	let (filter, rank) = match full_text_query {
+		Some(full_text_query) => (
+			Condition::any()
+				.add(similar_name)
+				.add(Expr::cust_with_values(
+					r#"to_tsvector('simple', clean_game_name("game"."name")) @@ to_tsquery('simple', $1)"#,
+					[full_text_query],
+				)),
+			Expr::cust_with_values(
+				r#"word_similarity($1, clean_game_name("game"."name")) + ts_rank(to_tsvector('simple', clean_game_name("game"."name")), to_tsquery('simple', $2))"#,
+				[name, full_text_query],
+			),
+		),
+		None => (Condition::all().add(similar_name), word_similarity.clone()),
+	};
+
+	let mut query = Game::find()
+		.select_only()
+		.column(game::Column::Id)
+		.expr(word_similarity)
+		.filter(filter);
+
+	if let Some(platform_id) = platform_id {
+		query = query
+			.join(JoinType::InnerJoin, game::Relation::DatFileImport.def())
+			.join(
+				JoinType::InnerJoin,
+				dat_file_import::Relation::DatFile.def(),
+			)
+			.filter(dat_file::Column::PlatformId.eq(platform_id));
+	}
+
+	if !include_retired {
+		query = query.filter(game::Column::RetiredAt.is_null());
+	}
+
+	// names containing more than the searched words are less similar as a whole
+	query
+		.order_by(rank, Order::Desc)
+		.order_by(
+			Expr::cust_with_values(r#"similarity($1, clean_game_name("game"."name"))"#, [name]),
+			Order::Desc,
+		)
+		.order_by_asc(game::Column::Name)
+		.order_by_asc(game::Column::Id)
+		.limit(limit)
+		.into_tuple()
+		.all(conn)
+		.await
+}
+
+/// Returns the clones of a game, ordered by name.
+pub async fn find_game_clones(
+	game_id: Uuid,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<game::Model>, DbErr> {
+	Game::find()
+		.filter(game::Column::CloneOf.eq(game_id))
+		.order_by_asc(game::Column::Name)
+		.all(conn)
+		.await
+}
+
+/// Returns the clones of all given games, ordered by name.
+pub async fn find_games_by_clone_of_ids(
+	game_ids: Vec<Uuid>,
+	include_retired: bool,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<game::Model>, DbErr> {
+	let mut query = Game::find().filter(game::Column::CloneOf.is_in(game_ids));
+
+	if !include_retired {
+		query = query.filter(game::Column::RetiredAt.is_null());
+	}
+
+	query.order_by_asc(game::Column::Name).all(conn).await
+}
+
+/// Escapes the wildcards of LIKE, so the text is matched literally.
+fn escape_like_pattern(text: &str) -> String {
+	text.replace('\\', "\\\\")
+		.replace('%', "\\%")
+		.replace('_', "\\_")
+}
+
+pub async fn find_game_by_signature_group_internal_id_and_dat_file_id(
+	signature_group_internal_id: String,
 	dat_file_id: Uuid,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> Result<Option<game::Model>, DbErr> {
 	Game::find()
-		.filter(game::Column::Name.eq(name))
+		.filter(game::Column::SignatureGroupInternalId.eq(signature_group_internal_id))
 		.join(JoinType::InnerJoin, game::Relation::DatFileImport.def())
 		.filter(dat_file_import::Column::DatFileId.eq(dat_file_id))
 		.one(conn)
 		.await
 }
 
-pub async fn find_game_and_id_mapping_by_md5(
+pub async fn find_game_match_candidates_by_md5(
 	md5: &str,
-	conn: &DbConn,
-) -> Result<Option<(game::Model, Vec<signature_metadata_mapping::Model>)>, DbErr> {
-	find_signature_metadata_mapping_if_exists_by_filter(game_file::Column::Md5.eq(md5), conn).await
+	include_retired: bool,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<GameMatchCandidate>, DbErr> {
+	find_game_match_candidates_by_filter(game_file::Column::Md5.eq(md5), include_retired, conn)
+		.await
 }
 
-pub async fn find_game_and_id_mapping_by_sha1(
+pub async fn find_game_match_candidates_by_sha1(
 	sha1: &str,
-	conn: &DbConn,
-) -> Result<Option<(game::Model, Vec<signature_metadata_mapping::Model>)>, DbErr> {
-	find_signature_metadata_mapping_if_exists_by_filter(game_file::Column::Sha1.eq(sha1), conn)
+	include_retired: bool,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<GameMatchCandidate>, DbErr> {
+	find_game_match_candidates_by_filter(game_file::Column::Sha1.eq(sha1), include_retired, conn)
 		.await
 }
 
-pub async fn find_game_and_id_mapping_by_sha256(
+pub async fn find_game_match_candidates_by_sha256(
 	sha256: &str,
-	conn: &DbConn,
-) -> Result<Option<(game::Model, Vec<signature_metadata_mapping::Model>)>, DbErr> {
-	find_signature_metadata_mapping_if_exists_by_filter(game_file::Column::Sha256.eq(sha256), conn)
+	include_retired: bool,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<GameMatchCandidate>, DbErr> {
+	find_game_match_candidates_by_filter(
+		game_file::Column::Sha256.eq(sha256),
+		include_retired,
+		conn,
+	)
+	.await
+}
+
+pub async fn find_game_match_candidates_by_crc_and_size(
+	crc: &str,
+	size: i64,
+	include_retired: bool,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<GameMatchCandidate>, DbErr> {
+	find_game_match_candidates_by_filter(crc_and_size_filter(crc, size), include_retired, conn)
 		.await
 }
 
-pub async fn find_game_and_id_mapping_by_name_and_size(
+fn crc_and_size_filter(crc: &str, size: i64) -> SimpleExpr {
+	game_file::Column::Crc
+		.eq(crc)
+		.and(game_file::Column::FileSizeInBytes.eq(size))
+}
+
+pub async fn find_game_match_candidates_by_name_and_size(
 	name: &str,
 	size: i64,
-	conn: &DbConn,
-) -> Result<Option<(game::Model, Vec<signature_metadata_mapping::Model>)>, DbErr> {
-	find_signature_metadata_mapping_if_exists_by_filter(
+	include_retired: bool,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<GameMatchCandidate>, DbErr> {
+	find_game_match_candidates_by_filter(
 		game_file::Column::FileName
 			.eq(name)
 			.and(game_file::Column::FileSizeInBytes.eq(size)),
+		include_retired,
+		conn,
+	)
+	.await
+}
+
+/// Only matches games of DATs using the given header detector, as only those contain the size of
+/// the data without the header.
+pub async fn find_game_match_candidates_by_name_and_headerless_size(
+	name: &str,
+	size: i64,
+	header_detector_name: &str,
+	include_retired: bool,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<GameMatchCandidate>, DbErr> {
+	let dat_file_import_ids = Query::select()
+		.column((dat_file_import::Entity, dat_file_import::Column::Id))
+		.from(dat_file_import::Entity)
+		.inner_join(
+			dat_file::Entity,
+			Expr::col((dat_file::Entity, dat_file::Column::Id))
+				.equals((dat_file_import::Entity, dat_file_import::Column::DatFileId)),
+		)
+		.and_where(
+			Expr::expr(Func::lower(Expr::col((
+				dat_file::Entity,
+				dat_file::Column::HeaderDetectorName,
+			))))
+			.eq(header_detector_name.to_lowercase()),
+		)
+		.to_owned();
+
+	find_game_match_candidates_by_filter(
+		game_file::Column::FileName
+			.eq(name)
+			.and(game_file::Column::FileSizeInBytes.eq(size))
+			.and(game::Column::DatFileImportId.in_subquery(dat_file_import_ids)),
+		include_retired,
 		conn,
 	)
 	.await
 }
 
-async fn find_signature_metadata_mapping_if_exists_by_filter(
+pub async fn find_game_match_candidates_by_serial(
+	serial: &str,
+	include_retired: bool,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<GameMatchCandidate>, DbErr> {
+	find_game_match_candidates_by_filter(serial_filter(serial), include_retired, conn).await
+}
+
+/// Returns all files carrying one of the given serials together with their game, active games
+/// first.
+pub async fn find_game_files_and_games_by_serial(
+	serial: &str,
+	include_retired: bool,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<(game_file::Model, game::Model)>, DbErr> {
+	let mut query = GameFile::find()
+		.filter(serial_filter(serial))
+		.find_also_related(Game);
+
+	query = if include_retired {
+		query.order_by_with_nulls(game::Column::RetiredAt, Order::Desc, NullOrdering::First)
+	} else {
+		query.filter(game::Column::RetiredAt.is_null())
+	};
+
+	let game_files = query
+		.order_by_asc(game::Column::Name)
+		.order_by_asc(game_file::Column::FileName)
+		.all(conn)
+		.await?;
+
+	Ok(game_files
+		.into_iter()
+		.filter_map(|(game_file, game)| game.map(|game| (game_file, game)))
+		.collect())
+}
+
+/// The searched serial is normalized by the same database function as the stored ones, so both
+/// can never disagree.
+fn serial_filter(serial: &str) -> SimpleExpr {
+	Expr::col((game_file::Entity, game_file::Column::NormalizedSerials)).binary(
+		PgBinOper::Overlap,
+		Expr::cust_with_values("normalize_serials($1)", [serial]),
+	)
+}
+
+async fn find_game_match_candidates_by_filter(
 	input: SimpleExpr,
-	conn: &DbConn,
-) -> Result<Option<(game::Model, Vec<signature_metadata_mapping::Model>)>, DbErr> {
-	let game_file = GameFile::find()
-		.filter(input)
-		.find_also_related(Game)
-		.one(conn)
+	include_retired: bool,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<GameMatchCandidate>, DbErr> {
+	let matching_game_ids = Query::select()
+		.column((game_file::Entity, game_file::Column::GameId))
+		.from(game_file::Entity)
+		.and_where(input)
+		.to_owned();
+
+	find_game_match_candidates(
+		game::Column::Id.in_subquery(matching_game_ids),
+		include_retired,
+		Some(MAX_GAME_MATCH_CANDIDATES),
+		conn,
+	)
+	.await
+}
+
+/// Matches games whose id is in the list. The ids are bound as a single array, so the amount of
+/// ids isn't limited by the number of bind parameters.
+pub(crate) fn game_id_in(game_ids: Vec<Uuid>) -> SimpleExpr {
+	Expr::cust_with_values(r#""game"."id" = ANY($1)"#, [game_ids])
+}
+
+/// Loads the games matching the condition, ordered from best to worst match: active games before
+/// retired ones, parents before clones, then by name and the game imported first. The order only
+/// depends on the data, so the same search always picks the same game.
+pub(crate) async fn find_game_match_candidates(
+	condition: SimpleExpr,
+	include_retired: bool,
+	limit: Option<u64>,
+	conn: &impl ConnectionTrait,
+) -> Result<Vec<GameMatchCandidate>, DbErr> {
+	let mut query = Game::find().filter(condition);
+
+	query = if include_retired {
+		query.order_by_with_nulls(game::Column::RetiredAt, Order::Desc, NullOrdering::First)
+	} else {
+		query.filter(game::Column::RetiredAt.is_null())
+	};
+
+	let games = query
+		.order_by_with_nulls(game::Column::CloneOf, Order::Asc, NullOrdering::First)
+		.order_by_asc(game::Column::Name)
+		.order_by_asc(game::Column::CreatedAt)
+		.order_by_asc(game::Column::Id)
+		.limit(limit)
+		.find_also_related(dat_file_import::Entity)
+		.all(conn)
 		.await?;
 
-	match game_file {
-		Some((_, Some(game))) => {
-			let signature_metadata_mappings = signature_metadata_mapping::Entity::find()
-				.filter(signature_metadata_mapping::Column::GameId.eq(game.id))
-				.all(conn)
-				.await?;
+	if games.is_empty() {
+		return Ok(Vec::new());
+	}
 
-			Ok(Some((game, signature_metadata_mappings)))
-		}
-		_ => Ok(None),
+	let dat_file_ids: HashSet<Uuid> = games
+		.iter()
+		.filter_map(|(_, dat_file_import)| dat_file_import.as_ref())
+		.map(|dat_file_import| dat_file_import.dat_file_id)
+		.collect();
+
+	let dat_files: HashMap<Uuid, (dat_file::Model, Option<platform::Model>)> =
+		dat_file::Entity::find()
+			.filter(dat_file::Column::Id.is_in(dat_file_ids))
+			.find_also_related(platform::Entity)
+			.all(conn)
+			.await?
+			.into_iter()
+			.map(|(dat_file, platform)| (dat_file.id, (dat_file, platform)))
+			.collect();
+
+	let signature_groups: HashMap<Uuid, signature_group::Model> = signature_group::Entity::find()
+		.filter(
+			signature_group::Column::Id.is_in(
+				dat_files
+					.values()
+					.map(|(dat_file, _)| dat_file.signature_group_id),
+			),
+		)
+		.all(conn)
+		.await?
+		.into_iter()
+		.map(|signature_group| (signature_group.id, signature_group))
+		.collect();
+
+	let game_ids: Vec<Uuid> = games.iter().map(|(game, _)| game.id).collect();
+	let signature_metadata_mappings = signature_metadata_mapping::Entity::find()
+		.filter(Expr::cust_with_values(
+			r#""signature_metadata_mapping"."game_id" = ANY($1)"#,
+			[game_ids],
+		))
+		.all(conn)
+		.await?;
+
+	let mut candidates = Vec::with_capacity(games.len());
+
+	for (game, dat_file_import) in games {
+		let Some((dat_file, Some(platform))) =
+			dat_file_import.and_then(|dat_file_import| dat_files.get(&dat_file_import.dat_file_id))
+		else {
+			continue;
+		};
+		let Some(signature_group) = signature_groups.get(&dat_file.signature_group_id) else {
+			continue;
+		};
+
+		candidates.push(GameMatchCandidate {
+			dat_file: dat_file.clone(),
+			signature_group: signature_group.clone(),
+			platform: platform.clone(),
+			signature_metadata_mappings: signature_metadata_mappings
+				.iter()
+				.filter(|mapping| mapping.game_id == Some(game.id))
+				.cloned()
+				.collect(),
+			game,
+		});
 	}
+
+	Ok(candidates)
 }
 
 pub async fn find_game_parent(
 	game: &game::Model,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> Result<Option<game::Model>, DbErr> {
 	match game.clone_of {
 		Some(clone_of_id) => {
@@ -133,9 +581,24 @@ pub async fn find_game_parent(
 	}
 }
 
+pub async fn find_game_rom_of(
+	game: &game::Model,
+	conn: &impl ConnectionTrait,
+) -> Result<Option<game::Model>, DbErr> {
+	match game.rom_of {
+		Some(rom_of_id) => {
+			Game::find()
+				.filter(game::Column::Id.eq(rom_of_id))
+				.one(conn)
+				.await
+		}
+		None => Ok(None),
+	}
+}
+
 pub async fn find_game_signature_metadata_mapping(
 	game: &game::Model,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> Result<Option<signature_metadata_mapping::Model>, DbErr> {
 	signature_metadata_mapping::Entity::find()
 		.filter(signature_metadata_mapping::Column::GameId.eq(game.id))
@@ -143,7 +606,10 @@ pub async fn find_game_signature_metadata_mapping(
 		.await
 }
 
-pub async fn get_dat_file_id_of_game(game: &game::Model, conn: &DbConn) -> Result<Uuid, DbErr> {
+pub async fn get_dat_file_id_of_game(
+	game: &game::Model,
+	conn: &impl ConnectionTrait,
+) -> Result<Uuid, DbErr> {
 	let dat_file_import = dat_file_import::Entity::find()
 		.filter(dat_file_import::Column::Id.eq(game.dat_file_import_id))
 		.one(conn)
@@ -156,13 +622,17 @@ pub async fn get_dat_file_id_of_game(game: &game::Model, conn: &DbConn) -> Resul
 		)),
 	}
 }
-pub fn get_unpopulated_clone_of_games(
+pub fn get_unpopulated_clone_of_and_rom_of_games(
 	dat_file_id: Uuid,
 	page_size: u64,
 	conn: &DbConn,
-) -> Paginator<DbConn, SelectModel<game::Model>> {
+) -> Paginator<'_, DbConn, SelectModel<game::Model>> {
 	Game::find()
-		.filter(game::Column::SignatureGroupInternalCloneOfId.is_not_null())
+		.filter(
+			Condition::any()
+				.add(game::Column::SignatureGroupInternalCloneOfId.is_not_null())
+				.add(game::Column::SignatureGroupInternalRomOfId.is_not_null()),
+		)
 		.join(JoinType::InnerJoin, game::Relation::DatFileImport.def())
 		.filter(dat_file_import::Column::DatFileId.eq(dat_file_id))
 		.order_by_asc(game::Column::Id)
@@ -209,6 +679,8 @@ fn get_unmatched_games_with_limit<'a>(
 				game::Relation::SignatureMetadataMapping.def(),
 				smm2.clone(),
 			)
+			.filter(game::Column::Kind.eq(GameKindEnum::Game))
+			.filter(game::Column::RetiredAt.is_null())
 			.filter(if clone_of_null {
 				game::Column::CloneOf.is_null()
 			} else {
@@ -3,12 +3,12 @@ use entity::game_file;
 use entity::game_file::ActiveModel;
 use sea_orm::prelude::Uuid;
 use sea_orm::ActiveValue::Set;
-use sea_orm::{ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, QueryFilter};
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter};
 
 pub async fn insert_game_file_bulk(
 	game_files: Vec<RomElement>,
 	game_id: Uuid,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> anyhow::Result<()> {
 	let mut to_insert = Vec::new();
 
@@ -26,7 +26,7 @@ pub async fn insert_game_file_bulk(
 pub async fn insert_game_file(
 	game_file: RomElement,
 	game_id: Uuid,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> anyhow::Result<ActiveModel> {
 	let game_file = get_active_model_from_rom_element(game_id, game_file)?;
 
@@ -35,7 +35,7 @@ pub async fn insert_game_file(
 
 pub async fn get_game_files_from_game_id(
 	game_id: Uuid,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> anyhow::Result<Vec<game_file::Model>> {
 	Ok(game_file::Entity::find()
 		.filter(game_file::Column::GameId.eq(game_id))
@@ -43,25 +43,30 @@ pub async fn get_game_files_from_game_id(
 		.await?)
 }
 
+/// DATs contain the size as string, empty sizes are treated as unknown.
+pub fn parse_file_size(size: Option<String>) -> anyhow::Result<Option<i64>> {
+	match size {
+		Some(size) if !size.is_empty() => Ok(Some(size.parse::<i64>()?)),
+		_ => Ok(None),
+	}
+}
+
+/// DATs differ in the case of CRCs and some drop leading zeros, they are stored as 8 lowercase hex
+/// digits so they can be compared directly.
+pub fn normalize_crc(crc: Option<String>) -> Option<String> {
+	crc.map(|crc| crc.trim().to_lowercase())
+		.filter(|crc| !crc.is_empty())
+		.map(|crc| format!("{:0>8}", crc))
+}
+
 fn get_active_model_from_rom_element(
 	game_id: Uuid,
 	game_file: RomElement,
 ) -> anyhow::Result<ActiveModel> {
-	let file_size = match game_file.size {
-		None => None,
-		Some(inner) => {
-			if inner.is_empty() {
-				None
-			} else {
-				Some(inner.parse::<i64>()?)
-			}
-		}
-	};
-
 	let game_file = ActiveModel {
+		file_size_in_bytes: Set(parse_file_size(game_file.size)?),
 		file_name: Set(game_file.name),
-		file_size_in_bytes: Set(file_size),
-		crc: Set(game_file.crc),
+		crc: Set(normalize_crc(game_file.crc)),
 		md5: Set(game_file.md5),
 		sha1: Set(game_file.sha1),
 		sha256: Set(game_file.sha256),
@@ -72,3 +77,44 @@ fn get_active_model_from_rom_element(
 	};
 	Ok(game_file)
 }
+
+pub async fn get_game_files_from_game_ids(
+	game_ids: Vec<Uuid>,
+	conn: &impl ConnectionTrait,
+) -> anyhow::Result<Vec<game_file::Model>> {
+	Ok(game_file::Entity::find()
+		.filter(game_file::Column::GameId.is_in(game_ids))
+		.all(conn)
+		.await?)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn normalizes_crc_case_and_whitespace() {
+		assert_eq!(
+			normalize_crc(Some(" 46DF91AD ".to_string())).as_deref(),
+			Some("46df91ad")
+		);
+	}
+
+	#[test]
+	fn pads_crc_with_leading_zeros() {
+		assert_eq!(
+			normalize_crc(Some("9D3A".to_string())).as_deref(),
+			Some("00009d3a")
+		);
+		assert_eq!(
+			normalize_crc(Some("0".to_string())).as_deref(),
+			Some("00000000")
+		);
+	}
+
+	#[test]
+	fn drops_empty_crc() {
+		assert_eq!(normalize_crc(Some("  ".to_string())), None);
+		assert_eq!(normalize_crc(None), None);
+	}
+}
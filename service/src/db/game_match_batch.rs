@@ -0,0 +1,258 @@
+use crate::db::game::{
+	find_game_match_candidates, game_id_in, GameMatchCandidate, MAX_GAME_MATCH_CANDIDATES,
+};
+use sea_orm::prelude::Uuid;
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, Statement, Value};
+use std::collections::HashMap;
+
+/// Every query joins the searches of a batch, passed as arrays, with the game files. The position
+/// of a search in the arrays is returned with every matching game.
+const MATCH_BY_SHA256_SQL: &str = r#"
+SELECT DISTINCT search.position, game_file.game_id
+FROM unnest($1::bpchar[]) WITH ORDINALITY AS search(sha256, position)
+JOIN game_file ON game_file.sha256 = search.sha256
+"#;
+
+const MATCH_BY_SHA1_SQL: &str = r#"
+SELECT DISTINCT search.position, game_file.game_id
+FROM unnest($1::bpchar[]) WITH ORDINALITY AS search(sha1, position)
+JOIN game_file ON game_file.sha1 = search.sha1
+"#;
+
+const MATCH_BY_MD5_SQL: &str = r#"
+SELECT DISTINCT search.position, game_file.game_id
+FROM unnest($1::bpchar[]) WITH ORDINALITY AS search(md5, position)
+JOIN game_file ON game_file.md5 = search.md5
+"#;
+
+const MATCH_BY_CRC_AND_SIZE_SQL: &str = r#"
+SELECT DISTINCT search.position, game_file.game_id
+FROM unnest($1::text[], $2::bigint[]) WITH ORDINALITY AS search(crc, size, position)
+JOIN game_file ON game_file.crc = search.crc AND game_file.file_size_in_bytes = search.size
+"#;
+
+const MATCH_BY_SERIAL_SQL: &str = r#"
+SELECT DISTINCT search.position, game_file.game_id
+FROM unnest($1::text[]) WITH ORDINALITY AS search(serial, position)
+JOIN game_file ON game_file.normalized_serials && normalize_serials(search.serial)
+"#;
+
+const MATCH_BY_NAME_AND_SIZE_SQL: &str = r#"
+SELECT DISTINCT search.position, game_file.game_id
+FROM unnest($1::text[], $2::bigint[]) WITH ORDINALITY AS search(file_name, size, position)
+JOIN game_file ON game_file.file_name = search.file_name
+	AND game_file.file_size_in_bytes = search.size
+"#;
+
+/// Only games of DATs using the header detector of the search contain the size of the data without
+/// the header.
+const MATCH_BY_NAME_AND_HEADERLESS_SIZE_SQL: &str = r#"
+SELECT DISTINCT search.position, game_file.game_id
+FROM unnest($1::text[], $2::bigint[], $3::text[])
+	WITH ORDINALITY AS search(file_name, size, header_detector_name, position)
+JOIN game_file ON game_file.file_name = search.file_name
+	AND game_file.file_size_in_bytes = search.size
+JOIN game ON game.id = game_file.game_id
+JOIN dat_file_import ON dat_file_import.id = game.dat_file_import_id
+JOIN dat_file ON dat_file.id = dat_file_import.dat_file_id
+WHERE lower(dat_file.header_detector_name) = lower(search.header_detector_name)
+"#;
+
+/// Keeps the best matches of every search, in the same order as a single search, so common files
+/// (e.g. empty files or a BIOS in many DATs) can't load more than the candidates of every search.
+fn limit_matches_per_search(match_sql: &str) -> String {
+	format!(
+		r#"
+WITH matches AS ({})
+SELECT ranked.position, ranked.game_id
+FROM (
+	SELECT matches.position, matches.game_id, row_number() OVER (
+		PARTITION BY matches.position
+		ORDER BY game.retired_at DESC NULLS FIRST, game.clone_of ASC NULLS FIRST, game.name,
+			game.created_at, game.id
+	) AS rank
+	FROM matches
+	JOIN game ON game.id = matches.game_id
+) AS ranked
+WHERE ranked.rank <= {}
+"#,
+		match_sql, MAX_GAME_MATCH_CANDIDATES
+	)
+}
+
+/// Candidates of the searches of a batch, keyed by the index of the search in the batch. Retired
+/// games are always included, as whether they are wanted can differ between the searches.
+pub type BatchGameMatchCandidates = HashMap<usize, Vec<GameMatchCandidate>>;
+
+pub async fn find_batch_game_match_candidates_by_sha256(
+	searches: Vec<(usize, String)>,
+	conn: &impl ConnectionTrait,
+) -> Result<BatchGameMatchCandidates, DbErr> {
+	let (indexes, sha256s): (Vec<usize>, Vec<String>) = searches.into_iter().unzip();
+
+	find_batch_game_match_candidates(indexes, MATCH_BY_SHA256_SQL, vec![sha256s.into()], conn).await
+}
+
+pub async fn find_batch_game_match_candidates_by_sha1(
+	searches: Vec<(usize, String)>,
+	conn: &impl ConnectionTrait,
+) -> Result<BatchGameMatchCandidates, DbErr> {
+	let (indexes, sha1s): (Vec<usize>, Vec<String>) = searches.into_iter().unzip();
+
+	find_batch_game_match_candidates(indexes, MATCH_BY_SHA1_SQL, vec![sha1s.into()], conn).await
+}
+
+pub async fn find_batch_game_match_candidates_by_md5(
+	searches: Vec<(usize, String)>,
+	conn: &impl ConnectionTrait,
+) -> Result<BatchGameMatchCandidates, DbErr> {
+	let (indexes, md5s): (Vec<usize>, Vec<String>) = searches.into_iter().unzip();
+
+	find_batch_game_match_candidates(indexes, MATCH_BY_MD5_SQL, vec![md5s.into()], conn).await
+}
+
+pub async fn find_batch_game_match_candidates_by_crc_and_size(
+	searches: Vec<(usize, String, i64)>,
+	conn: &impl ConnectionTrait,
+) -> Result<BatchGameMatchCandidates, DbErr> {
+	let mut indexes = Vec::with_capacity(searches.len());
+	let mut crcs = Vec::with_capacity(searches.len());
+	let mut sizes = Vec::with_capacity(searches.len());
+
+	for (index, crc, size) in searches {
+		indexes.push(index);
+		crcs.push(crc);
+		sizes.push(size);
+	}
+
+	find_batch_game_match_candidates(
+		indexes,
+		MATCH_BY_CRC_AND_SIZE_SQL,
+		vec![crcs.into(), sizes.into()],
+		conn,
+	)
+	.await
+}
+
+pub async fn find_batch_game_match_candidates_by_serial(
+	searches: Vec<(usize, String)>,
+	conn: &impl ConnectionTrait,
+) -> Result<BatchGameMatchCandidates, DbErr> {
+	let (indexes, serials): (Vec<usize>, Vec<String>) = searches.into_iter().unzip();
+
+	find_batch_game_match_candidates(indexes, MATCH_BY_SERIAL_SQL, vec![serials.into()], conn).await
+}
+
+pub async fn find_batch_game_match_candidates_by_name_and_size(
+	searches: Vec<(usize, String, i64)>,
+	conn: &impl ConnectionTrait,
+) -> Result<BatchGameMatchCandidates, DbErr> {
+	let mut indexes = Vec::with_capacity(searches.len());
+	let mut names = Vec::with_capacity(searches.len());
+	let mut sizes = Vec::with_capacity(searches.len());
+
+	for (index, name, size) in searches {
+		indexes.push(index);
+		names.push(name);
+		sizes.push(size);
+	}
+
+	find_batch_game_match_candidates(
+		indexes,
+		MATCH_BY_NAME_AND_SIZE_SQL,
+		vec![names.into(), sizes.into()],
+		conn,
+	)
+	.await
+}
+
+/// Searches consist of the file name, the size without the header and the header detector name.
+pub async fn find_batch_game_match_candidates_by_name_and_headerless_size(
+	searches: Vec<(usize, String, i64, String)>,
+	conn: &impl ConnectionTrait,
+) -> Result<BatchGameMatchCandidates, DbErr> {
+	let mut indexes = Vec::with_capacity(searches.len());
+	let mut names = Vec::with_capacity(searches.len());
+	let mut sizes = Vec::with_capacity(searches.len());
+	let mut header_detector_names = Vec::with_capacity(searches.len());
+
+	for (index, name, size, header_detector_name) in searches {
+		indexes.push(index);
+		names.push(name);
+		sizes.push(size);
+		header_detector_names.push(header_detector_name);
+	}
+
+	find_batch_game_match_candidates(
+		indexes,
+		MATCH_BY_NAME_AND_HEADERLESS_SIZE_SQL,
+		vec![names.into(), sizes.into(), header_detector_names.into()],
+		conn,
+	)
+	.await
+}
+
+/// Runs the match query and loads the candidates of all matched games at once, every search gets
+/// its candidates in the same order as a single search would.
+async fn find_batch_game_match_candidates(
+	indexes: Vec<usize>,
+	sql: &str,
+	values: Vec<Value>,
+	conn: &impl ConnectionTrait,
+) -> Result<BatchGameMatchCandidates, DbErr> {
+	if indexes.is_empty() {
+		return Ok(HashMap::new());
+	}
+
+	let rows = conn
+		.query_all(Statement::from_sql_and_values(
+			DbBackend::Postgres,
+			limit_matches_per_search(sql),
+			values,
+		))
+		.await?;
+
+	let mut indexes_by_game_id: HashMap<Uuid, Vec<usize>> = HashMap::new();
+
+	for row in rows {
+		let position: i64 = row.try_get("", "position")?;
+		let game_id: Uuid = row.try_get("", "game_id")?;
+
+		// positions of WITH ORDINALITY start at 1
+		if let Some(index) = usize::try_from(position - 1)
+			.ok()
+			.and_then(|position| indexes.get(position))
+		{
+			indexes_by_game_id.entry(game_id).or_default().push(*index);
+		}
+	}
+
+	if indexes_by_game_id.is_empty() {
+		return Ok(HashMap::new());
+	}
+
+	let candidates = find_game_match_candidates(
+		game_id_in(indexes_by_game_id.keys().copied().collect()),
+		true,
+		None,
+		conn,
+	)
+	.await?;
+
+	let mut candidates_by_index: BatchGameMatchCandidates = HashMap::new();
+
+	for candidate in candidates {
+		for index in indexes_by_game_id
+			.get(&candidate.game.id)
+			.into_iter()
+			.flatten()
+		{
+			candidates_by_index
+				.entry(*index)
+				.or_default()
+				.push(candidate.clone());
+		}
+	}
+
+	Ok(candidates_by_index)
+}
@@ -0,0 +1,360 @@
+use crate::dat::shared::model;
+use crate::db::game_file::{normalize_crc, parse_file_size};
+use entity::game_file;
+use entity::sea_orm_active_enums::GameKindEnum;
+use sea_orm::prelude::Uuid;
+use sea_orm::{ActiveEnum, ConnectionTrait, DbBackend, DbErr, EntityTrait, QueryResult, Statement};
+use serde::Serialize;
+
+/// The staging tables only live until the import transaction ends, they are the source of the
+/// set-based merge into `game` and `game_file`.
+const CREATE_STAGING_TABLES_SQL: &str = r#"
+CREATE TEMPORARY TABLE staging_game (
+	name text NOT NULL,
+	description text,
+	signature_group_internal_id text,
+	signature_group_internal_clone_of_id text,
+	signature_group_internal_rom_of_id text,
+	categories text[],
+	kind game_kind_enum NOT NULL
+) ON COMMIT DROP;
+
+CREATE TEMPORARY TABLE staging_game_file (
+	game_name text NOT NULL,
+	file_name varchar NOT NULL,
+	file_size_in_bytes bigint,
+	crc varchar,
+	md5 char(32),
+	sha1 char(40),
+	sha256 char(64),
+	status varchar,
+	serial varchar
+) ON COMMIT DROP;
+"#;
+
+const INSERT_STAGED_GAMES_SQL: &str = r#"
+INSERT INTO staging_game
+SELECT name, description, signature_group_internal_id, signature_group_internal_clone_of_id,
+	signature_group_internal_rom_of_id, categories, kind::game_kind_enum
+FROM json_to_recordset($1::json) AS staged (
+	name text,
+	description text,
+	signature_group_internal_id text,
+	signature_group_internal_clone_of_id text,
+	signature_group_internal_rom_of_id text,
+	categories text[],
+	kind text
+)
+"#;
+
+const INSERT_STAGED_GAME_FILES_SQL: &str = r#"
+INSERT INTO staging_game_file
+SELECT game_name, file_name, file_size_in_bytes, crc, md5, sha1, sha256, status, serial
+FROM json_to_recordset($1::json) AS staged (
+	game_name text,
+	file_name text,
+	file_size_in_bytes bigint,
+	crc text,
+	md5 text,
+	sha1 text,
+	sha256 text,
+	status text,
+	serial text
+)
+"#;
+
+/// Temporary tables have no statistics, without them the merge plans are terrible.
+const ANALYZE_STAGING_TABLES_SQL: &str = r#"
+CREATE INDEX ON staging_game (name);
+CREATE INDEX ON staging_game_file (game_name);
+ANALYZE staging_game;
+ANALYZE staging_game_file;
+"#;
+
+/// Games of the dat file which are part of the staged DAT again.
+const STAGE_EXISTING_GAMES_SQL: &str = r#"
+CREATE TEMPORARY TABLE staging_existing_game ON COMMIT DROP AS
+SELECT DISTINCT ON (game.name) game.id, game.name, game.retired_at IS NOT NULL AS retired,
+	staging_game.kind
+FROM game
+JOIN dat_file_import ON dat_file_import.id = game.dat_file_import_id
+JOIN staging_game ON staging_game.name = game.name
+WHERE dat_file_import.dat_file_id = $1
+ORDER BY game.name, game.created_at
+"#;
+
+const REACTIVATE_GAMES_SQL: &str = r#"
+UPDATE game
+SET retired_at = NULL, retired_by_dat_file_import_id = NULL, kind = staging_existing_game.kind
+FROM staging_existing_game
+WHERE game.id = staging_existing_game.id AND staging_existing_game.retired
+RETURNING game.id, game.name
+"#;
+
+const UPDATE_GAME_KINDS_SQL: &str = r#"
+UPDATE game
+SET kind = staging_existing_game.kind
+FROM staging_existing_game
+WHERE game.id = staging_existing_game.id
+	AND NOT staging_existing_game.retired
+	AND game.kind <> staging_existing_game.kind
+"#;
+
+const DELETE_REMOVED_GAME_FILES_SQL: &str = r#"
+DELETE FROM game_file
+USING staging_existing_game
+WHERE game_file.game_id = staging_existing_game.id
+	AND NOT EXISTS (
+		SELECT 1 FROM staging_game_file
+		WHERE staging_game_file.game_name = staging_existing_game.name
+			AND staging_game_file.file_name = game_file.file_name
+			AND staging_game_file.file_size_in_bytes IS NOT DISTINCT FROM game_file.file_size_in_bytes
+			AND staging_game_file.crc IS NOT DISTINCT FROM game_file.crc
+			AND staging_game_file.md5 IS NOT DISTINCT FROM game_file.md5
+			AND staging_game_file.sha1 IS NOT DISTINCT FROM game_file.sha1
+			AND staging_game_file.sha256 IS NOT DISTINCT FROM game_file.sha256
+	)
+RETURNING game_file.*
+"#;
+
+const INSERT_ADDED_GAME_FILES_SQL: &str = r#"
+INSERT INTO game_file (game_id, file_name, file_size_in_bytes, crc, md5, sha1, sha256, status, serial)
+SELECT staging_existing_game.id, staging_game_file.file_name, staging_game_file.file_size_in_bytes,
+	staging_game_file.crc, staging_game_file.md5, staging_game_file.sha1, staging_game_file.sha256,
+	staging_game_file.status, staging_game_file.serial
+FROM staging_game_file
+JOIN staging_existing_game ON staging_existing_game.name = staging_game_file.game_name
+WHERE NOT EXISTS (
+	SELECT 1 FROM game_file
+	WHERE game_file.game_id = staging_existing_game.id
+		AND game_file.file_name = staging_game_file.file_name
+		AND game_file.file_size_in_bytes IS NOT DISTINCT FROM staging_game_file.file_size_in_bytes
+		AND game_file.crc IS NOT DISTINCT FROM staging_game_file.crc
+		AND game_file.md5 IS NOT DISTINCT FROM staging_game_file.md5
+		AND game_file.sha1 IS NOT DISTINCT FROM staging_game_file.sha1
+		AND game_file.sha256 IS NOT DISTINCT FROM staging_game_file.sha256
+)
+RETURNING *
+"#;
+
+const INSERT_NEW_GAMES_SQL: &str = r#"
+INSERT INTO game (dat_file_import_id, signature_group_internal_id, signature_group_internal_clone_of_id,
+	signature_group_internal_rom_of_id, name, description, categories, kind)
+SELECT $1, signature_group_internal_id, signature_group_internal_clone_of_id,
+	signature_group_internal_rom_of_id, name, description, categories, kind
+FROM staging_game
+WHERE NOT EXISTS (
+	SELECT 1 FROM staging_existing_game WHERE staging_existing_game.name = staging_game.name
+)
+RETURNING id, name
+"#;
+
+const INSERT_NEW_GAME_FILES_SQL: &str = r#"
+INSERT INTO game_file (game_id, file_name, file_size_in_bytes, crc, md5, sha1, sha256, status, serial)
+SELECT game.id, staging_game_file.file_name, staging_game_file.file_size_in_bytes,
+	staging_game_file.crc, staging_game_file.md5, staging_game_file.sha1, staging_game_file.sha256,
+	staging_game_file.status, staging_game_file.serial
+FROM staging_game_file
+JOIN game ON game.name = staging_game_file.game_name
+WHERE game.dat_file_import_id = $1
+"#;
+
+const RETIRE_MISSING_GAMES_SQL: &str = r#"
+UPDATE game
+SET retired_at = CURRENT_TIMESTAMP, retired_by_dat_file_import_id = $2
+FROM dat_file_import
+WHERE dat_file_import.id = game.dat_file_import_id
+	AND dat_file_import.dat_file_id = $1
+	AND game.retired_at IS NULL
+	AND NOT EXISTS (SELECT 1 FROM staging_game WHERE staging_game.name = game.name)
+RETURNING game.id, game.name
+"#;
+
+#[derive(Debug, Serialize)]
+struct StagedGame {
+	name: String,
+	description: Option<String>,
+	signature_group_internal_id: Option<String>,
+	signature_group_internal_clone_of_id: Option<String>,
+	signature_group_internal_rom_of_id: Option<String>,
+	categories: Option<Vec<String>>,
+	kind: String,
+}
+
+#[derive(Debug, Serialize)]
+struct StagedGameFile {
+	game_name: String,
+	file_name: String,
+	file_size_in_bytes: Option<i64>,
+	crc: Option<String>,
+	md5: Option<String>,
+	sha1: Option<String>,
+	sha256: Option<String>,
+	status: Option<String>,
+	serial: Option<String>,
+}
+
+/// Games and files of a DAT which are written to the staging tables in one statement each.
+#[derive(Debug, Default)]
+pub struct GameStagingBatch {
+	games: Vec<StagedGame>,
+	files: Vec<StagedGameFile>,
+}
+
+impl GameStagingBatch {
+	pub fn push(&mut self, game: model::Game, kind: GameKindEnum) -> anyhow::Result<()> {
+		for rom in game.rom {
+			self.files.push(StagedGameFile {
+				game_name: game.name.clone(),
+				file_name: rom.name,
+				file_size_in_bytes: parse_file_size(rom.size)?,
+				crc: normalize_crc(rom.crc),
+				md5: rom.md5,
+				sha1: rom.sha1,
+				sha256: rom.sha256,
+				status: rom.status.map(|status| status.to_string()),
+				serial: rom.serial,
+			});
+		}
+
+		self.games.push(StagedGame {
+			name: game.name,
+			description: game.description,
+			signature_group_internal_id: game.id,
+			signature_group_internal_clone_of_id: game.cloneofid,
+			signature_group_internal_rom_of_id: game.romof,
+			categories: game.category,
+			kind: kind.to_value(),
+		});
+
+		Ok(())
+	}
+
+	pub fn len(&self) -> usize {
+		self.games.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.games.is_empty()
+	}
+}
+
+/// What changed when merging the staged games into the games of a dat file.
+pub struct GameMergeResult {
+	/// Games which are new or were retired before, only collected if requested.
+	pub added_games: Vec<(Uuid, String)>,
+
+	/// Games which are no longer part of the dat file.
+	pub retired_games: Vec<(Uuid, String)>,
+
+	/// Files of games which were part of the dat file before and are not anymore.
+	pub removed_files: Vec<game_file::Model>,
+
+	/// Files which were added to games which were part of the dat file before.
+	pub added_files: Vec<game_file::Model>,
+}
+
+pub async fn create_game_staging_tables(conn: &impl ConnectionTrait) -> Result<(), DbErr> {
+	conn.execute_unprepared(CREATE_STAGING_TABLES_SQL).await?;
+
+	Ok(())
+}
+
+pub async fn insert_game_staging_batch(
+	batch: GameStagingBatch,
+	conn: &impl ConnectionTrait,
+) -> anyhow::Result<()> {
+	if batch.is_empty() {
+		return Ok(());
+	}
+
+	conn.execute(statement(
+		INSERT_STAGED_GAMES_SQL,
+		vec![serde_json::to_string(&batch.games)?.into()],
+	))
+	.await?;
+
+	if !batch.files.is_empty() {
+		conn.execute(statement(
+			INSERT_STAGED_GAME_FILES_SQL,
+			vec![serde_json::to_string(&batch.files)?.into()],
+		))
+		.await?;
+	}
+
+	Ok(())
+}
+
+/// Merges the staged games into the games of the dat file: known games are updated and get their
+/// files replaced where hashes changed, new games are inserted and games missing from the staged
+/// DAT are retired.
+pub async fn merge_staged_games(
+	dat_file_id: Uuid,
+	dat_file_import_id: Uuid,
+	collect_added_games: bool,
+	conn: &impl ConnectionTrait,
+) -> Result<GameMergeResult, DbErr> {
+	conn.execute_unprepared(ANALYZE_STAGING_TABLES_SQL).await?;
+	conn.execute(statement(
+		STAGE_EXISTING_GAMES_SQL,
+		vec![dat_file_id.into()],
+	))
+	.await?;
+
+	let mut added_games = ids_and_names(
+		conn.query_all(statement(REACTIVATE_GAMES_SQL, vec![]))
+			.await?,
+	)?;
+
+	conn.execute(statement(UPDATE_GAME_KINDS_SQL, vec![]))
+		.await?;
+
+	let removed_files = game_file::Entity::find()
+		.from_raw_sql(statement(DELETE_REMOVED_GAME_FILES_SQL, vec![]))
+		.all(conn)
+		.await?;
+
+	let added_files = game_file::Entity::find()
+		.from_raw_sql(statement(INSERT_ADDED_GAME_FILES_SQL, vec![]))
+		.all(conn)
+		.await?;
+
+	let insert_new_games = statement(INSERT_NEW_GAMES_SQL, vec![dat_file_import_id.into()]);
+
+	if collect_added_games {
+		added_games.extend(ids_and_names(conn.query_all(insert_new_games).await?)?);
+	} else {
+		conn.execute(insert_new_games).await?;
+	}
+
+	conn.execute(statement(
+		INSERT_NEW_GAME_FILES_SQL,
+		vec![dat_file_import_id.into()],
+	))
+	.await?;
+
+	let retired_games = ids_and_names(
+		conn.query_all(statement(
+			RETIRE_MISSING_GAMES_SQL,
+			vec![dat_file_id.into(), dat_file_import_id.into()],
+		))
+		.await?,
+	)?;
+
+	Ok(GameMergeResult {
+		added_games,
+		retired_games,
+		removed_files,
+		added_files,
+	})
+}
+
+fn statement(sql: &str, values: Vec<sea_orm::Value>) -> Statement {
+	Statement::from_sql_and_values(DbBackend::Postgres, sql, values)
+}
+
+fn ids_and_names(rows: Vec<QueryResult>) -> Result<Vec<(Uuid, String)>, DbErr> {
+	rows.into_iter()
+		.map(|row| Ok((row.try_get("", "id")?, row.try_get("", "name")?)))
+		.collect()
+}
@@ -3,8 +3,12 @@ pub mod company;
 pub mod constants;
 pub mod dat_file;
 pub mod dat_file_import;
+pub mod dat_file_import_change;
+pub mod dat_file_import_failure;
 pub mod game;
 pub mod game_file;
+pub mod game_match_batch;
+pub mod game_staging;
 pub mod platform;
 pub mod signature_group;
 pub mod signature_metadata_mapping;
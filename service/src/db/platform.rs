@@ -5,13 +5,13 @@ use entity::{company, dat_file, dat_file_import, game, platform, signature_metad
 use sea_orm::prelude::Uuid;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-	ActiveModelTrait, ColumnTrait, DbConn, DbErr, EntityTrait, JoinType, LoaderTrait, ModelTrait,
-	QueryFilter, QueryOrder, QuerySelect, RelationTrait, TryIntoModel,
+	ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, JoinType, LoaderTrait,
+	ModelTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait, TryIntoModel,
 };
 
 pub async fn get_by_id_and_join_company_and_signature_metadata_mappings(
 	id: Uuid,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> Result<
 	Option<(
 		platform::Model,
@@ -40,7 +40,7 @@ pub async fn get_by_id_and_join_company_and_signature_metadata_mappings(
 }
 
 pub async fn find_all_and_join_company_and_signature_metadata_mappings(
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> Result<
 	Vec<(
 		platform::Model,
@@ -96,7 +96,7 @@ pub async fn find_all_and_join_company_and_signature_metadata_mappings(
 pub async fn create_or_find_platform_by_name(
 	name: &str,
 	company_id: Option<Uuid>,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> Result<platform::Model, DbErr> {
 	let platform = Platform::find()
 		.filter(platform::Column::Name.eq(name))
@@ -120,7 +120,7 @@ pub async fn create_or_find_platform_by_name(
 
 pub async fn get_unmatched_platforms_with_limit(
 	limit: u64,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> anyhow::Result<Option<Vec<platform::Model>>> {
 	let res = Platform::find()
 		.left_join(signature_metadata_mapping::Entity)
@@ -143,7 +143,7 @@ pub async fn get_unmatched_platforms_with_limit(
 
 pub async fn find_platform_of_game(
 	game_id: Uuid,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> Result<Option<platform::Model>, DbErr> {
 	Platform::find()
 		.join(JoinType::InnerJoin, platform::Relation::DatFile.def())
@@ -156,7 +156,7 @@ pub async fn find_platform_of_game(
 
 pub async fn find_related_signature_metadata_mapping(
 	model: &platform::Model,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> Result<Option<signature_metadata_mapping::Model>, DbErr> {
 	model
 		.find_related(signature_metadata_mapping::Entity)
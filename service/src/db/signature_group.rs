@@ -1,13 +1,32 @@
 use entity::signature_group;
 use entity::signature_group::Model as SignatureGroup;
-use sea_orm::{ColumnTrait, DbConn, DbErr, EntityTrait, QueryFilter};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+	ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, TryIntoModel,
+};
 
 pub async fn find_signature_group_by_name(
 	name: &str,
-	conn: &DbConn,
+	conn: &impl ConnectionTrait,
 ) -> Result<Option<SignatureGroup>, DbErr> {
 	signature_group::Entity::find()
 		.filter(signature_group::Column::Name.eq(name))
 		.one(conn)
 		.await
 }
+
+pub async fn create_or_find_signature_group_by_name(
+	name: &str,
+	conn: &impl ConnectionTrait,
+) -> Result<SignatureGroup, DbErr> {
+	if let Some(signature_group) = find_signature_group_by_name(name, conn).await? {
+		Ok(signature_group)
+	} else {
+		let signature_group = signature_group::ActiveModel {
+			name: Set(name.to_string()),
+			..Default::default()
+		};
+
+		signature_group.save(conn).await?.try_into_model()
+	}
+}
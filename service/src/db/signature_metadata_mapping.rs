@@ -8,7 +8,8 @@ use entity::signature_metadata_mapping;
 use sea_orm::prelude::Uuid;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-	ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, IntoActiveModel, QueryFilter, TryIntoModel,
+	ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, IntoActiveModel, QueryFilter,
+	TryIntoModel,
 };
 
 #[derive(Debug, Clone, Builder)]
@@ -35,7 +36,7 @@ pub struct SignatureMetadataMappingInput {
 
 pub async fn create_or_update_signature_metadata_mapping(
 	input: SignatureMetadataMappingInput,
-	db_conn: &DbConn,
+	db_conn: &impl ConnectionTrait,
 ) -> anyhow::Result<signature_metadata_mapping::Model> {
 	let signature_metadata_mapping = signature_metadata_mapping::Entity::find()
 		.filter(signature_metadata_mapping::Column::PlatformId.eq_null(input.platform_id))
@@ -68,3 +69,13 @@ pub async fn create_or_update_signature_metadata_mapping(
 
 	Ok(active_model.try_into_model()?)
 }
+
+pub async fn find_signature_metadata_mappings_by_game_ids(
+	game_ids: Vec<Uuid>,
+	conn: &impl ConnectionTrait,
+) -> anyhow::Result<Vec<signature_metadata_mapping::Model>> {
+	Ok(signature_metadata_mapping::Entity::find()
+		.filter(signature_metadata_mapping::Column::GameId.is_in(game_ids))
+		.all(conn)
+		.await?)
+}
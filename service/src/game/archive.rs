@@ -0,0 +1,179 @@
+use crate::archive::{
+	extract_archive_to_directory_with_limits, is_archive, read_archive_listing, ArchiveListing,
+	ExtractionLimits,
+};
+use crate::fs::read_files_recursive;
+use crate::game::set::{match_set, SetEntry};
+use crate::hash::{hash_file_without_header, FileHashes};
+use crate::header::DetectedHeader;
+use crate::model::{
+	ArchiveEntriesSearch, ArchiveEntryMatch, ArchiveIdentifyResponse, GameFileMatchSearch,
+};
+use sea_orm::DbConn;
+use std::path::Path;
+use tokio::task;
+
+/// An entry of an uploaded archive, hashed by the server.
+#[derive(Debug, Clone)]
+pub struct HashedArchiveEntry {
+	/// The path of the entry in the archive.
+	pub name: String,
+
+	pub hashes: FileHashes,
+
+	/// If a header was detected, the header and the hashes of the data after it.
+	pub header: Option<(DetectedHeader, FileHashes)>,
+}
+
+/// Whether the file name has the extension of an archive which can be extracted.
+pub fn is_supported_archive(file_name: &str) -> bool {
+	is_archive(Path::new(file_name))
+}
+
+/// Reads the number of files in an uploaded archive and their size, without extracting it.
+pub async fn list_archive_entries(archive: &Path) -> anyhow::Result<ArchiveListing> {
+	let archive_owned = archive.to_owned();
+
+	task::spawn_blocking(move || read_archive_listing(&archive_owned)).await?
+}
+
+/// Extracts an archive into `out_dir` and hashes all files in it, sorted by their path. Archives in
+/// the archive are not extracted, they are files to identify like any other.
+pub async fn hash_archive_entries(
+	archive: &Path,
+	out_dir: &Path,
+	max_size: u64,
+	max_entries: usize,
+) -> anyhow::Result<Vec<HashedArchiveEntry>> {
+	let limits = ExtractionLimits {
+		max_size,
+		max_entries,
+		extract_nested: false,
+	};
+
+	let archive_owned = archive.to_owned();
+	let out_dir_owned = out_dir.to_owned();
+	task::spawn_blocking(move || {
+		extract_archive_to_directory_with_limits(&archive_owned, &out_dir_owned, limits)
+	})
+	.await??;
+
+	let mut files = read_files_recursive(out_dir).await?;
+	files.sort();
+
+	let mut entries = Vec::with_capacity(files.len());
+
+	for file in files {
+		let (hashes, header) = hash_file_without_header(&file).await?;
+
+		entries.push(HashedArchiveEntry {
+			name: file
+				.strip_prefix(out_dir)
+				.unwrap_or(&file)
+				.to_string_lossy()
+				.replace('\\', "/"),
+			hashes,
+			header,
+		});
+	}
+
+	Ok(entries)
+}
+
+/// Identifies the entries of an uploaded archive. Entries with a header are matched with the
+/// hashes of the data after it, which is what DATs of headered formats contain.
+pub async fn identify_hashed_archive_entries(
+	entries: Vec<HashedArchiveEntry>,
+	include_retired: Option<bool>,
+	conn: &DbConn,
+) -> anyhow::Result<ArchiveIdentifyResponse> {
+	let set_entries: Vec<SetEntry> = entries
+		.iter()
+		.map(|entry| {
+			let hashes = entry
+				.header
+				.as_ref()
+				.map(|(_, hashes)| hashes)
+				.unwrap_or(&entry.hashes);
+
+			SetEntry {
+				name: entry.name.clone(),
+				search: GameFileMatchSearch {
+					file_name: entry_file_name(&entry.name),
+					file_size: entry.hashes.size as i64,
+					md5: Some(hashes.md5.clone()),
+					sha1: Some(hashes.sha1.clone()),
+					sha256: Some(hashes.sha256.clone()),
+					crc: Some(hashes.crc.clone()),
+					include_retired,
+					header: None,
+					serial: None,
+				},
+				header: entry.header.as_ref().map(|(header, _)| header.clone()),
+			}
+		})
+		.collect();
+
+	let (results, sets) = match_set(&set_entries, conn).await?;
+
+	Ok(ArchiveIdentifyResponse {
+		entries: entries
+			.into_iter()
+			.zip(results)
+			.map(|(entry, game_match)| ArchiveEntryMatch {
+				name: entry.name,
+				hashes: Some(entry.hashes.into()),
+				headerless_hashes: entry.header.map(|(_, hashes)| hashes.into()),
+				game_match,
+			})
+			.collect(),
+		sets,
+	})
+}
+
+/// Identifies the entries of an archive by the CRC32, size and name from its central directory.
+pub async fn identify_archive_entries(
+	search: ArchiveEntriesSearch,
+	conn: &DbConn,
+) -> anyhow::Result<ArchiveIdentifyResponse> {
+	let set_entries: Vec<SetEntry> = search
+		.entries
+		.into_iter()
+		.map(|entry| SetEntry {
+			search: GameFileMatchSearch {
+				file_name: entry_file_name(&entry.name),
+				file_size: entry.size,
+				md5: None,
+				sha1: None,
+				sha256: None,
+				crc: Some(entry.crc),
+				include_retired: search.include_retired,
+				header: None,
+				serial: None,
+			},
+			name: entry.name,
+			header: None,
+		})
+		.collect();
+
+	let (results, sets) = match_set(&set_entries, conn).await?;
+
+	Ok(ArchiveIdentifyResponse {
+		entries: set_entries
+			.into_iter()
+			.zip(results)
+			.map(|(entry, game_match)| ArchiveEntryMatch {
+				name: entry.name,
+				hashes: None,
+				headerless_hashes: None,
+				game_match,
+			})
+			.collect(),
+		sets,
+	})
+}
+
+/// DATs only contain the file names of ROMs, so folders in the archive are ignored for matching.
+fn entry_file_name(name: &str) -> String {
+	name.rsplit(['/', '\\']).next().unwrap_or(name).to_string()
+}
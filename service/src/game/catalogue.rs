@@ -0,0 +1,200 @@
+use crate::db::game::{
+	find_game_clones, find_game_ids_by_similar_name, find_game_ids_page_by_filters,
+	find_game_match_candidates, find_game_parent, find_game_rom_of,
+};
+use crate::db::game_file::get_game_files_from_game_ids;
+use crate::model::{
+	GameFileResponse, GameListQuery, GameListResponse, GameMatchDatResponse, GameNameSearch,
+	GameNameSearchResult, GameResponse,
+};
+use crate::r#match::igdb::clean_name;
+use entity::game;
+use sea_orm::prelude::Uuid;
+use sea_orm::{ColumnTrait, DbConn};
+
+pub const DEFAULT_GAME_PAGE_SIZE: u64 = 50;
+
+pub const MAX_GAME_PAGE_SIZE: u64 = 200;
+
+pub const DEFAULT_GAME_NAME_SEARCH_LIMIT: u64 = 20;
+
+pub const MAX_GAME_NAME_SEARCH_LIMIT: u64 = 100;
+
+/// Words shorter than this, e.g. "a" or "2", are left out of the full text query, they would
+/// match most of the games.
+const MIN_FULL_TEXT_WORD_LENGTH: usize = 2;
+
+/// Returns a page of the games matching the filters of the query, with where they come from.
+pub async fn find_games(query: GameListQuery, conn: &DbConn) -> anyhow::Result<GameListResponse> {
+	let page = query.page.unwrap_or(1).max(1);
+	let page_size = query
+		.page_size
+		.unwrap_or(DEFAULT_GAME_PAGE_SIZE)
+		.clamp(1, MAX_GAME_PAGE_SIZE);
+
+	let (game_ids, total_games) =
+		find_game_ids_page_by_filters(&query, page - 1, page_size, conn).await?;
+
+	let mut candidates = if game_ids.is_empty() {
+		Vec::new()
+	} else {
+		find_game_match_candidates(game::Column::Id.is_in(game_ids.clone()), true, None, conn)
+			.await?
+	};
+	// the candidates are ordered for matching, the page is ordered by name
+	candidates.sort_by_key(|candidate| {
+		game_ids
+			.iter()
+			.position(|id| *id == candidate.game.id)
+			.unwrap_or(usize::MAX)
+	});
+
+	Ok(GameListResponse {
+		games: candidates.into_iter().map(Into::into).collect(),
+		page,
+		page_size,
+		total_games,
+		total_pages: total_games.div_ceil(page_size),
+	})
+}
+
+/// Returns a game with its files, its DAT, its parent, clones and the game whose ROMs it uses and
+/// its external metadata.
+pub async fn get_game_by_id(id: Uuid, conn: &DbConn) -> anyhow::Result<Option<GameResponse>> {
+	let Some(candidate) = find_game_match_candidates(game::Column::Id.eq(id), true, Some(1), conn)
+		.await?
+		.into_iter()
+		.next()
+	else {
+		return Ok(None);
+	};
+
+	let parent = find_game_parent(&candidate.game, conn).await?;
+	let rom_of = find_game_rom_of(&candidate.game, conn).await?;
+	let clones = find_game_clones(candidate.game.id, conn).await?;
+
+	let mut files = get_game_files_from_game_ids(vec![candidate.game.id], conn).await?;
+	files.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+
+	let game = candidate.game;
+
+	Ok(Some(GameResponse {
+		id: game.id,
+		name: game.name,
+		description: game.description,
+		kind: game.kind.into(),
+		categories: game.categories.unwrap_or_default(),
+		retired_at: game.retired_at,
+		dat: GameMatchDatResponse {
+			id: candidate.dat_file.id,
+			name: candidate.dat_file.name,
+			version: candidate.dat_file.current_version,
+			signature_group_id: candidate.signature_group.id,
+			signature_group_name: candidate.signature_group.name,
+		},
+		platform_id: candidate.platform.id,
+		platform_name: candidate.platform.name,
+		parent: parent.map(Into::into),
+		rom_of: rom_of.map(Into::into),
+		clones: clones.into_iter().map(Into::into).collect(),
+		files: files.into_iter().map(GameFileResponse::from).collect(),
+		external_metadata: candidate
+			.signature_metadata_mappings
+			.into_iter()
+			.map(Into::into)
+			.collect(),
+	}))
+}
+
+/// Searches games by name, ignoring tags like (USA) or (Rev 1) on both sides, most similar first.
+pub async fn search_games_by_name(
+	search: GameNameSearch,
+	conn: &DbConn,
+) -> anyhow::Result<Vec<GameNameSearchResult>> {
+	let name = clean_name(&search.name).trim().to_string();
+	if name.is_empty() {
+		return Ok(Vec::new());
+	}
+
+	let limit = search
+		.limit
+		.unwrap_or(DEFAULT_GAME_NAME_SEARCH_LIMIT)
+		.clamp(1, MAX_GAME_NAME_SEARCH_LIMIT);
+
+	let full_text_query = full_text_query(&name);
+
+	let similar_games = find_game_ids_by_similar_name(
+		&name,
+		full_text_query.as_deref(),
+		search.platform_id,
+		search.include_retired.unwrap_or_default(),
+		limit,
+		conn,
+	)
+	.await?;
+
+	if similar_games.is_empty() {
+		return Ok(Vec::new());
+	}
+
+	let mut candidates = find_game_match_candidates(
+		game::Column::Id.is_in(similar_games.iter().map(|(id, _)| *id)),
+		true,
+		None,
+		conn,
+	)
+	.await?;
+
+	Ok(similar_games
+		.into_iter()
+		.filter_map(|(id, similarity)| {
+			let index = candidates
+				.iter()
+				.position(|candidate| candidate.game.id == id)?;
+
+			Some(GameNameSearchResult {
+				similarity,
+				game: candidates.swap_remove(index).into(),
+			})
+		})
+		.collect())
+}
+
+/// Builds a full text query matching any of the words of the name, e.g. `zelda | link | past` for
+/// "Zelda: Link to the Past". Everything but letters and digits is dropped, so the name can't
+/// contain operators of the query syntax.
+fn full_text_query(name: &str) -> Option<String> {
+	let words: Vec<String> = name
+		.split(|c: char| !c.is_alphanumeric())
+		.filter(|word| word.chars().count() >= MIN_FULL_TEXT_WORD_LENGTH)
+		.map(str::to_lowercase)
+		.collect();
+
+	if words.is_empty() {
+		return None;
+	}
+
+	Some(words.join(" | "))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn full_text_query_matches_any_word() {
+		assert_eq!(
+			full_text_query("zelda link past snes").as_deref(),
+			Some("zelda | link | past | snes")
+		);
+	}
+
+	#[test]
+	fn full_text_query_drops_punctuation_and_short_words() {
+		assert_eq!(
+			full_text_query("Zelda: A Link to the Past & 'Oracle' | !").as_deref(),
+			Some("zelda | link | to | the | past | oracle")
+		);
+		assert_eq!(full_text_query("a - 2"), None);
+	}
+}
@@ -0,0 +1,201 @@
+use crate::db::game::{
+	find_games_and_dat_file_ids_by_ids, find_games_by_clone_of_ids, find_games_by_ids,
+	find_games_by_name_prefix_and_dat_file_id,
+};
+use crate::model::{GameDisc, GameDiscGroup, GameSetMatch, GameSetStatus};
+use entity::game;
+use lazy_static::lazy_static;
+use regex::Regex;
+use sea_orm::prelude::Uuid;
+use sea_orm::DbConn;
+use std::collections::{HashMap, HashSet};
+
+lazy_static! {
+	/// Disc tags as used by Redump and TOSEC, e.g. `(Disc 2)` or `(Disc 2 of 3)`.
+	static ref DISC_TAG_REGEX: Regex =
+		Regex::new(r"(?i)\s*\((?:disc|disk|cd)\s*(\d+)(?:\s*of\s*\d+)?\)").unwrap();
+}
+
+/// A game name split at its disc tag.
+struct DiscName {
+	/// The name without the disc tag.
+	title: String,
+
+	/// The name up to the disc tag, all discs of the title start with it.
+	prefix: String,
+
+	disc: u32,
+}
+
+fn parse_disc_name(name: &str) -> Option<DiscName> {
+	let captures = DISC_TAG_REGEX.captures(name)?;
+	let tag = captures.get(0)?;
+
+	Some(DiscName {
+		title: format!("{}{}", &name[..tag.start()], &name[tag.end()..]),
+		prefix: name[..tag.start()].to_string(),
+		disc: captures.get(1)?.as_str().parse().ok()?,
+	})
+}
+
+/// Groups the games of a set which are a disc of a title with multiple discs. Discs are grouped
+/// by their parent, the disc itself if it is no clone, and the title of the parent, so every group
+/// contains all discs of the parent's title in the same DAT and all discs of their clones,
+/// including the ones not in the set.
+pub async fn group_discs(
+	sets: &[GameSetMatch],
+	include_retired: bool,
+	conn: &DbConn,
+) -> anyhow::Result<Vec<GameDiscGroup>> {
+	let statuses: HashMap<Uuid, GameSetStatus> =
+		sets.iter().map(|set| (set.id, set.status)).collect();
+
+	let games =
+		find_games_and_dat_file_ids_by_ids(sets.iter().map(|set| set.id).collect(), conn).await?;
+
+	let parent_ids: Vec<Uuid> = games.iter().filter_map(|(game, _)| game.clone_of).collect();
+	let parents: HashMap<Uuid, game::Model> = find_games_by_ids(parent_ids, conn)
+		.await?
+		.into_iter()
+		.map(|parent| (parent.id, parent))
+		.collect();
+
+	let mut groups: Vec<GameDiscGroup> = Vec::new();
+	let mut grouped: Vec<(Uuid, String)> = Vec::new();
+
+	// in the order of the sets, so groups of complete sets come first
+	for set in sets {
+		let Some((game, dat_file_id)) = games.iter().find(|(game, _)| game.id == set.id) else {
+			continue;
+		};
+
+		if parse_disc_name(&game.name).is_none() {
+			continue;
+		}
+
+		// a clone whose parent has no disc tag is grouped on its own
+		let parent_disc_name = game
+			.clone_of
+			.and_then(|clone_of| parents.get(&clone_of))
+			.and_then(|parent| parse_disc_name(&parent.name))
+			.or_else(|| parse_disc_name(&game.name));
+		let Some(parent_disc_name) = parent_disc_name else {
+			continue;
+		};
+
+		if grouped.contains(&(*dat_file_id, parent_disc_name.title.clone())) {
+			continue;
+		}
+		grouped.push((*dat_file_id, parent_disc_name.title.clone()));
+
+		let mut discs: Vec<game::Model> = find_games_by_name_prefix_and_dat_file_id(
+			&parent_disc_name.prefix,
+			*dat_file_id,
+			include_retired,
+			conn,
+		)
+		.await?
+		.into_iter()
+		.filter(|disc| {
+			parse_disc_name(&disc.name)
+				.is_some_and(|sibling| sibling.title == parent_disc_name.title)
+		})
+		.collect();
+
+		let clones = find_games_by_clone_of_ids(
+			discs.iter().map(|disc| disc.id).collect(),
+			include_retired,
+			conn,
+		)
+		.await?;
+		for clone in clones {
+			if !discs.iter().any(|disc| disc.id == clone.id) {
+				discs.push(clone);
+			}
+		}
+
+		let mut discs: Vec<(GameDisc, String)> = discs
+			.into_iter()
+			.filter_map(|disc| {
+				let disc_name = parse_disc_name(&disc.name)?;
+
+				Some((
+					GameDisc {
+						id: disc.id,
+						status: statuses.get(&disc.id).copied(),
+						name: disc.name,
+						disc: disc_name.disc,
+						clone_of: disc.clone_of,
+					},
+					disc_name.title,
+				))
+			})
+			.collect();
+		discs.sort_by(|(a, _), (b, _)| a.disc.cmp(&b.disc).then_with(|| a.name.cmp(&b.name)));
+
+		// the discs of the parent and of every clone are complete on their own, if they cover
+		// every disc number of the group
+		let numbers: HashSet<u32> = discs.iter().map(|(disc, _)| disc.disc).collect();
+		let complete = discs.iter().any(|(_, title)| {
+			numbers.iter().all(|number| {
+				discs.iter().any(|(disc, disc_title)| {
+					disc_title == title
+						&& disc.disc == *number
+						&& matches!(
+							disc.status,
+							Some(GameSetStatus::Complete | GameSetStatus::Superset)
+						)
+				})
+			})
+		});
+
+		groups.push(GameDiscGroup {
+			title: parent_disc_name.title,
+			complete,
+			discs: discs.into_iter().map(|(disc, _)| disc).collect(),
+		});
+	}
+
+	Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_disc_tags() {
+		let disc_name = parse_disc_name("Final Fantasy VII (USA) (Disc 2)").unwrap();
+
+		assert_eq!(disc_name.title, "Final Fantasy VII (USA)");
+		assert_eq!(disc_name.prefix, "Final Fantasy VII (USA)");
+		assert_eq!(disc_name.disc, 2);
+	}
+
+	#[test]
+	fn parses_disc_tags_with_a_total_and_other_tags_after_them() {
+		let disc_name = parse_disc_name("Riven (USA) (Disc 3 of 5) (Rev 1)").unwrap();
+
+		assert_eq!(disc_name.title, "Riven (USA) (Rev 1)");
+		assert_eq!(disc_name.prefix, "Riven (USA)");
+		assert_eq!(disc_name.disc, 3);
+	}
+
+	#[test]
+	fn parses_disk_and_cd_tags_case_insensitively() {
+		assert_eq!(
+			parse_disc_name("Monkey Island (1990)(Lucasfilm)(disk 4)")
+				.unwrap()
+				.disc,
+			4
+		);
+		assert_eq!(parse_disc_name("Myst (Europe) (CD1)").unwrap().disc, 1);
+	}
+
+	#[test]
+	fn ignores_names_without_disc_tag() {
+		assert!(parse_disc_name("Tetris (World) (Rev 1)").is_none());
+		assert!(parse_disc_name("Discworld (Europe)").is_none());
+		assert!(parse_disc_name("Disc Game (USA) (Disc A)").is_none());
+	}
+}
@@ -0,0 +1,181 @@
+use crate::db::dat_file::find_dat_files_by_ids;
+use crate::db::game::{find_game_ids_and_names_by_ids, find_games_by_ids};
+use crate::db::game_file::get_game_files_from_game_ids;
+use crate::game::set::is_same_file;
+use crate::header::DetectedHeader;
+use crate::model::{
+	GameDetailsResponse, GameFileMatchSearch, GameFileResponse, GameMatchDatResponse,
+	GameMatchExpand, GameMatchResult, GameMatchType, PlatformResponse,
+};
+use crate::platform::get_platform_by_id_and_related_company_and_signature_metadata_mapping;
+use entity::game_file;
+use sea_orm::prelude::Uuid;
+use sea_orm::DbConn;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// Adds the requested details of the matched game to every result, loaded at once for all
+/// results. The searches are needed to find the matched file and are in the order of the results.
+pub async fn expand_game_match_results(
+	results: &mut [GameMatchResult],
+	searches: &[(&GameFileMatchSearch, Option<&DetectedHeader>)],
+	expand: GameMatchExpand,
+	conn: &DbConn,
+) -> anyhow::Result<()> {
+	let mut game_ids: Vec<Uuid> = results.iter().filter_map(|result| result.id).collect();
+	game_ids.sort();
+	game_ids.dedup();
+
+	if expand.is_empty() || game_ids.is_empty() {
+		return Ok(());
+	}
+
+	if expand.game {
+		expand_games(results, game_ids.clone(), conn).await?;
+	}
+
+	if expand.platform {
+		expand_platforms(results, conn).await?;
+	}
+
+	if expand.dat {
+		expand_dats(results, conn).await?;
+	}
+
+	if expand.files {
+		expand_files(results, searches, game_ids, conn).await?;
+	}
+
+	Ok(())
+}
+
+async fn expand_games(
+	results: &mut [GameMatchResult],
+	game_ids: Vec<Uuid>,
+	conn: &DbConn,
+) -> anyhow::Result<()> {
+	let games = find_games_by_ids(game_ids, conn).await?;
+
+	let parent_names: HashMap<Uuid, String> = find_game_ids_and_names_by_ids(
+		games.iter().filter_map(|game| game.clone_of).collect(),
+		conn,
+	)
+	.await?
+	.into_iter()
+	.collect();
+
+	let games: HashMap<Uuid, GameDetailsResponse> = games
+		.into_iter()
+		.map(|game| {
+			(
+				game.id,
+				GameDetailsResponse {
+					name: game.name,
+					description: game.description,
+					categories: game.categories.unwrap_or_default(),
+					clone_of_id: game.clone_of,
+					clone_of_name: game
+						.clone_of
+						.and_then(|clone_of| parent_names.get(&clone_of).cloned()),
+				},
+			)
+		})
+		.collect();
+
+	for result in results.iter_mut() {
+		result.game = result.id.and_then(|id| games.get(&id).cloned());
+	}
+
+	Ok(())
+}
+
+async fn expand_platforms(results: &mut [GameMatchResult], conn: &DbConn) -> anyhow::Result<()> {
+	let mut platforms: HashMap<Uuid, Option<PlatformResponse>> = HashMap::new();
+
+	for result in results.iter_mut() {
+		let Some(platform_id) = result
+			.candidates
+			.first()
+			.map(|candidate| candidate.platform_id)
+		else {
+			continue;
+		};
+
+		// a batch usually only spans a few platforms
+		if let Entry::Vacant(entry) = platforms.entry(platform_id) {
+			entry.insert(
+				get_platform_by_id_and_related_company_and_signature_metadata_mapping(
+					platform_id,
+					conn,
+				)
+				.await?,
+			);
+		}
+
+		result.platform = platforms.get(&platform_id).cloned().flatten();
+	}
+
+	Ok(())
+}
+
+async fn expand_dats(results: &mut [GameMatchResult], conn: &DbConn) -> anyhow::Result<()> {
+	let dat_file_ids: Vec<Uuid> = results
+		.iter()
+		.filter_map(|result| result.candidates.first())
+		.map(|candidate| candidate.dat_file_id)
+		.collect();
+
+	let dat_files: HashMap<Uuid, _> = find_dat_files_by_ids(dat_file_ids, conn)
+		.await?
+		.into_iter()
+		.map(|dat_file| (dat_file.id, dat_file))
+		.collect();
+
+	for result in results.iter_mut() {
+		result.dat = result.candidates.first().and_then(|candidate| {
+			dat_files
+				.get(&candidate.dat_file_id)
+				.map(|dat_file| GameMatchDatResponse {
+					id: dat_file.id,
+					name: dat_file.name.clone(),
+					version: dat_file.current_version.clone(),
+					signature_group_id: candidate.signature_group_id,
+					signature_group_name: candidate.signature_group_name.clone(),
+				})
+		});
+	}
+
+	Ok(())
+}
+
+async fn expand_files(
+	results: &mut [GameMatchResult],
+	searches: &[(&GameFileMatchSearch, Option<&DetectedHeader>)],
+	game_ids: Vec<Uuid>,
+	conn: &DbConn,
+) -> anyhow::Result<()> {
+	let mut files_by_game_id: HashMap<Uuid, Vec<game_file::Model>> = HashMap::new();
+	for file in get_game_files_from_game_ids(game_ids, conn).await? {
+		files_by_game_id.entry(file.game_id).or_default().push(file);
+	}
+
+	for (result, (search, header)) in results.iter_mut().zip(searches) {
+		let Some(files) = result.id.and_then(|id| files_by_game_id.get_mut(&id)) else {
+			continue;
+		};
+		files.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+
+		// a serial is shared by all files of a disc, so the first file carrying one is taken
+		result.matched_file = files
+			.iter()
+			.find(|file| match result.game_match_type {
+				GameMatchType::Serial => file.serial.is_some(),
+				_ => is_same_file(search, *header, file),
+			})
+			.cloned()
+			.map(Into::into);
+		result.files = files.iter().cloned().map(GameFileResponse::from).collect();
+	}
+
+	Ok(())
+}
@@ -1,81 +1,482 @@
 use crate::db::game::{
-	find_game_and_id_mapping_by_md5, find_game_and_id_mapping_by_name_and_size,
-	find_game_and_id_mapping_by_sha1, find_game_and_id_mapping_by_sha256,
+	find_game_files_and_games_by_serial, find_game_match_candidates_by_crc_and_size,
+	find_game_match_candidates_by_md5, find_game_match_candidates_by_name_and_headerless_size,
+	find_game_match_candidates_by_name_and_size, find_game_match_candidates_by_serial,
+	find_game_match_candidates_by_sha1, find_game_match_candidates_by_sha256, GameMatchCandidate,
 };
-use crate::model::{GameFileMatchSearch, GameMatchResult, GameMatchResultBuilder, GameMatchType};
-use entity::{game, signature_metadata_mapping};
+use crate::db::game_file::normalize_crc;
+use crate::db::game_match_batch::{
+	find_batch_game_match_candidates_by_crc_and_size, find_batch_game_match_candidates_by_md5,
+	find_batch_game_match_candidates_by_name_and_headerless_size,
+	find_batch_game_match_candidates_by_name_and_size, find_batch_game_match_candidates_by_serial,
+	find_batch_game_match_candidates_by_sha1, find_batch_game_match_candidates_by_sha256,
+	BatchGameMatchCandidates,
+};
+use crate::db::signature_metadata_mapping::find_signature_metadata_mappings_by_game_ids;
+use crate::game::expand::expand_game_match_results;
+use crate::hash::FileHashes;
+use crate::header::{detect_header, parse_hex, DetectedHeader, HEADER_PROBE_SIZE};
+use crate::model::{
+	FileIdentifyResponse, GameFileMatchSearch, GameMatchExpand, GameMatchResult,
+	GameMatchResultBuilder, GameMatchType, GameSerialResponse, GameSerialSearch,
+};
+use anyhow::anyhow;
 use sea_orm::DbConn;
 use strum::IntoEnumIterator;
 
+pub mod archive;
+pub mod catalogue;
+mod disc;
+pub mod expand;
+pub mod set;
+
+/// Detects a header in the start of the file given with the search, fails if it is no valid hex.
+pub fn detect_search_header(
+	search: &GameFileMatchSearch,
+) -> anyhow::Result<Option<DetectedHeader>> {
+	let Some(header) = &search.header else {
+		return Ok(None);
+	};
+
+	let data = parse_hex(header)?;
+	let data = &data[..data.len().min(HEADER_PROBE_SIZE)];
+
+	Ok(u64::try_from(search.file_size)
+		.ok()
+		.and_then(|file_size| detect_header(data, file_size)))
+}
+
 pub async fn match_game_if_possible(
 	search: GameFileMatchSearch,
+	header: Option<DetectedHeader>,
 	conn: &DbConn,
 ) -> anyhow::Result<GameMatchResult> {
-	let mut response_body = None;
+	let include_retired = search.include_retired.unwrap_or_default();
 
 	for r#type in GameMatchType::iter() {
 		if r#type == GameMatchType::NoMatch {
 			continue;
 		}
 
-		if let Some((game_release, game_release_id_mappings)) = match r#type {
+		let candidates = match r#type {
 			GameMatchType::SHA256 => {
 				if let Some(sha256) = &search.sha256 {
-					find_game_and_id_mapping_by_sha256(sha256, conn).await?
+					find_game_match_candidates_by_sha256(sha256, include_retired, conn).await?
 				} else {
-					None
+					Vec::new()
 				}
 			}
 			GameMatchType::SHA1 => {
 				if let Some(sha1) = &search.sha1 {
-					find_game_and_id_mapping_by_sha1(sha1, conn).await?
+					find_game_match_candidates_by_sha1(sha1, include_retired, conn).await?
 				} else {
-					None
+					Vec::new()
 				}
 			}
 			GameMatchType::MD5 => {
 				if let Some(md5) = &search.md5 {
-					find_game_and_id_mapping_by_md5(md5, conn).await?
+					find_game_match_candidates_by_md5(md5, include_retired, conn).await?
+				} else {
+					Vec::new()
+				}
+			}
+			GameMatchType::CRC32AndSize => {
+				if let Some(crc) = normalize_crc(search.crc.clone()) {
+					match_by_crc_and_size(
+						&crc,
+						search.file_size,
+						header.as_ref(),
+						include_retired,
+						conn,
+					)
+					.await?
 				} else {
-					None
+					Vec::new()
+				}
+			}
+			GameMatchType::Serial => {
+				if let Some(serial) = &search.serial {
+					find_game_match_candidates_by_serial(serial, include_retired, conn).await?
+				} else {
+					Vec::new()
 				}
 			}
 			GameMatchType::FileNameAndSize => {
-				find_game_and_id_mapping_by_name_and_size(&search.file_name, search.file_size, conn)
+				let headerless_candidates = match &header {
+					Some(header) => {
+						find_game_match_candidates_by_name_and_headerless_size(
+							&search.file_name,
+							header.data_size() as i64,
+							&header.detector,
+							include_retired,
+							conn,
+						)
+						.await?
+					}
+					None => Vec::new(),
+				};
+
+				if headerless_candidates.is_empty() {
+					find_game_match_candidates_by_name_and_size(
+						&search.file_name,
+						search.file_size,
+						include_retired,
+						conn,
+					)
 					.await?
+				} else {
+					headerless_candidates
+				}
 			}
 			GameMatchType::NoMatch => unreachable!(),
-		} {
-			response_body = Some(build_result(
-				r#type,
-				game_release,
-				game_release_id_mappings,
-			)?);
-			break;
+		};
+
+		if !candidates.is_empty() {
+			return build_result(r#type, candidates, header.as_ref());
 		}
 	}
 
-	Ok(response_body.unwrap_or(GameMatchResult {
+	Ok(no_match_result(header))
+}
+
+fn no_match_result(header: Option<DetectedHeader>) -> GameMatchResult {
+	GameMatchResult {
 		game_match_type: GameMatchType::NoMatch,
 		id: None,
+		kind: None,
+		retired_at: None,
 		external_metadata: Vec::new(),
-	}))
+		header_detected: header.is_some(),
+		header_detector: header.map(|header| header.detector),
+		ambiguous: false,
+		candidates: Vec::new(),
+		game: None,
+		platform: None,
+		dat: None,
+		matched_file: None,
+		files: Vec::new(),
+	}
 }
 
+/// Identifies a file hashed by the server. If a header was detected, the hashes of the data after
+/// it are matched first, as DATs of headered formats only contain the headerless hashes.
+pub async fn identify_hashed_file(
+	file_name: String,
+	hashes: FileHashes,
+	header: Option<(DetectedHeader, FileHashes)>,
+	include_retired: Option<bool>,
+	expand: GameMatchExpand,
+	conn: &DbConn,
+) -> anyhow::Result<FileIdentifyResponse> {
+	let (header, headerless_hashes) = header.unzip();
+
+	let search = |hashes: &FileHashes| GameFileMatchSearch {
+		file_name: file_name.clone(),
+		file_size: hashes.size as i64,
+		md5: Some(hashes.md5.clone()),
+		sha1: Some(hashes.sha1.clone()),
+		sha256: Some(hashes.sha256.clone()),
+		crc: Some(hashes.crc.clone()),
+		include_retired,
+		header: None,
+		serial: None,
+	};
+
+	let mut matched_search = search(headerless_hashes.as_ref().unwrap_or(&hashes));
+	let mut game_match =
+		match_game_if_possible(matched_search.clone(), header.clone(), conn).await?;
+
+	if game_match.game_match_type == GameMatchType::NoMatch && headerless_hashes.is_some() {
+		matched_search = search(&hashes);
+		game_match = match_game_if_possible(matched_search.clone(), header.clone(), conn).await?;
+	}
+
+	expand_game_match_results(
+		std::slice::from_mut(&mut game_match),
+		&[(&matched_search, header.as_ref())],
+		expand,
+		conn,
+	)
+	.await?;
+
+	Ok(FileIdentifyResponse {
+		file_name,
+		hashes: hashes.into(),
+		headerless_hashes: headerless_hashes.map(Into::into),
+		game_match,
+	})
+}
+
+/// Matches by CRC32 and the size without the header first if one was detected, as DATs of headered
+/// formats contain the CRC32 of the data without the header.
+async fn match_by_crc_and_size(
+	crc: &str,
+	file_size: i64,
+	header: Option<&DetectedHeader>,
+	include_retired: bool,
+	conn: &DbConn,
+) -> anyhow::Result<Vec<GameMatchCandidate>> {
+	let sizes = header
+		.map(|header| header.data_size() as i64)
+		.into_iter()
+		.chain([file_size]);
+
+	for size in sizes {
+		let candidates =
+			find_game_match_candidates_by_crc_and_size(crc, size, include_retired, conn).await?;
+
+		if !candidates.is_empty() {
+			return Ok(candidates);
+		}
+	}
+
+	Ok(Vec::new())
+}
+
+/// Matches a batch of searches like `match_game_if_possible` would one by one, but with one query
+/// per match type for the whole batch. Results are in the order of the searches.
+pub async fn match_games_in_batch(
+	searches: Vec<(GameFileMatchSearch, Option<DetectedHeader>)>,
+	conn: &DbConn,
+) -> anyhow::Result<Vec<GameMatchResult>> {
+	let mut results: Vec<Option<GameMatchResult>> = vec![None; searches.len()];
+
+	// searches which did not match with any of the previous match types
+	let pending = |results: &[Option<GameMatchResult>]| {
+		searches
+			.iter()
+			.enumerate()
+			.filter(|(index, _)| results[*index].is_none())
+			.collect::<Vec<_>>()
+	};
+
+	for r#type in GameMatchType::iter() {
+		if r#type == GameMatchType::NoMatch {
+			continue;
+		}
+
+		let mut candidates = match r#type {
+			GameMatchType::SHA256 => {
+				find_batch_game_match_candidates_by_sha256(
+					pending(&results)
+						.into_iter()
+						.filter_map(|(index, (search, _))| {
+							search.sha256.clone().map(|sha256| (index, sha256))
+						})
+						.collect(),
+					conn,
+				)
+				.await?
+			}
+			GameMatchType::SHA1 => {
+				find_batch_game_match_candidates_by_sha1(
+					pending(&results)
+						.into_iter()
+						.filter_map(|(index, (search, _))| {
+							search.sha1.clone().map(|sha1| (index, sha1))
+						})
+						.collect(),
+					conn,
+				)
+				.await?
+			}
+			GameMatchType::MD5 => {
+				find_batch_game_match_candidates_by_md5(
+					pending(&results)
+						.into_iter()
+						.filter_map(|(index, (search, _))| {
+							search.md5.clone().map(|md5| (index, md5))
+						})
+						.collect(),
+					conn,
+				)
+				.await?
+			}
+			GameMatchType::CRC32AndSize => {
+				let mut candidates = find_batch_game_match_candidates_by_crc_and_size(
+					pending(&results)
+						.into_iter()
+						.filter_map(|(index, (search, header))| {
+							Some((
+								index,
+								normalize_crc(search.crc.clone())?,
+								header.as_ref()?.data_size() as i64,
+							))
+						})
+						.collect(),
+					conn,
+				)
+				.await?;
+				retain_included_candidates(&mut candidates, &searches);
+
+				let sized_candidates = find_batch_game_match_candidates_by_crc_and_size(
+					pending(&results)
+						.into_iter()
+						.filter(|(index, _)| !candidates.contains_key(index))
+						.filter_map(|(index, (search, _))| {
+							Some((index, normalize_crc(search.crc.clone())?, search.file_size))
+						})
+						.collect(),
+					conn,
+				)
+				.await?;
+				candidates.extend(sized_candidates);
+
+				candidates
+			}
+			GameMatchType::Serial => {
+				find_batch_game_match_candidates_by_serial(
+					pending(&results)
+						.into_iter()
+						.filter_map(|(index, (search, _))| {
+							search.serial.clone().map(|serial| (index, serial))
+						})
+						.collect(),
+					conn,
+				)
+				.await?
+			}
+			GameMatchType::FileNameAndSize => {
+				let mut candidates = find_batch_game_match_candidates_by_name_and_headerless_size(
+					pending(&results)
+						.into_iter()
+						.filter_map(|(index, (search, header))| {
+							let header = header.as_ref()?;
+							Some((
+								index,
+								search.file_name.clone(),
+								header.data_size() as i64,
+								header.detector.clone(),
+							))
+						})
+						.collect(),
+					conn,
+				)
+				.await?;
+				retain_included_candidates(&mut candidates, &searches);
+
+				let sized_candidates = find_batch_game_match_candidates_by_name_and_size(
+					pending(&results)
+						.into_iter()
+						.filter(|(index, _)| !candidates.contains_key(index))
+						.map(|(index, (search, _))| {
+							(index, search.file_name.clone(), search.file_size)
+						})
+						.collect(),
+					conn,
+				)
+				.await?;
+				candidates.extend(sized_candidates);
+
+				candidates
+			}
+			GameMatchType::NoMatch => unreachable!(),
+		};
+
+		retain_included_candidates(&mut candidates, &searches);
+
+		for (index, index_candidates) in candidates {
+			let header = searches[index].1.as_ref();
+			results[index] = Some(build_result(r#type, index_candidates, header)?);
+		}
+	}
+
+	Ok(results
+		.into_iter()
+		.zip(searches)
+		.map(|(result, (_, header))| result.unwrap_or_else(|| no_match_result(header)))
+		.collect())
+}
+
+/// Batch queries always include retired games, they are dropped for searches which don't want
+/// them. Searches without any candidates left are removed.
+fn retain_included_candidates(
+	candidates: &mut BatchGameMatchCandidates,
+	searches: &[(GameFileMatchSearch, Option<DetectedHeader>)],
+) {
+	candidates.retain(|index, index_candidates| {
+		if !searches[*index].0.include_retired.unwrap_or_default() {
+			index_candidates.retain(|candidate| candidate.game.retired_at.is_none());
+		}
+
+		!index_candidates.is_empty()
+	});
+}
+
+/// Returns all games with files carrying the serial, a serial is usually shared by all discs and
+/// revisions of a release.
+pub async fn find_games_by_serial(
+	search: GameSerialSearch,
+	conn: &DbConn,
+) -> anyhow::Result<Vec<GameSerialResponse>> {
+	let game_files = find_game_files_and_games_by_serial(
+		&search.serial,
+		search.include_retired.unwrap_or_default(),
+		conn,
+	)
+	.await?;
+
+	let mut games: Vec<GameSerialResponse> = Vec::new();
+
+	for (game_file, game) in game_files {
+		match games.iter_mut().find(|response| response.id == game.id) {
+			Some(response) => response.files.push(game_file.into()),
+			None => games.push(GameSerialResponse {
+				id: game.id,
+				name: game.name,
+				kind: game.kind.into(),
+				retired_at: game.retired_at,
+				external_metadata: Vec::new(),
+				files: vec![game_file.into()],
+			}),
+		}
+	}
+
+	let signature_metadata_mappings = find_signature_metadata_mappings_by_game_ids(
+		games.iter().map(|game| game.id).collect(),
+		conn,
+	)
+	.await?;
+
+	for signature_metadata_mapping in signature_metadata_mappings {
+		if let Some(game) = games
+			.iter_mut()
+			.find(|game| Some(game.id) == signature_metadata_mapping.game_id)
+		{
+			game.external_metadata
+				.push(signature_metadata_mapping.into());
+		}
+	}
+
+	Ok(games)
+}
+
+/// The first candidate is the best match, it fills the fields of the result.
 fn build_result(
 	game_match_type: GameMatchType,
-	game: game::Model,
-	signature_metadata_mappings: Vec<signature_metadata_mapping::Model>,
+	candidates: Vec<GameMatchCandidate>,
+	header: Option<&DetectedHeader>,
 ) -> anyhow::Result<GameMatchResult> {
+	let best = candidates
+		.first()
+		.ok_or_else(|| anyhow!("No candidates for a {:?} match", game_match_type))?;
+
 	let result = GameMatchResultBuilder::default()
 		.game_match_type(game_match_type)
-		.id(Some(game.id))
+		.id(Some(best.game.id))
+		.kind(Some(best.game.kind.clone().into()))
+		.retired_at(best.game.retired_at)
 		.external_metadata(
-			signature_metadata_mappings
-				.into_iter()
+			best.signature_metadata_mappings
+				.iter()
+				.cloned()
 				.map(Into::into)
 				.collect(),
 		)
+		.header_detected(header.is_some())
+		.header_detector(header.map(|header| header.detector.clone()))
+		.ambiguous(candidates.len() > 1)
+		.candidates(candidates.into_iter().map(Into::into).collect())
 		.build()?;
 
 	Ok(result)
@@ -0,0 +1,328 @@
+use crate::db::game_file::{get_game_files_from_game_ids, normalize_crc};
+use crate::game::disc::group_discs;
+use crate::game::match_games_in_batch;
+use crate::header::DetectedHeader;
+use crate::model::{
+	GameFileMatchSearch, GameMatchResult, GameSetIdentifyResponse, GameSetMatch, GameSetStatus,
+};
+use entity::game_file;
+use sea_orm::prelude::Uuid;
+use sea_orm::DbConn;
+use std::collections::HashMap;
+
+/// Upper bound for the games returned for a set, the best ones are kept.
+const MAX_GAME_SET_MATCHES: usize = 20;
+
+/// A file of a set, e.g. an entry of an archive.
+#[derive(Debug, Clone)]
+pub struct SetEntry {
+	/// Where the file is in the set, e.g. its path in the archive.
+	pub name: String,
+
+	pub search: GameFileMatchSearch,
+
+	pub header: Option<DetectedHeader>,
+}
+
+/// Identifies a set of files given by their hashes, e.g. the tracks of a disc. Titles with multiple
+/// discs are grouped, so a set can be told apart from the other discs.
+pub async fn identify_set(
+	files: Vec<(GameFileMatchSearch, Option<DetectedHeader>)>,
+	include_retired: Option<bool>,
+	conn: &DbConn,
+) -> anyhow::Result<GameSetIdentifyResponse> {
+	let entries: Vec<SetEntry> = files
+		.into_iter()
+		.map(|(search, header)| SetEntry {
+			name: search.file_name.clone(),
+			search: GameFileMatchSearch {
+				include_retired,
+				..search
+			},
+			header,
+		})
+		.collect();
+
+	let (files, sets) = match_set(&entries, conn).await?;
+	let disc_groups = group_discs(&sets, include_retired.unwrap_or_default(), conn).await?;
+
+	Ok(GameSetIdentifyResponse {
+		files,
+		sets,
+		disc_groups,
+	})
+}
+
+/// Identifies every file of a set and compares the games they belong to with the whole set.
+/// Returns the match of every file in the order of the entries and the games, complete ones first.
+pub async fn match_set(
+	entries: &[SetEntry],
+	conn: &DbConn,
+) -> anyhow::Result<(Vec<GameMatchResult>, Vec<GameSetMatch>)> {
+	let results = match_games_in_batch(
+		entries
+			.iter()
+			.map(|entry| (entry.search.clone(), entry.header.clone()))
+			.collect(),
+		conn,
+	)
+	.await?;
+
+	// every candidate of every file could be the game of the set, the best ones first
+	let mut games: Vec<(Uuid, String)> = Vec::new();
+	for candidate in results.iter().flat_map(|result| &result.candidates) {
+		if !games.iter().any(|(id, _)| *id == candidate.id) {
+			games.push((candidate.id, candidate.name.clone()));
+		}
+	}
+
+	if games.is_empty() {
+		return Ok((results, Vec::new()));
+	}
+
+	let mut files_by_game_id: HashMap<Uuid, Vec<game_file::Model>> = HashMap::new();
+	for file in
+		get_game_files_from_game_ids(games.iter().map(|(id, _)| *id).collect(), conn).await?
+	{
+		files_by_game_id.entry(file.game_id).or_default().push(file);
+	}
+
+	let mut sets: Vec<GameSetMatch> = games
+		.into_iter()
+		.map(|(id, name)| {
+			compare_set(
+				id,
+				name,
+				files_by_game_id.remove(&id).unwrap_or_default(),
+				entries,
+			)
+		})
+		.collect();
+
+	// stable, so games of equal rank stay in the order of the candidates
+	sets.sort_by(|a, b| {
+		a.status
+			.cmp(&b.status)
+			.then(b.matched_files.len().cmp(&a.matched_files.len()))
+			.then(a.missing_files.len().cmp(&b.missing_files.len()))
+	});
+	sets.truncate(MAX_GAME_SET_MATCHES);
+
+	Ok((results, sets))
+}
+
+fn compare_set(
+	id: Uuid,
+	name: String,
+	mut files: Vec<game_file::Model>,
+	entries: &[SetEntry],
+) -> GameSetMatch {
+	files.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+
+	let mut matched_files = Vec::new();
+	let mut missing_files = Vec::new();
+	let mut used_entries = vec![false; entries.len()];
+
+	for file in files {
+		let matching_entries: Vec<usize> = entries
+			.iter()
+			.enumerate()
+			.filter(|(_, entry)| is_same_file(&entry.search, entry.header.as_ref(), &file))
+			.map(|(index, _)| index)
+			.collect();
+
+		if matching_entries.is_empty() {
+			missing_files.push(file.file_name);
+		} else {
+			matching_entries
+				.into_iter()
+				.for_each(|index| used_entries[index] = true);
+			matched_files.push(file.file_name);
+		}
+	}
+
+	let extra_files: Vec<String> = entries
+		.iter()
+		.zip(used_entries)
+		.filter(|(_, used)| !used)
+		.map(|(entry, _)| entry.name.clone())
+		.collect();
+
+	let status = match (missing_files.is_empty(), extra_files.is_empty()) {
+		(true, true) => GameSetStatus::Complete,
+		(true, false) => GameSetStatus::Superset,
+		(false, _) => GameSetStatus::Partial,
+	};
+
+	GameSetMatch {
+		id,
+		name,
+		status,
+		matched_files,
+		missing_files,
+		extra_files,
+	}
+}
+
+/// Compares the most accurate hash both sides have, CRC32 and size are only used without any of
+/// the other hashes and file name and size only without CRC32.
+pub(crate) fn is_same_file(
+	search: &GameFileMatchSearch,
+	header: Option<&DetectedHeader>,
+	file: &game_file::Model,
+) -> bool {
+	for (entry_hash, file_hash) in [
+		(&search.sha256, &file.sha256),
+		(&search.sha1, &file.sha1),
+		(&search.md5, &file.md5),
+	] {
+		if let (Some(entry_hash), Some(file_hash)) = (entry_hash, file_hash) {
+			return entry_hash.trim().eq_ignore_ascii_case(file_hash.trim());
+		}
+	}
+
+	let size_matches = file.file_size_in_bytes.is_some_and(|size| {
+		size == search.file_size || header.is_some_and(|header| size == header.data_size() as i64)
+	});
+
+	if let (Some(entry_crc), Some(file_crc)) = (normalize_crc(search.crc.clone()), &file.crc) {
+		return size_matches && entry_crc == *file_crc;
+	}
+
+	size_matches && search.file_name == file.file_name
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sea_orm::prelude::DateTimeWithTimeZone;
+
+	fn game_file(file_name: &str, size: i64, crc: &str, sha1: Option<&str>) -> game_file::Model {
+		let created_at = DateTimeWithTimeZone::parse_from_rfc3339("2024-08-16T00:00:00Z").unwrap();
+
+		game_file::Model {
+			id: Uuid::nil(),
+			game_id: Uuid::nil(),
+			file_name: file_name.to_string(),
+			file_size_in_bytes: Some(size),
+			crc: Some(crc.to_string()),
+			md5: None,
+			sha1: sha1.map(str::to_string),
+			sha256: None,
+			status: None,
+			serial: None,
+			normalized_serials: None,
+			created_at,
+			updated_at: created_at,
+		}
+	}
+
+	fn entry(name: &str, size: i64, crc: &str, sha1: Option<&str>) -> SetEntry {
+		SetEntry {
+			name: name.to_string(),
+			search: GameFileMatchSearch {
+				file_name: name.to_string(),
+				file_size: size,
+				md5: None,
+				sha1: sha1.map(str::to_string),
+				sha256: None,
+				crc: Some(crc.to_string()),
+				include_retired: None,
+				header: None,
+				serial: None,
+			},
+			header: None,
+		}
+	}
+
+	fn game_files() -> Vec<game_file::Model> {
+		vec![
+			game_file("Game (Track 2).bin", 2000, "0000beef", None),
+			game_file("Game (Track 1).bin", 1000, "0000dead", None),
+			game_file("Game.cue", 100, "12345678", None),
+		]
+	}
+
+	#[test]
+	fn compares_complete_sets() {
+		let entries = vec![
+			entry("game.cue", 100, "12345678", None),
+			entry("track01.bin", 1000, "DEAD", None),
+			entry("track02.bin", 2000, "BEEF", None),
+		];
+
+		let set = compare_set(Uuid::nil(), "Game".to_string(), game_files(), &entries);
+
+		assert_eq!(set.status, GameSetStatus::Complete);
+		assert_eq!(
+			set.matched_files,
+			vec!["Game (Track 1).bin", "Game (Track 2).bin", "Game.cue"]
+		);
+		assert!(set.missing_files.is_empty());
+		assert!(set.extra_files.is_empty());
+	}
+
+	#[test]
+	fn compares_partial_sets() {
+		let entries = vec![
+			entry("game.cue", 100, "12345678", None),
+			entry("track01.bin", 1000, "0000dead", None),
+			entry("readme.txt", 10, "00000001", None),
+		];
+
+		let set = compare_set(Uuid::nil(), "Game".to_string(), game_files(), &entries);
+
+		assert_eq!(set.status, GameSetStatus::Partial);
+		assert_eq!(set.missing_files, vec!["Game (Track 2).bin"]);
+		assert_eq!(set.extra_files, vec!["readme.txt"]);
+	}
+
+	#[test]
+	fn compares_superset_sets() {
+		let entries = vec![
+			entry("game.cue", 100, "12345678", None),
+			entry("track01.bin", 1000, "0000dead", None),
+			entry("track02.bin", 2000, "0000beef", None),
+			entry("game.sbi", 10, "00000001", None),
+		];
+
+		let set = compare_set(Uuid::nil(), "Game".to_string(), game_files(), &entries);
+
+		assert_eq!(set.status, GameSetStatus::Superset);
+		assert!(set.missing_files.is_empty());
+		assert_eq!(set.extra_files, vec!["game.sbi"]);
+	}
+
+	#[test]
+	fn prefers_the_most_accurate_hash() {
+		let sha1 = "74591cc9501af93873f9a5d3eb12da12c0723bbc";
+		let file = game_file("Game.gb", 1000, "0000dead", Some(sha1));
+
+		// a matching CRC doesn't help if the SHA1 differs
+		let other = entry("game.gb", 1000, "0000dead", Some(&sha1.replace('7', "8")));
+		assert!(!is_same_file(&other.search, None, &file));
+
+		let same = entry("game.gb", 1, "00000000", Some(&sha1.to_uppercase()));
+		assert!(is_same_file(&same.search, None, &file));
+
+		// CRC32 needs the size to match as well
+		let crc = entry("game.gb", 1000, "DEAD", None);
+		assert!(is_same_file(&crc.search, None, &file));
+		let crc = entry("game.gb", 1001, "DEAD", None);
+		assert!(!is_same_file(&crc.search, None, &file));
+	}
+
+	#[test]
+	fn compares_the_size_without_header() {
+		let file = game_file("Game.nes", 0x8000, "0000dead", None);
+		let entry = entry("game.nes", 0x8010, "0000dead", None);
+		let header = DetectedHeader {
+			detector: "No-Intro_NES.xml".to_string(),
+			start_offset: 0x10,
+			end_offset: 0x8010,
+		};
+
+		assert!(!is_same_file(&entry.search, None, &file));
+		assert!(is_same_file(&entry.search, Some(&header), &file));
+	}
+}
@@ -0,0 +1,204 @@
+use crate::header::{detect_header, DetectedHeader, HEADER_PROBE_SIZE};
+use md5::{Digest, Md5};
+use sha1::Sha1;
+use sha2::Sha256;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+use tokio::sync::mpsc;
+use tokio::task::{self, JoinHandle};
+
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+const HASH_CHANNEL_SIZE: usize = 16;
+
+/// Calculates only the MD5 hash of a file, which is what DAT imports are identified by.
+pub async fn calculate_md5(path: &Path) -> anyhow::Result<String> {
+	let mut file = tokio::fs::File::open(path).await?;
+	let mut hasher = Md5::new();
+
+	let mut buffer = vec![0; HASH_BUFFER_SIZE];
+	loop {
+		let n = file.read(&mut buffer).await?;
+		if n == 0 {
+			break;
+		}
+		hasher.update(&buffer[..n]);
+	}
+
+	Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Hashes in the same (lowercase hex) format DATs use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHashes {
+	pub size: u64,
+	pub crc: String,
+	pub md5: String,
+	pub sha1: String,
+	pub sha256: String,
+}
+
+/// Calculates all hashes DATs can contain in a single pass.
+#[derive(Default)]
+pub struct MultiHasher {
+	size: u64,
+	crc: crc32fast::Hasher,
+	md5: Md5,
+	sha1: Sha1,
+	sha256: Sha256,
+}
+
+impl MultiHasher {
+	pub fn update(&mut self, data: &[u8]) {
+		self.size += data.len() as u64;
+		self.crc.update(data);
+		self.md5.update(data);
+		self.sha1.update(data);
+		self.sha256.update(data);
+	}
+
+	pub fn finalize(self) -> FileHashes {
+		FileHashes {
+			size: self.size,
+			crc: format!("{:08x}", self.crc.finalize()),
+			md5: format!("{:x}", self.md5.finalize()),
+			sha1: format!("{:x}", self.sha1.finalize()),
+			sha256: format!("{:x}", self.sha256.finalize()),
+		}
+	}
+
+	/// Hashes everything until the end of the reader.
+	pub fn update_from_reader(&mut self, reader: &mut impl Read) -> std::io::Result<()> {
+		let mut buffer = vec![0; HASH_BUFFER_SIZE];
+
+		loop {
+			let n = reader.read(&mut buffer)?;
+			if n == 0 {
+				return Ok(());
+			}
+			self.update(&buffer[..n]);
+		}
+	}
+}
+
+/// Hashes a file while it is received in chunks and keeps its start to detect a header once the
+/// size is known.
+#[derive(Default)]
+pub struct StreamingFileHasher {
+	hasher: MultiHasher,
+	probe: Vec<u8>,
+}
+
+impl StreamingFileHasher {
+	pub fn update(&mut self, data: &[u8]) {
+		let missing = HEADER_PROBE_SIZE.saturating_sub(self.probe.len());
+		self.probe
+			.extend_from_slice(&data[..missing.min(data.len())]);
+		self.hasher.update(data);
+	}
+
+	/// Returns the hashes of the whole file and the header detected in its start, if any.
+	pub fn finalize(self) -> (FileHashes, Option<DetectedHeader>) {
+		let hashes = self.hasher.finalize();
+		let header = detect_header(&self.probe, hashes.size);
+
+		(hashes, header)
+	}
+}
+
+/// Runs a [StreamingFileHasher] in a blocking task, so the chunks of an upload can be hashed
+/// while it is received without blocking the async worker thread.
+pub struct BackgroundFileHasher<T> {
+	sender: mpsc::Sender<T>,
+	task: JoinHandle<StreamingFileHasher>,
+}
+
+impl<T: AsRef<[u8]> + Send + 'static> BackgroundFileHasher<T> {
+	pub fn spawn() -> Self {
+		let (sender, mut receiver) = mpsc::channel::<T>(HASH_CHANNEL_SIZE);
+		let task = task::spawn_blocking(move || {
+			let mut hasher = StreamingFileHasher::default();
+			while let Some(chunk) = receiver.blocking_recv() {
+				hasher.update(chunk.as_ref());
+			}
+			hasher
+		});
+
+		Self { sender, task }
+	}
+
+	/// Waits if the hasher is behind by more than a few chunks.
+	pub async fn update(&self, chunk: T) -> anyhow::Result<()> {
+		self.sender
+			.send(chunk)
+			.await
+			.map_err(|_| anyhow::anyhow!("The file hasher stopped"))
+	}
+
+	/// Waits for all sent chunks to be hashed, see [StreamingFileHasher::finalize].
+	pub async fn finalize(self) -> anyhow::Result<(FileHashes, Option<DetectedHeader>)> {
+		drop(self.sender);
+
+		Ok(self.task.await?.finalize())
+	}
+}
+
+/// Hashes the data of a file after the given header.
+pub async fn hash_file_without_given_header(
+	path: &Path,
+	header: &DetectedHeader,
+) -> anyhow::Result<FileHashes> {
+	let path: PathBuf = path.to_owned();
+	let header = header.clone();
+
+	task::spawn_blocking(move || {
+		let mut file = BufReader::new(File::open(&path)?);
+
+		hash_data_after_header(&mut file, &header)
+	})
+	.await?
+}
+
+fn hash_data_after_header(
+	file: &mut BufReader<File>,
+	header: &DetectedHeader,
+) -> anyhow::Result<FileHashes> {
+	file.seek(SeekFrom::Start(header.start_offset))?;
+	let mut hasher = MultiHasher::default();
+	hasher.update_from_reader(&mut file.by_ref().take(header.data_size()))?;
+
+	Ok(hasher.finalize())
+}
+
+/// Hashes a file the way DATs of headered formats do: if a known header is detected, only the
+/// data after it is hashed. Returns the hashes of the whole file as well as the headerless ones.
+pub async fn hash_file_without_header(
+	path: &Path,
+) -> anyhow::Result<(FileHashes, Option<(DetectedHeader, FileHashes)>)> {
+	let path: PathBuf = path.to_owned();
+
+	task::spawn_blocking(move || {
+		let mut file = BufReader::new(File::open(&path)?);
+		let file_size = file.get_ref().metadata()?.len();
+
+		let mut probe = Vec::with_capacity(HEADER_PROBE_SIZE);
+		file.by_ref()
+			.take(HEADER_PROBE_SIZE as u64)
+			.read_to_end(&mut probe)?;
+
+		let mut hasher = MultiHasher::default();
+		hasher.update(&probe);
+		hasher.update_from_reader(&mut file)?;
+		let hashes = hasher.finalize();
+
+		let Some(header) = detect_header(&probe, file_size) else {
+			return Ok((hashes, None));
+		};
+
+		let headerless_hashes = hash_data_after_header(&mut file, &header)?;
+
+		Ok((hashes, Some((header, headerless_hashes))))
+	})
+	.await?
+}
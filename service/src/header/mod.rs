@@ -0,0 +1,631 @@
+use crate::dat::shared::xml::attributes;
+use anyhow::{anyhow, bail};
+use lazy_static::lazy_static;
+use log::{error, info, warn};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::{env, fs};
+
+const HEADER_DETECTORS_PATH_ENV: &str = "HEADER_DETECTORS_PATH";
+
+/// Detectors referenced by the No-Intro DATs of headered formats, more can be added with
+/// `HEADER_DETECTORS_PATH`.
+const BUILT_IN_HEADER_DETECTORS: [(&str, &str); 4] = [
+	(
+		"No-Intro_NES.xml",
+		include_str!("detectors/No-Intro_NES.xml"),
+	),
+	(
+		"No-Intro_FDS.xml",
+		include_str!("detectors/No-Intro_FDS.xml"),
+	),
+	(
+		"No-Intro_A7800.xml",
+		include_str!("detectors/No-Intro_A7800.xml"),
+	),
+	(
+		"No-Intro_LNX.xml",
+		include_str!("detectors/No-Intro_LNX.xml"),
+	),
+];
+
+/// The start of a file which is enough for all known detectors, callers don't need to pass more.
+pub const HEADER_PROBE_SIZE: usize = 1024;
+
+lazy_static! {
+	/// Keyed by the lowercase file name, which is how DATs reference their detector.
+	static ref HEADER_DETECTORS: BTreeMap<String, HeaderDetector> = load_header_detectors();
+}
+
+/// ClrMamePro header detector, describes how to recognize a header in front of the actual data of
+/// a file. DATs of headered formats contain the hashes of the data without the header.
+#[derive(Debug, Clone)]
+pub struct HeaderDetector {
+	/// File name of the detector, e.g. `No-Intro_NES.xml`.
+	pub file_name: String,
+
+	pub name: String,
+
+	rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+	start_offset: u64,
+
+	/// `None` means until the end of the file.
+	end_offset: Option<u64>,
+
+	/// Only rules which don't transform the data are supported.
+	supported: bool,
+
+	tests: Vec<Test>,
+}
+
+#[derive(Debug, Clone)]
+enum Test {
+	Data {
+		offset: i64,
+		value: Vec<u8>,
+		result: bool,
+	},
+	Bitwise {
+		operation: BitwiseOperation,
+		offset: i64,
+		mask: Vec<u8>,
+		value: Vec<u8>,
+		result: bool,
+	},
+	File {
+		size: FileSize,
+		operator: FileSizeOperator,
+		result: bool,
+	},
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BitwiseOperation {
+	And,
+	Or,
+	Xor,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FileSize {
+	PowerOfTwo,
+	Bytes(u64),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FileSizeOperator {
+	Equal,
+	Less,
+	Greater,
+}
+
+/// A header found at the start of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedHeader {
+	/// File name of the detector which found the header.
+	pub detector: String,
+
+	/// Where the data after the header starts, which is also the size of the header.
+	pub start_offset: u64,
+
+	/// Where the data ends, usually the end of the file.
+	pub end_offset: u64,
+}
+
+impl DetectedHeader {
+	pub fn data_size(&self) -> u64 {
+		self.end_offset - self.start_offset
+	}
+}
+
+impl HeaderDetector {
+	/// Checks the rules in order and returns the header of the first matching rule. `data` is the
+	/// start of the file, tests which need more of it than given fail.
+	pub fn detect(&self, data: &[u8], file_size: u64) -> Option<DetectedHeader> {
+		self.rules
+			.iter()
+			.filter(|rule| rule.supported)
+			.find(|rule| rule.matches(data, file_size))
+			.and_then(|rule| {
+				let end_offset = rule.end_offset.unwrap_or(file_size).min(file_size);
+
+				(rule.start_offset < end_offset).then(|| DetectedHeader {
+					detector: self.file_name.clone(),
+					start_offset: rule.start_offset,
+					end_offset,
+				})
+			})
+	}
+}
+
+impl Rule {
+	fn matches(&self, data: &[u8], file_size: u64) -> bool {
+		self.tests.iter().all(|test| test.matches(data, file_size))
+	}
+}
+
+impl Test {
+	fn matches(&self, data: &[u8], file_size: u64) -> bool {
+		match self {
+			Test::Data {
+				offset,
+				value,
+				result,
+			} => (read_at(data, file_size, *offset, value.len()) == Some(value)) == *result,
+			Test::Bitwise {
+				operation,
+				offset,
+				mask,
+				value,
+				result,
+			} => {
+				let Some(bytes) = read_at(data, file_size, *offset, value.len()) else {
+					return !*result;
+				};
+
+				let matches =
+					bytes
+						.iter()
+						.zip(mask.iter())
+						.zip(value.iter())
+						.all(|((byte, mask), value)| {
+							let masked = match operation {
+								BitwiseOperation::And => byte & mask,
+								BitwiseOperation::Or => byte | mask,
+								BitwiseOperation::Xor => byte ^ mask,
+							};
+							masked == *value
+						});
+
+				matches == *result
+			}
+			Test::File {
+				size,
+				operator,
+				result,
+			} => {
+				let matches = match (size, operator) {
+					(FileSize::PowerOfTwo, FileSizeOperator::Equal) => file_size.is_power_of_two(),
+					(FileSize::PowerOfTwo, _) => false,
+					(FileSize::Bytes(size), FileSizeOperator::Equal) => file_size == *size,
+					(FileSize::Bytes(size), FileSizeOperator::Less) => file_size < *size,
+					(FileSize::Bytes(size), FileSizeOperator::Greater) => file_size > *size,
+				};
+
+				matches == *result
+			}
+		}
+	}
+}
+
+/// Negative offsets are relative to the end of the file.
+fn read_at(data: &[u8], file_size: u64, offset: i64, len: usize) -> Option<&[u8]> {
+	let start = if offset < 0 {
+		file_size.checked_sub(offset.unsigned_abs())?
+	} else {
+		offset as u64
+	};
+	let start = usize::try_from(start).ok()?;
+
+	data.get(start..start.checked_add(len)?)
+}
+
+/// Returns the detector a DAT references, e.g. `No-Intro_NES.xml`.
+pub fn find_header_detector(file_name: &str) -> Option<&'static HeaderDetector> {
+	HEADER_DETECTORS.get(&file_name.to_lowercase())
+}
+
+/// Runs all known detectors on the start of a file and returns the first header found.
+pub fn detect_header(data: &[u8], file_size: u64) -> Option<DetectedHeader> {
+	HEADER_DETECTORS
+		.values()
+		.find_map(|detector| detector.detect(data, file_size))
+}
+
+/// Decodes a hex string, e.g. the start of a file sent to the API.
+pub fn parse_hex(value: &str) -> anyhow::Result<Vec<u8>> {
+	let value = value.trim();
+
+	if !value.len().is_multiple_of(2) {
+		bail!("Hex value has an odd length");
+	}
+
+	(0..value.len())
+		.step_by(2)
+		.map(|i| {
+			value
+				.get(i..i + 2)
+				.and_then(|byte| u8::from_str_radix(byte, 16).ok())
+				.ok_or_else(|| anyhow!("Invalid hex value"))
+		})
+		.collect()
+}
+
+fn load_header_detectors() -> BTreeMap<String, HeaderDetector> {
+	let mut detectors = BTreeMap::new();
+
+	for (file_name, content) in BUILT_IN_HEADER_DETECTORS {
+		match parse_header_detector(file_name, content) {
+			Ok(detector) => {
+				detectors.insert(file_name.to_lowercase(), detector);
+			}
+			Err(e) => error!("Invalid built-in header detector {}: {:?}", file_name, e),
+		}
+	}
+
+	if let Ok(path) = env::var(HEADER_DETECTORS_PATH_ENV) {
+		if let Err(e) = load_header_detectors_from_dir(Path::new(&path), &mut detectors) {
+			error!("Failed to load header detectors from {}: {:?}", path, e);
+		}
+	}
+
+	detectors
+}
+
+/// Detectors in the directory take precedence over the built-in ones with the same file name.
+fn load_header_detectors_from_dir(
+	path: &Path,
+	detectors: &mut BTreeMap<String, HeaderDetector>,
+) -> anyhow::Result<()> {
+	for entry in fs::read_dir(path)? {
+		let path = entry?.path();
+
+		if path.extension().and_then(|e| e.to_str()) != Some("xml") {
+			continue;
+		}
+
+		let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+			continue;
+		};
+
+		match parse_header_detector(file_name, &fs::read_to_string(&path)?) {
+			Ok(detector) => {
+				info!("Loaded header detector: {}", file_name);
+				detectors.insert(file_name.to_lowercase(), detector);
+			}
+			Err(e) => warn!("Skipping invalid header detector {}: {:?}", file_name, e),
+		}
+	}
+
+	Ok(())
+}
+
+fn parse_header_detector(file_name: &str, content: &str) -> anyhow::Result<HeaderDetector> {
+	let mut reader = Reader::from_str(content);
+	let mut detector = HeaderDetector {
+		file_name: file_name.to_string(),
+		name: file_name.to_string(),
+		rules: Vec::new(),
+	};
+	let mut in_name = false;
+
+	loop {
+		match reader.read_event()? {
+			Event::Start(element) | Event::Empty(element) if element.name().as_ref() == b"rule" => {
+				let attributes = attributes(&element)?;
+				let operation = attributes.get("operation").map(String::as_str);
+
+				if !matches!(operation, None | Some("none")) {
+					warn!(
+						"Header detector {} uses the unsupported operation {:?}, the rule is ignored",
+						file_name, operation
+					);
+				}
+
+				detector.rules.push(Rule {
+					start_offset: attributes
+						.get("start_offset")
+						.map(|offset| parse_offset(offset).map(|offset| offset.max(0) as u64))
+						.transpose()?
+						.unwrap_or_default(),
+					end_offset: match attributes.get("end_offset").map(String::as_str) {
+						None | Some("EOF") => None,
+						Some(offset) => Some(parse_offset(offset)?.max(0) as u64),
+					},
+					supported: matches!(operation, None | Some("none")),
+					tests: Vec::new(),
+				});
+			}
+			Event::Start(element) | Event::Empty(element)
+				if matches!(
+					element.name().as_ref(),
+					b"data" | b"and" | b"or" | b"xor" | b"file"
+				) =>
+			{
+				let Some(rule) = detector.rules.last_mut() else {
+					bail!("Test outside of a rule");
+				};
+
+				let name = element.name().as_ref().to_vec();
+				let attributes = attributes(&element)?;
+				let attribute = |key: &str| {
+					attributes
+						.get(key)
+						.ok_or_else(|| anyhow!("Test is missing the {} attribute", key))
+				};
+				let result = attributes.get("result").map(String::as_str) != Some("false");
+
+				let test = match name.as_slice() {
+					b"data" => Test::Data {
+						offset: parse_offset(attribute("offset")?)?,
+						value: parse_hex(attribute("value")?)?,
+						result,
+					},
+					b"file" => Test::File {
+						size: match attribute("size")?.as_str() {
+							"PO2" => FileSize::PowerOfTwo,
+							size => FileSize::Bytes(u64::from_str_radix(size, 16)?),
+						},
+						operator: match attributes.get("operator").map(String::as_str) {
+							None | Some("equal") => FileSizeOperator::Equal,
+							Some("less") => FileSizeOperator::Less,
+							Some("greater") => FileSizeOperator::Greater,
+							Some(operator) => bail!("Unknown file size operator: {}", operator),
+						},
+						result,
+					},
+					operation => Test::Bitwise {
+						operation: match operation {
+							b"and" => BitwiseOperation::And,
+							b"or" => BitwiseOperation::Or,
+							_ => BitwiseOperation::Xor,
+						},
+						offset: parse_offset(attribute("offset")?)?,
+						mask: parse_hex(attribute("mask")?)?,
+						value: parse_hex(attribute("value")?)?,
+						result,
+					},
+				};
+
+				rule.tests.push(test);
+			}
+			Event::Start(element) if element.name().as_ref() == b"name" => in_name = true,
+			Event::Text(text) if in_name => {
+				detector.name = text.unescape()?.trim().to_string();
+				in_name = false;
+			}
+			Event::End(_) => in_name = false,
+			Event::Eof => break,
+			_ => {}
+		}
+	}
+
+	if detector.rules.is_empty() {
+		bail!("Header detector has no rules");
+	}
+
+	Ok(detector)
+}
+
+/// Offsets are hex encoded and can be negative.
+fn parse_offset(value: &str) -> anyhow::Result<i64> {
+	match value.strip_prefix('-') {
+		Some(value) => Ok(-i64::from_str_radix(value, 16)?),
+		None => Ok(i64::from_str_radix(value, 16)?),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn detector(rules: &str) -> HeaderDetector {
+		parse_header_detector(
+			"test.xml",
+			&format!(
+				r#"<?xml version="1.0"?>
+<detector>
+	<name>Test</name>
+	{}
+</detector>"#,
+				rules
+			),
+		)
+		.unwrap()
+	}
+
+	/// A file of the given size starting with the bytes.
+	fn file(start: &[u8], size: usize) -> Vec<u8> {
+		let mut data = start.to_vec();
+		data.resize(size, 0);
+		data
+	}
+
+	fn detect(detector: &HeaderDetector, data: &[u8]) -> Option<DetectedHeader> {
+		detector.detect(data, data.len() as u64)
+	}
+
+	#[test]
+	fn parses_the_name_and_rules() {
+		let detector = detector(
+			r#"<rule start_offset="10" end_offset="EOF" operation="none">
+		<data offset="0" value="4E45531A"/>
+		<file size="PO2" result="false"/>
+	</rule>
+	<rule start_offset="80" operation="bitswap">
+		<data offset="0" value="00"/>
+	</rule>"#,
+		);
+
+		assert_eq!(detector.name, "Test");
+		assert_eq!(detector.rules.len(), 2);
+		assert_eq!(detector.rules[0].start_offset, 0x10);
+		assert_eq!(detector.rules[0].end_offset, None);
+		assert_eq!(detector.rules[0].tests.len(), 2);
+		assert!(detector.rules[0].supported);
+		assert!(!detector.rules[1].supported);
+	}
+
+	#[test]
+	fn rejects_invalid_detectors() {
+		assert!(
+			parse_header_detector("test.xml", "<detector><name>Test</name></detector>").is_err()
+		);
+		assert!(parse_header_detector(
+			"test.xml",
+			r#"<detector><rule start_offset="10"><data offset="0"/></rule></detector>"#
+		)
+		.is_err());
+		assert!(parse_header_detector(
+			"test.xml",
+			r#"<detector><rule start_offset="10"><data offset="0" value="4E4"/></rule></detector>"#
+		)
+		.is_err());
+	}
+
+	#[test]
+	fn detects_data_tests_with_negative_offsets_and_results() {
+		let detector = detector(
+			r#"<rule start_offset="10">
+		<data offset="-2" value="AA55"/>
+		<data offset="0" value="FF" result="false"/>
+	</rule>"#,
+		);
+
+		let mut data = file(b"\x00", 0x20);
+		data[0x1E..].copy_from_slice(&[0xAA, 0x55]);
+
+		assert_eq!(
+			detect(&detector, &data),
+			Some(DetectedHeader {
+				detector: "test.xml".to_string(),
+				start_offset: 0x10,
+				end_offset: 0x20,
+			})
+		);
+
+		data[0] = 0xFF;
+		assert_eq!(detect(&detector, &data), None);
+	}
+
+	#[test]
+	fn detects_bitwise_tests() {
+		let and =
+			detector(r#"<rule start_offset="10"><and offset="0" mask="F0" value="40"/></rule>"#);
+		let or =
+			detector(r#"<rule start_offset="10"><or offset="0" mask="0F" value="4F"/></rule>"#);
+		let xor =
+			detector(r#"<rule start_offset="10"><xor offset="0" mask="FF" value="B1"/></rule>"#);
+
+		let data = file(b"\x4E", 0x20);
+		assert!(detect(&and, &data).is_some());
+		assert!(detect(&or, &data).is_some());
+		assert!(detect(&xor, &data).is_some());
+
+		let data = file(b"\x5E", 0x20);
+		assert!(detect(&and, &data).is_none());
+		assert!(detect(&or, &data).is_none());
+		assert!(detect(&xor, &data).is_none());
+	}
+
+	#[test]
+	fn detects_file_size_tests() {
+		let power_of_two =
+			detector(r#"<rule start_offset="10"><file size="PO2" result="false"/></rule>"#);
+		let less = detector(r#"<rule start_offset="10"><file size="100" operator="less"/></rule>"#);
+		let greater =
+			detector(r#"<rule start_offset="10"><file size="100" operator="greater"/></rule>"#);
+
+		assert!(detect(&power_of_two, &file(b"", 0x110)).is_some());
+		assert!(detect(&power_of_two, &file(b"", 0x100)).is_none());
+		assert!(detect(&less, &file(b"", 0xFF)).is_some());
+		assert!(detect(&less, &file(b"", 0x100)).is_none());
+		assert!(detect(&greater, &file(b"", 0x101)).is_some());
+		assert!(detect(&greater, &file(b"", 0x100)).is_none());
+	}
+
+	#[test]
+	fn fails_tests_beyond_the_given_data() {
+		let detector = detector(r#"<rule start_offset="10"><data offset="40" value="01"/></rule>"#);
+		let data = file(b"", 0x20);
+
+		// the file is larger than the probe, but the test can't be checked
+		assert_eq!(detector.detect(&data, 0x100), None);
+	}
+
+	#[test]
+	fn ignores_unsupported_rules() {
+		let detector = detector(
+			r#"<rule start_offset="10" operation="byteswap"><data offset="0" value="4E"/></rule>"#,
+		);
+
+		assert_eq!(detect(&detector, &file(b"\x4E", 0x20)), None);
+	}
+
+	#[test]
+	fn parses_all_built_in_detectors() {
+		for (file_name, content) in BUILT_IN_HEADER_DETECTORS {
+			assert!(
+				parse_header_detector(file_name, content).is_ok(),
+				"{} is invalid",
+				file_name
+			);
+			assert!(find_header_detector(&file_name.to_uppercase()).is_some());
+		}
+	}
+
+	#[test]
+	fn detects_ines_headers() {
+		let header = detect_header(&file(b"NES\x1A\x02\x01", 0x8010), 0x8010).unwrap();
+
+		assert_eq!(header.detector, "No-Intro_NES.xml");
+		assert_eq!(header.start_offset, 0x10);
+		assert_eq!(header.data_size(), 0x8000);
+		assert_eq!(detect_header(&file(b"NES\x00", 0x8000), 0x8000), None);
+	}
+
+	#[test]
+	fn detects_fds_headers() {
+		let header = detect_header(&file(b"FDS\x1A\x02", 0x20010), 0x20010).unwrap();
+
+		assert_eq!(header.detector, "No-Intro_FDS.xml");
+		assert_eq!(header.start_offset, 0x10);
+		assert_eq!(detect_header(&file(b"FDS\x1A\x05", 0x20010), 0x20010), None);
+	}
+
+	#[test]
+	fn detects_a7800_headers() {
+		let mut data = file(b"\x01ATARI7800", 0x8080);
+		let header = detect_header(&data, 0x8080).unwrap();
+
+		assert_eq!(header.detector, "No-Intro_A7800.xml");
+		assert_eq!(header.start_offset, 0x80);
+
+		data[1..10].fill(0);
+		data[0x64..0x80].copy_from_slice(b"ACTUAL CART DATA STARTS HERE");
+		assert_eq!(
+			detect_header(&data, 0x8080).unwrap().detector,
+			"No-Intro_A7800.xml"
+		);
+	}
+
+	#[test]
+	fn detects_lnx_headers() {
+		let header = detect_header(&file(b"LYNX", 0x20040), 0x20040).unwrap();
+		assert_eq!(header.detector, "No-Intro_LNX.xml");
+		assert_eq!(header.start_offset, 0x40);
+
+		let mut data = file(b"", 0x20040);
+		data[6..11].copy_from_slice(b"BATCH");
+		assert_eq!(
+			detect_header(&data, 0x20040).unwrap().detector,
+			"No-Intro_LNX.xml"
+		);
+	}
+
+	#[test]
+	fn parses_hex() {
+		assert_eq!(
+			parse_hex(" 4e45531A ").unwrap(),
+			vec![0x4E, 0x45, 0x53, 0x1A]
+		);
+		assert!(parse_hex("4E4").is_err());
+		assert!(parse_hex("ZZ").is_err());
+	}
+}
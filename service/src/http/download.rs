@@ -2,12 +2,18 @@ use std::path::{Path, PathBuf};
 
 use crate::http::abstraction::RequestClientExt;
 use crate::util::random_sized_string;
+use anyhow::{anyhow, bail};
 use futures_util::stream::StreamExt;
 use lazy_static::lazy_static;
-use log::debug;
+use log::{debug, warn};
 use regex::Regex;
-use reqwest::Client;
-use tokio::fs::File;
+use reqwest::header::{
+	HeaderMap, CONTENT_DISPOSITION, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE,
+	IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED, RANGE,
+};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use tokio::fs::{self, File, OpenOptions};
 use tokio::io::AsyncWriteExt;
 
 lazy_static! {
@@ -15,6 +21,12 @@ lazy_static! {
 	static ref FILENAME_REGEX: Regex = Regex::new(r#"filename\*?=(?:UTF-8''|")?([^";]+)"#).unwrap();
 }
 
+/// How often an interrupted download is resumed before giving up.
+const MAX_RESUME_ATTEMPTS: usize = 3;
+
+/// Error pages are usually served as HTML, which is never a valid download for us.
+const REJECTED_CONTENT_TYPES: [&str; 2] = ["text/html", "application/xhtml+xml"];
+
 #[derive(Debug)]
 pub enum DownloadFileNameResult {
 	FromContentDisposition(PathBuf),
@@ -22,44 +34,337 @@ pub enum DownloadFileNameResult {
 	Random(PathBuf),
 }
 
+#[derive(Debug)]
+pub enum DownloadResult {
+	/// The file did not change since the response the given validators belong to.
+	NotModified,
+	Downloaded {
+		file: DownloadFileNameResult,
+		validators: CacheValidators,
+	},
+}
+
+/// Validators of a response, used to make the next request conditional and to resume downloads.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheValidators {
+	pub etag: Option<String>,
+	pub last_modified: Option<String>,
+}
+
+impl CacheValidators {
+	fn from_headers(headers: &HeaderMap) -> Self {
+		let header = |name| {
+			headers
+				.get(name)
+				.and_then(|value| value.to_str().ok())
+				.map(ToString::to_string)
+		};
+
+		CacheValidators {
+			etag: header(ETAG),
+			last_modified: header(LAST_MODIFIED),
+		}
+	}
+
+	/// Weak ETags can not be used for range requests, so the modification date is used instead.
+	fn if_range(&self) -> Option<&str> {
+		match &self.etag {
+			Some(etag) if !etag.starts_with("W/") => Some(etag),
+			_ => self.last_modified.as_deref(),
+		}
+	}
+}
+
+/// Stored next to a partial download, so it can be resumed with a range request later on.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PartialDownload {
+	url: String,
+	validators: CacheValidators,
+}
+
+enum DownloadAttempt {
+	Done(DownloadResult),
+	Interrupted(anyhow::Error),
+}
+
+/// Downloads a file into the given directory. The body is written to `partial_path` first, which
+/// has to be on the same file system, and is resumed from there if the transfer got interrupted,
+/// even across restarts. If `cached` is given, the request is conditional and nothing is
+/// downloaded if the file did not change.
 pub async fn download_file(
 	client: &Client,
 	url: &str,
 	path: &Path,
-) -> anyhow::Result<DownloadFileNameResult> {
-	let response = client.get_default_user_agent(url).send().await?;
-	let content_disposition = response.headers().get("content-disposition");
-
-	let mut file_name = None;
-
-	if let Some(content_disposition) = content_disposition {
-		if let Ok(content_disposition) = content_disposition.to_str() {
-			if let Some(filename) = extract_filename(content_disposition) {
-				debug!(
-					"Filename extracted from Content-Disposition header: {:?}",
-					&filename
+	partial_path: &Path,
+	cached: Option<&CacheValidators>,
+) -> anyhow::Result<DownloadResult> {
+	let mut attempt = 0;
+
+	loop {
+		match try_download_file(client, url, path, partial_path, cached).await? {
+			DownloadAttempt::Done(result) => return Ok(result),
+			DownloadAttempt::Interrupted(e) if attempt < MAX_RESUME_ATTEMPTS => {
+				attempt += 1;
+				warn!(
+					"Download of {} got interrupted, resuming (attempt {}/{}): {}",
+					url, attempt, MAX_RESUME_ATTEMPTS, e
 				);
-				file_name = Some(filename);
 			}
+			DownloadAttempt::Interrupted(e) => {
+				return Err(e.context(format!("Failed to download {}", url)))
+			}
+		}
+	}
+}
+
+async fn try_download_file(
+	client: &Client,
+	url: &str,
+	path: &Path,
+	partial_path: &Path,
+	cached: Option<&CacheValidators>,
+) -> anyhow::Result<DownloadAttempt> {
+	let partial_meta_path = partial_meta_path(partial_path);
+	let resume_from = find_resumable_download(url, partial_path, &partial_meta_path).await;
+
+	let mut request = client.get_default_user_agent(url);
+
+	if let Some(cached) = cached {
+		if let Some(etag) = &cached.etag {
+			request = request.header(IF_NONE_MATCH, etag);
 		}
+		if let Some(last_modified) = &cached.last_modified {
+			request = request.header(IF_MODIFIED_SINCE, last_modified);
+		}
+	}
+
+	if let Some((offset, if_range)) = &resume_from {
+		debug!("Resuming download of {} at byte {}", url, offset);
+		request = request
+			.header(RANGE, format!("bytes={}-", offset))
+			.header(IF_RANGE, if_range);
 	}
 
-	let file_name_final = match &file_name {
-		None => &random_sized_string(16),
-		Some(file_name) => file_name,
+	let response = match request.send().await {
+		Ok(response) => response,
+		Err(e) => return Ok(DownloadAttempt::Interrupted(e.into())),
 	};
+	let status = response.status();
+
+	if status == StatusCode::NOT_MODIFIED {
+		debug!("{} has not been modified", url);
+		remove_partial_download(partial_path, &partial_meta_path).await?;
+		return Ok(DownloadAttempt::Done(DownloadResult::NotModified));
+	}
+
+	if status == StatusCode::RANGE_NOT_SATISFIABLE {
+		remove_partial_download(partial_path, &partial_meta_path).await?;
+		return Ok(DownloadAttempt::Interrupted(anyhow!(
+			"Partial download does not match the remote file anymore"
+		)));
+	}
+
+	if !status.is_success() {
+		bail!("Failed to download {}: HTTP {}", url, status);
+	}
+
+	validate_content_type(response.headers())?;
+
+	let validators = CacheValidators::from_headers(response.headers());
+
+	let (mut file, mut written, expected_size) = match (status, &resume_from) {
+		(StatusCode::PARTIAL_CONTENT, Some((offset, _))) => {
+			let (start, total) = parse_content_range(response.headers())
+				.ok_or_else(|| anyhow!("Invalid Content-Range for {}", url))?;
+
+			if start != *offset {
+				bail!(
+					"Server resumed {} at byte {} instead of {}",
+					url,
+					start,
+					offset
+				);
+			}
+
+			let file = OpenOptions::new().append(true).open(partial_path).await?;
+			(file, *offset, total)
+		}
+		(StatusCode::PARTIAL_CONTENT, None) => {
+			bail!(
+				"Server sent partial content for {} without a range request",
+				url
+			)
+		}
+		_ => (
+			File::create(partial_path).await?,
+			0,
+			response.content_length(),
+		),
+	};
+
+	let partial_meta = PartialDownload {
+		url: url.to_string(),
+		validators: validators.clone(),
+	};
+	fs::write(&partial_meta_path, serde_json::to_vec(&partial_meta)?).await?;
+
+	let file_name = file_name_from_response(response.headers(), url);
 
-	let file_path = path.join(file_name_final);
-	let mut file = File::create(&file_path).await?;
 	let mut stream = response.bytes_stream();
-	while let Some(v) = stream.next().await {
-		file.write_buf(&mut v?).await?;
+	while let Some(chunk) = stream.next().await {
+		match chunk {
+			Ok(mut chunk) => {
+				written += chunk.len() as u64;
+				file.write_buf(&mut chunk).await?;
+			}
+			Err(e) => {
+				file.flush().await?;
+				return Ok(DownloadAttempt::Interrupted(e.into()));
+			}
+		}
+	}
+	file.flush().await?;
+
+	if let Some(expected_size) = expected_size {
+		if written != expected_size {
+			return Ok(DownloadAttempt::Interrupted(anyhow!(
+				"Received {} of {} bytes",
+				written,
+				expected_size
+			)));
+		}
+	}
+
+	let file = match file_name {
+		DownloadFileNameResult::FromContentDisposition(name) => {
+			DownloadFileNameResult::FromContentDisposition(path.join(name))
+		}
+		DownloadFileNameResult::FromUrl(name) => DownloadFileNameResult::FromUrl(path.join(name)),
+		DownloadFileNameResult::Random(name) => DownloadFileNameResult::Random(path.join(name)),
+	};
+
+	let file_path = match &file {
+		DownloadFileNameResult::FromContentDisposition(path)
+		| DownloadFileNameResult::FromUrl(path)
+		| DownloadFileNameResult::Random(path) => path,
+	};
+
+	fs::rename(partial_path, file_path).await?;
+	fs::remove_file(&partial_meta_path).await?;
+
+	Ok(DownloadAttempt::Done(DownloadResult::Downloaded {
+		file,
+		validators,
+	}))
+}
+
+fn partial_meta_path(partial_path: &Path) -> PathBuf {
+	let mut file_name = partial_path.file_name().unwrap_or_default().to_owned();
+	file_name.push(".json");
+	partial_path.with_file_name(file_name)
+}
+
+/// Returns the offset and `If-Range` value, if a partial download of the same URL exists which can
+/// be resumed.
+async fn find_resumable_download(
+	url: &str,
+	partial_path: &Path,
+	partial_meta_path: &Path,
+) -> Option<(u64, String)> {
+	let meta = fs::read(partial_meta_path).await.ok()?;
+	let meta: PartialDownload = serde_json::from_slice(&meta).ok()?;
+
+	if meta.url != url {
+		return None;
 	}
 
-	match file_name {
-		Some(_) => Ok(DownloadFileNameResult::FromContentDisposition(file_path)),
-		None => Ok(DownloadFileNameResult::Random(file_path)),
+	let offset = fs::metadata(partial_path).await.ok()?.len();
+	if offset == 0 {
+		return None;
 	}
+
+	meta.validators
+		.if_range()
+		.map(|if_range| (offset, if_range.to_string()))
+}
+
+async fn remove_partial_download(
+	partial_path: &Path,
+	partial_meta_path: &Path,
+) -> anyhow::Result<()> {
+	for path in [partial_path, partial_meta_path] {
+		if fs::try_exists(path).await.unwrap_or(false) {
+			fs::remove_file(path).await?;
+		}
+	}
+
+	Ok(())
+}
+
+fn validate_content_type(headers: &HeaderMap) -> anyhow::Result<()> {
+	let content_type = headers
+		.get(CONTENT_TYPE)
+		.and_then(|value| value.to_str().ok())
+		.unwrap_or_default();
+	let mime_type = content_type
+		.split(';')
+		.next()
+		.unwrap_or_default()
+		.trim()
+		.to_lowercase();
+
+	if REJECTED_CONTENT_TYPES.contains(&mime_type.as_str()) {
+		bail!("Unexpected content type: {}", content_type);
+	}
+
+	Ok(())
+}
+
+/// Parses `Content-Range: bytes {start}-{end}/{total}`, the total is optional.
+fn parse_content_range(headers: &HeaderMap) -> Option<(u64, Option<u64>)> {
+	let content_range = headers.get(CONTENT_RANGE)?.to_str().ok()?;
+	let (range, total) = content_range.strip_prefix("bytes ")?.split_once('/')?;
+	let (start, _) = range.split_once('-')?;
+
+	Some((start.parse().ok()?, total.parse().ok()))
+}
+
+/// Only the last path component is used, so a malicious name can not escape the download folder.
+fn file_name_from_response(headers: &HeaderMap, url: &str) -> DownloadFileNameResult {
+	let content_disposition = headers
+		.get(CONTENT_DISPOSITION)
+		.and_then(|value| value.to_str().ok())
+		.and_then(extract_filename)
+		.and_then(|name| safe_file_name(&name));
+
+	if let Some(file_name) = content_disposition {
+		debug!(
+			"Filename extracted from Content-Disposition header: {:?}",
+			&file_name
+		);
+		return DownloadFileNameResult::FromContentDisposition(file_name);
+	}
+
+	// only names with an extension are useful, otherwise archives and DATs can not be detected
+	let from_url = reqwest::Url::parse(url)
+		.ok()
+		.and_then(|url| url.path_segments()?.next_back().map(ToString::to_string))
+		.and_then(|name| safe_file_name(&name))
+		.filter(|name| name.extension().is_some());
+
+	match from_url {
+		Some(file_name) => DownloadFileNameResult::FromUrl(file_name),
+		None => DownloadFileNameResult::Random(PathBuf::from(random_sized_string(16))),
+	}
+}
+
+fn safe_file_name(name: &str) -> Option<PathBuf> {
+	Path::new(name.trim())
+		.file_name()
+		.filter(|name| !name.to_string_lossy().starts_with('.'))
+		.map(PathBuf::from)
 }
 
 // Function to extract the filename from Content-Disposition header value
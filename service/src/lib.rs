@@ -1,14 +1,17 @@
+pub mod archive;
 pub mod cache;
 pub mod company;
 pub mod constants;
 pub mod dat;
+pub mod dat_file;
 pub mod db;
 mod fs;
 pub mod game;
+pub mod hash;
+pub mod header;
 pub mod http;
 pub mod r#match;
 pub mod metadata;
 pub mod model;
 pub mod platform;
 mod util;
-mod zip;
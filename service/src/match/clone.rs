@@ -2,7 +2,7 @@ use crate::constants::PARALLELISM;
 use crate::db::dat_file::find_all_dat_files;
 use crate::db::game::{
 	find_game_by_signature_group_internal_id_and_dat_file_id, get_dat_file_id_of_game,
-	get_unpopulated_clone_of_games,
+	get_unpopulated_clone_of_and_rom_of_games,
 };
 use crate::r#match::PAGE_SIZE;
 use entity::game;
@@ -12,6 +12,7 @@ use sea_orm::ActiveValue::Set;
 use sea_orm::{ActiveModelTrait, DbConn, IntoActiveModel};
 use tokio::task::JoinHandle;
 
+/// Resolves the clone_of and rom_of relations of the games of all DATs.
 pub async fn populate_all_clone_of_ids(conn: &DbConn) -> anyhow::Result<()> {
 	let dat_files = find_all_dat_files(conn).await?;
 
@@ -23,7 +24,7 @@ pub async fn populate_all_clone_of_ids(conn: &DbConn) -> anyhow::Result<()> {
 }
 
 pub async fn populate_clone_of_id(dat_file_id: Uuid, conn: &DbConn) -> anyhow::Result<()> {
-	let mut paginator = get_unpopulated_clone_of_games(dat_file_id, PAGE_SIZE, conn);
+	let mut paginator = get_unpopulated_clone_of_and_rom_of_games(dat_file_id, PAGE_SIZE, conn);
 
 	while let Some(games_to_match) = paginator.fetch_and_next().await? {
 		for games_chunk in games_to_match.chunks(*PARALLELISM) {
@@ -40,30 +41,61 @@ pub async fn populate_clone_of_id(dat_file_id: Uuid, conn: &DbConn) -> anyhow::R
 		}
 	}
 
-	debug!("Created all clone_of relationships for games with internal_clone_of_id for dat_file_id: {}", dat_file_id);
+	debug!("Created all clone_of and rom_of relationships for games with internal_clone_of_id or internal_rom_of_id for dat_file_id: {}", dat_file_id);
 
 	Ok(())
 }
 
+/// Resolves the parent of a clone and the game whose ROMs it shares, e.g. the BIOS of a MAME
+/// machine, to the games of the same DAT.
 async fn try_match_parent(game: game::Model, conn: DbConn) -> anyhow::Result<()> {
-	if let Some(signature_group_internal_clone_of_id) = &game.signature_group_internal_clone_of_id {
-		let dat_file_id = get_dat_file_id_of_game(&game, &conn).await?;
-
-		let game_parent = find_game_by_signature_group_internal_id_and_dat_file_id(
-			signature_group_internal_clone_of_id.clone(),
-			dat_file_id,
-			&conn,
-		)
-		.await?;
+	if game.signature_group_internal_clone_of_id.is_none()
+		&& game.signature_group_internal_rom_of_id.is_none()
+	{
+		return Ok(());
+	}
 
-		if let Some(game_parent) = game_parent {
-			let mut game_active_model = game.into_active_model();
+	let dat_file_id = get_dat_file_id_of_game(&game, &conn).await?;
 
-			game_active_model.clone_of = Set(Some(game_parent.id));
+	let game_parent = match &game.signature_group_internal_clone_of_id {
+		Some(signature_group_internal_clone_of_id) => {
+			find_game_by_signature_group_internal_id_and_dat_file_id(
+				signature_group_internal_clone_of_id.clone(),
+				dat_file_id,
+				&conn,
+			)
+			.await?
+		}
+		None => None,
+	};
 
-			game_active_model.save(&conn).await?;
+	let game_rom_of = match &game.signature_group_internal_rom_of_id {
+		Some(signature_group_internal_rom_of_id) => {
+			find_game_by_signature_group_internal_id_and_dat_file_id(
+				signature_group_internal_rom_of_id.clone(),
+				dat_file_id,
+				&conn,
+			)
+			.await?
 		}
+		None => None,
+	};
+
+	if game_parent.is_none() && game_rom_of.is_none() {
+		return Ok(());
+	}
+
+	let mut game_active_model = game.into_active_model();
+
+	if let Some(game_parent) = game_parent {
+		game_active_model.clone_of = Set(Some(game_parent.id));
 	}
 
+	if let Some(game_rom_of) = game_rom_of {
+		game_active_model.rom_of = Set(Some(game_rom_of.id));
+	}
+
+	game_active_model.save(&conn).await?;
+
 	Ok(())
 }
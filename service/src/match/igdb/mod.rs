@@ -18,7 +18,9 @@ lazy_static! {
 	static ref BRACKET_REGEX: Regex = Regex::new(r"\s*\(.*?\)").unwrap();
 }
 
-fn clean_name(input: &str) -> String {
+/// Removes tags like (USA) or (Rev 1) from a game name. The clean_game_name SQL function used for
+/// searching game names follows the same rules.
+pub(crate) fn clean_name(input: &str) -> String {
 	BRACKET_REGEX.replace_all(input, "").to_string()
 }
 
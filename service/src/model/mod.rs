@@ -1,14 +1,16 @@
+use chrono::{DateTime, FixedOffset};
 use derive_builder::Builder;
 use entity::sea_orm_active_enums::{
-	AutomaticMatchReasonEnum, FailedMatchReasonEnum, ManualMatchModeEnum, MatchTypeEnum,
-	MetadataProviderEnum,
+	AutomaticMatchReasonEnum, DatFileImportChangeTypeEnum, FailedMatchReasonEnum, GameKindEnum,
+	ManualMatchModeEnum, MatchTypeEnum, MetadataProviderEnum,
 };
 use sea_orm::prelude::Uuid;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use strum::EnumIter;
 use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GameFileMatchSearch {
 	/// The file name of the game file.
@@ -25,6 +27,33 @@ pub struct GameFileMatchSearch {
 
 	/// Optional SHA256 hash of the game file.
 	pub sha256: Option<String>,
+
+	/// Optional CRC32 of the game file, only matched together with the size.
+	pub crc: Option<String>,
+
+	/// Also match games which were removed from newer DAT versions, defaults to false.
+	pub include_retired: Option<bool>,
+
+	/// Optional hex encoded start of the game file (the first 1024 bytes are enough), used to
+	/// detect headers like iNES. DATs of headered formats only contain the hashes and size of the
+	/// data without the header, so the size is matched without the header if one is detected.
+	/// Hashes can be of the whole file or of the data without the header.
+	pub header: Option<String>,
+
+	/// Optional serial of the disc or cartridge, e.g. SLUS-00594. Hyphens, spaces and other
+	/// separators are ignored, multiple serials can be separated by commas.
+	pub serial: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct GameSerialSearch {
+	/// The serial of the disc or cartridge, e.g. SLUS-00594. Hyphens, spaces and other
+	/// separators are ignored, multiple serials can be separated by commas.
+	pub serial: String,
+
+	/// Also return games which were removed from newer DAT versions, defaults to false.
+	pub include_retired: Option<bool>,
 }
 
 /// Type of match for this game.
@@ -39,6 +68,12 @@ pub enum GameMatchType {
 	/// Matched by MD5 hash.
 	MD5,
 
+	/// Matched by CRC32 and size, check `ambiguous` as CRC32 collisions are common.
+	CRC32AndSize,
+
+	/// Matched by the serial of the disc or cartridge.
+	Serial,
+
 	/// Matched by file name and size.
 	FileNameAndSize,
 
@@ -53,12 +88,621 @@ pub struct GameMatchResult {
 	/// The type of match that was found.
 	pub game_match_type: GameMatchType,
 
-	/// If a match was found, the ID of the matched game.
+	/// If a match was found, the ID of the matched game, which is the best of the candidates.
 	pub id: Option<Uuid>,
 
+	/// If a match was found, what kind of entry was matched (game, BIOS, ...).
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub kind: Option<GameKind>,
+
+	/// If the matched game was removed from a newer DAT version, when that happened.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub retired_at: Option<DateTime<FixedOffset>>,
+
 	/// External metadata for the matched game.
 	#[serde(skip_serializing_if = "Vec::is_empty")]
 	pub external_metadata: Vec<ExternalMetadata>,
+
+	/// Whether a header was detected in the given start of the file.
+	pub header_detected: bool,
+
+	/// If a header was detected, the file name of the ClrMamePro header detector which found it.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub header_detector: Option<String>,
+
+	/// Whether multiple games match, e.g. the same file is part of several DATs or games share a
+	/// CRC32 and size. The matched game might not be the one the client is looking for then.
+	pub ambiguous: bool,
+
+	/// All games matching with the match type, the best match first. Active games come before
+	/// retired ones and parents before clones, so the same search always picks the same game.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub candidates: Vec<GameMatchCandidateResponse>,
+
+	/// Details of the matched game, only with expand=game.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[builder(default)]
+	pub game: Option<GameDetailsResponse>,
+
+	/// The platform of the matched game, only with expand=platform.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[builder(default)]
+	pub platform: Option<PlatformResponse>,
+
+	/// The DAT of the matched game, only with expand=dat.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[builder(default)]
+	pub dat: Option<GameMatchDatResponse>,
+
+	/// The file of the matched game the search matched, only with expand=files.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[builder(default)]
+	pub matched_file: Option<GameFileResponse>,
+
+	/// All files of the matched game, only with expand=files.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	#[builder(default)]
+	pub files: Vec<GameFileResponse>,
+}
+
+/// Details of a game as written in its DAT.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameDetailsResponse {
+	/// The name of the game.
+	pub name: String,
+
+	/// The description of the game.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+
+	/// The categories of the game, e.g. Games or Demos.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub categories: Vec<String>,
+
+	/// If the game is a clone, the ID of its parent.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub clone_of_id: Option<Uuid>,
+
+	/// If the game is a clone, the name of its parent.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub clone_of_name: Option<String>,
+}
+
+/// The DAT a game comes from.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameMatchDatResponse {
+	/// The ID of the DAT file.
+	pub id: Uuid,
+
+	/// The name of the DAT file.
+	pub name: String,
+
+	/// The latest imported version of the DAT.
+	pub version: String,
+
+	/// The ID of the signature group of the DAT.
+	pub signature_group_id: Uuid,
+
+	/// The name of the signature group of the DAT, e.g. No-Intro.
+	pub signature_group_name: String,
+}
+
+/// Details which can be added to a game match.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GameMatchExpand {
+	pub game: bool,
+	pub platform: bool,
+	pub dat: bool,
+	pub files: bool,
+}
+
+impl GameMatchExpand {
+	pub fn is_empty(&self) -> bool {
+		*self == Self::default()
+	}
+}
+
+impl FromStr for GameMatchExpand {
+	type Err = anyhow::Error;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		let mut expand = Self::default();
+
+		for part in value
+			.split(',')
+			.map(str::trim)
+			.filter(|part| !part.is_empty())
+		{
+			match part {
+				"game" => expand.game = true,
+				"platform" => expand.platform = true,
+				"dat" => expand.dat = true,
+				"files" => expand.files = true,
+				_ => anyhow::bail!(
+					"Unknown expand value {}, expected game, platform, dat or files",
+					part
+				),
+			}
+		}
+
+		Ok(expand)
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct GameMatchExpandQuery {
+	/// Comma separated details to add to matches: game, platform, dat and files.
+	pub expand: Option<String>,
+}
+
+/// A game matching a search and where it comes from.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameMatchCandidateResponse {
+	/// The ID of the game.
+	pub id: Uuid,
+
+	/// The name of the game.
+	pub name: String,
+
+	/// What kind of entry the game is (game, BIOS, ...).
+	pub kind: GameKind,
+
+	/// If the game was removed from a newer DAT version, when that happened.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub retired_at: Option<DateTime<FixedOffset>>,
+
+	/// The ID of the DAT file the game is part of.
+	pub dat_file_id: Uuid,
+
+	/// The name of the DAT file the game is part of.
+	pub dat_file_name: String,
+
+	/// The ID of the signature group (No-Intro, Redump, ...) of the DAT file.
+	pub signature_group_id: Uuid,
+
+	/// The name of the signature group of the DAT file.
+	pub signature_group_name: String,
+
+	/// The ID of the platform of the DAT file.
+	pub platform_id: Uuid,
+
+	/// The name of the platform of the DAT file.
+	pub platform_name: String,
+
+	/// External metadata for the game.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub external_metadata: Vec<ExternalMetadata>,
+}
+
+/// Hashes calculated by the server, in the same lowercase hex format DATs use.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHashesResponse {
+	/// The size of the hashed data in bytes.
+	pub size: u64,
+
+	/// The CRC32 of the data.
+	pub crc: String,
+
+	/// The MD5 hash of the data.
+	pub md5: String,
+
+	/// The SHA1 hash of the data.
+	pub sha1: String,
+
+	/// The SHA256 hash of the data.
+	pub sha256: String,
+}
+
+/// Result of identifying a file hashed by the server.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FileIdentifyResponse {
+	/// The file name the file was matched with.
+	pub file_name: String,
+
+	/// The hashes of the whole file.
+	pub hashes: FileHashesResponse,
+
+	/// If a header was detected, the hashes of the data without the header, which are the ones
+	/// DATs of headered formats contain.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub headerless_hashes: Option<FileHashesResponse>,
+
+	/// The match found with the hashes, the headerless ones are tried first.
+	pub game_match: GameMatchResult,
+}
+
+/// An entry of an archive as listed in its central directory.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntrySearch {
+	/// The path of the entry in the archive.
+	pub name: String,
+
+	/// The uncompressed size of the entry in bytes.
+	pub size: i64,
+
+	/// The CRC32 of the entry.
+	pub crc: String,
+}
+
+/// The entries of an archive to identify.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntriesSearch {
+	/// All entries of the archive, directories excluded.
+	pub entries: Vec<ArchiveEntrySearch>,
+
+	/// Also match games which were removed from newer DAT versions, defaults to false.
+	pub include_retired: Option<bool>,
+}
+
+/// Result of identifying an archive.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveIdentifyResponse {
+	/// The match of every entry, in the order of the entries.
+	pub entries: Vec<ArchiveEntryMatch>,
+
+	/// The games the entries belong to, complete sets first.
+	pub sets: Vec<GameSetMatch>,
+}
+
+/// Result of identifying a single entry of an archive.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveEntryMatch {
+	/// The path of the entry in the archive.
+	pub name: String,
+
+	/// If the archive was uploaded, the hashes of the whole entry.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub hashes: Option<FileHashesResponse>,
+
+	/// If the archive was uploaded and a header was detected, the hashes of the data without the
+	/// header, which are the ones the entry is matched with.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub headerless_hashes: Option<FileHashesResponse>,
+
+	/// The match found for the entry.
+	pub game_match: GameMatchResult,
+}
+
+/// How the files of a game compare to a set of files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+pub enum GameSetStatus {
+	/// All files of the game are there and nothing else.
+	Complete,
+
+	/// All files of the game are there, as well as files which don't belong to it.
+	Superset,
+
+	/// Files of the game are missing.
+	Partial,
+}
+
+/// A game some of the files of a set belong to.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameSetMatch {
+	/// The ID of the game.
+	pub id: Uuid,
+
+	/// The name of the game.
+	pub name: String,
+
+	/// How the files of the game compare to the set.
+	pub status: GameSetStatus,
+
+	/// The names of the files of the game which are in the set.
+	pub matched_files: Vec<String>,
+
+	/// The names of the files of the game which are not in the set.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub missing_files: Vec<String>,
+
+	/// The names of the files of the set which don't belong to the game.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub extra_files: Vec<String>,
+}
+
+/// The files of a set to identify, e.g. the tracks of a disc or the files in a folder.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameSetSearch {
+	/// All files of the set, includeRetired of the files is ignored.
+	pub files: Vec<GameFileMatchSearch>,
+
+	/// Also match games which were removed from newer DAT versions, defaults to false.
+	pub include_retired: Option<bool>,
+}
+
+/// Result of identifying a set of files.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameSetIdentifyResponse {
+	/// The match of every file, in the order of the files.
+	pub files: Vec<GameMatchResult>,
+
+	/// The games the files belong to, complete sets first.
+	pub sets: Vec<GameSetMatch>,
+
+	/// The titles with multiple discs any of the games belong to, with all of their discs.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub disc_groups: Vec<GameDiscGroup>,
+}
+
+/// All discs of a title in a DAT and of its clones, e.g. the three discs of Final Fantasy VII (USA)
+/// and the discs of the releases of other regions.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameDiscGroup {
+	/// The name of the discs of the parent without the disc tag.
+	pub title: String,
+
+	/// Whether all files of all discs of the parent or of one of its clones are in the set.
+	pub complete: bool,
+
+	/// The discs of the parent and of its clones in the order of their numbers.
+	pub discs: Vec<GameDisc>,
+}
+
+/// A single disc of a title with multiple discs.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameDisc {
+	/// The ID of the game of the disc.
+	pub id: Uuid,
+
+	/// The name of the game of the disc.
+	pub name: String,
+
+	/// The number from the disc tag.
+	pub disc: u32,
+
+	/// If the disc is a clone, the ID of the disc of the parent, e.g. of the release of another
+	/// region.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub clone_of: Option<Uuid>,
+
+	/// How the files of the disc compare to the set, if any of them are in it. Discs in a set with
+	/// the other discs of the title are a superset.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub status: Option<GameSetStatus>,
+}
+
+/// A game carrying a serial and its files with that serial.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameSerialResponse {
+	/// The ID of the game.
+	pub id: Uuid,
+
+	/// The name of the game.
+	pub name: String,
+
+	/// What kind of entry the game is (game, BIOS, ...).
+	pub kind: GameKind,
+
+	/// If the game was removed from a newer DAT version, when that happened.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub retired_at: Option<DateTime<FixedOffset>>,
+
+	/// External metadata for the game.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub external_metadata: Vec<ExternalMetadata>,
+
+	/// The files of the game carrying the serial.
+	pub files: Vec<GameFileResponse>,
+}
+
+/// A file of a game as listed in its DAT.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameFileResponse {
+	/// The ID of the file.
+	pub id: Uuid,
+
+	/// The name of the file.
+	pub file_name: String,
+
+	/// The size of the file in bytes.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub file_size_in_bytes: Option<i64>,
+
+	/// The CRC32 of the file.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub crc: Option<String>,
+
+	/// The MD5 hash of the file.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub md5: Option<String>,
+
+	/// The SHA1 hash of the file.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub sha1: Option<String>,
+
+	/// The SHA256 hash of the file.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub sha256: Option<String>,
+
+	/// The serial as written in the DAT.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub serial: Option<String>,
+
+	/// The status of the dump as written in the DAT, e.g. verified or baddump.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub status: Option<String>,
+}
+
+/// Kind of entry in a DAT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum GameKind {
+	/// A regular game.
+	Game,
+
+	/// A BIOS needed by emulators to run games of a platform.
+	Bios,
+
+	/// Firmware of a system or peripheral.
+	Firmware,
+
+	/// A non-game application, e.g. a utility or media player.
+	Application,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct GameListQuery {
+	/// The page to return, starting at 1, defaults to 1.
+	pub page: Option<u64>,
+
+	/// The number of games per page, defaults to 50 and can be at most 200.
+	pub page_size: Option<u64>,
+
+	/// Only games of DATs of this platform.
+	pub platform_id: Option<Uuid>,
+
+	/// Only games of this DAT.
+	pub dat_file_id: Option<Uuid>,
+
+	/// Only games of DATs of this signature group (No-Intro, Redump, ...).
+	pub signature_group_id: Option<Uuid>,
+
+	/// Only games with this category, e.g. Games or Demos.
+	pub category: Option<String>,
+
+	/// Only games with this match status (Automatic, Failed, Manual or None). Games which were
+	/// never matched count as None.
+	#[param(value_type = Option<MatchType>)]
+	pub match_status: Option<MatchType>,
+
+	/// Only games whose name contains this text, ignoring case.
+	pub name: Option<String>,
+
+	/// Also return games which were removed from newer DAT versions, defaults to false.
+	pub include_retired: Option<bool>,
+}
+
+/// A page of the games playmatch knows about, ordered by name.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameListResponse {
+	/// The games of the page.
+	pub games: Vec<GameMatchCandidateResponse>,
+
+	/// The page, starting at 1.
+	pub page: u64,
+
+	/// The number of games per page.
+	pub page_size: u64,
+
+	/// The number of games matching the filters on all pages.
+	pub total_games: u64,
+
+	/// The number of pages.
+	pub total_pages: u64,
+}
+
+/// A game with its files, where it comes from and its relations to other games.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameResponse {
+	/// The ID of the game.
+	pub id: Uuid,
+
+	/// The name of the game.
+	pub name: String,
+
+	/// The description of the game from the DAT.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+
+	/// What kind of entry the game is (game, BIOS, ...).
+	pub kind: GameKind,
+
+	/// The categories of the game from the DAT, e.g. Games or Demos.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub categories: Vec<String>,
+
+	/// If the game was removed from a newer DAT version, when that happened.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub retired_at: Option<DateTime<FixedOffset>>,
+
+	/// The DAT the game is part of.
+	pub dat: GameMatchDatResponse,
+
+	/// The ID of the platform of the DAT file.
+	pub platform_id: Uuid,
+
+	/// The name of the platform of the DAT file.
+	pub platform_name: String,
+
+	/// The game this game is a clone of.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub parent: Option<GameReferenceResponse>,
+
+	/// The game whose ROMs this game uses as well, e.g. the BIOS of a MAME machine.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub rom_of: Option<GameReferenceResponse>,
+
+	/// The clones of this game, ordered by name.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub clones: Vec<GameReferenceResponse>,
+
+	/// The files of the game, ordered by name.
+	pub files: Vec<GameFileResponse>,
+
+	/// External metadata for the game.
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	pub external_metadata: Vec<ExternalMetadata>,
+}
+
+/// Another game a game is related to.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameReferenceResponse {
+	/// The ID of the game.
+	pub id: Uuid,
+
+	/// The name of the game.
+	pub name: String,
+
+	/// If the game was removed from a newer DAT version, when that happened.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub retired_at: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct GameNameSearch {
+	/// The name to search for, tags like (USA) or (Rev 1) are ignored.
+	pub name: String,
+
+	/// Only games of DATs of this platform.
+	pub platform_id: Option<Uuid>,
+
+	/// The maximum number of games to return, defaults to 20 and can be at most 100.
+	pub limit: Option<u64>,
+
+	/// Also return games which were removed from newer DAT versions, defaults to false.
+	pub include_retired: Option<bool>,
+}
+
+/// A game whose name is similar to a searched name.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GameNameSearchResult {
+	/// How similar the name of the game without its tags is to the searched name, from 0 to 1.
+	pub similarity: f32,
+
+	/// The game and where it comes from.
+	pub game: GameMatchCandidateResponse,
 }
 
 /// Response for a company including external metadata.
@@ -99,6 +743,155 @@ pub struct PlatformResponse {
 	pub external_metadata: Vec<ExternalMetadata>,
 }
 
+/// Response for an import of a DAT file version.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DatFileImportResponse {
+	/// The ID of the import.
+	pub id: Uuid,
+
+	/// The ID of the DAT file this import belongs to.
+	pub dat_file_id: Uuid,
+
+	/// The original file name of the imported DAT.
+	pub name: String,
+
+	/// The version of the imported DAT.
+	pub version: String,
+
+	/// The MD5 hash of the imported DAT.
+	pub md5_hash: String,
+
+	/// When the DAT was imported.
+	pub imported_at: DateTime<FixedOffset>,
+}
+
+/// Result of importing a single DAT file.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DatFileImportResultResponse {
+	/// The created import.
+	pub import: DatFileImportResponse,
+
+	/// Amount of games in the DAT.
+	pub game_count: usize,
+
+	/// Amount of files of all games in the DAT.
+	pub file_count: usize,
+}
+
+/// Response of a DAT upload, an archive can contain multiple DATs.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DatUploadResponse {
+	/// The DATs which were imported.
+	pub imports: Vec<DatFileImportResultResponse>,
+
+	/// Files which were skipped, because they are no DATs or were already imported before.
+	pub skipped: Vec<String>,
+
+	/// DATs which failed to import.
+	pub failed: Vec<DatUploadFailure>,
+}
+
+/// A DAT of an upload which failed to import.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DatUploadFailure {
+	/// The name of the file in the upload.
+	pub file_name: String,
+
+	/// Why the import failed.
+	pub error: String,
+}
+
+/// A DAT import which failed and was rolled back, it is retried on the next run.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DatFileImportFailureResponse {
+	/// The ID of the failure.
+	pub id: Uuid,
+
+	/// The ID of the signature group the DAT was imported into.
+	pub signature_group_id: Uuid,
+
+	/// The file name of the DAT.
+	pub file_name: String,
+
+	/// The MD5 hash of the DAT.
+	pub md5_hash: String,
+
+	/// Why the import failed.
+	pub error: String,
+
+	/// When the import failed.
+	pub failed_at: DateTime<FixedOffset>,
+}
+
+/// A single change of a DAT import compared to the previous version.
+#[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DatFileImportChangeResponse {
+	/// The ID of the change.
+	pub id: Uuid,
+
+	/// What changed.
+	pub change_type: DatFileImportChangeType,
+
+	/// The ID of the game which changed, if it still exists.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub game_id: Option<Uuid>,
+
+	/// The name of the game which changed.
+	pub game_name: String,
+
+	/// The previous name of a renamed game.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub previous_game_name: Option<String>,
+
+	/// The name of the file which changed.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub file_name: Option<String>,
+
+	/// The CRC of the file before the change.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub previous_crc: Option<String>,
+
+	/// The CRC of the file after the change.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub crc: Option<String>,
+
+	/// The SHA1 hash of the file before the change.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub previous_sha1: Option<String>,
+
+	/// The SHA1 hash of the file after the change.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub sha1: Option<String>,
+}
+
+/// Type of change between two DAT versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum DatFileImportChangeType {
+	/// The game was added (or added again after being removed).
+	GameAdded,
+
+	/// The game is no longer part of the DAT.
+	GameRemoved,
+
+	/// The game was renamed, its files stayed the same.
+	GameRenamed,
+
+	/// A file was added to the game.
+	FileAdded,
+
+	/// A file was removed from the game.
+	FileRemoved,
+
+	/// The hashes of a file changed.
+	FileChanged,
+}
+
 /// External metadata for a game/platform/company.
 #[derive(Debug, Serialize, Deserialize, Clone, Builder, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -192,6 +985,18 @@ pub enum AutomaticMatchReason {
 	ViaParent,
 }
 
+impl From<crate::hash::FileHashes> for FileHashesResponse {
+	fn from(hashes: crate::hash::FileHashes) -> Self {
+		Self {
+			size: hashes.size,
+			crc: hashes.crc,
+			md5: hashes.md5,
+			sha1: hashes.sha1,
+			sha256: hashes.sha256,
+		}
+	}
+}
+
 impl From<entity::signature_metadata_mapping::Model> for ExternalMetadata {
 	fn from(value: entity::signature_metadata_mapping::Model) -> Self {
 		ExternalMetadata {
@@ -214,6 +1019,111 @@ impl From<MetadataProviderEnum> for MetadataProvider {
 	}
 }
 
+impl From<entity::dat_file_import_failure::Model> for DatFileImportFailureResponse {
+	fn from(value: entity::dat_file_import_failure::Model) -> Self {
+		DatFileImportFailureResponse {
+			id: value.id,
+			signature_group_id: value.signature_group_id,
+			file_name: value.file_name,
+			md5_hash: value.md5_hash,
+			error: value.error,
+			failed_at: value.failed_at,
+		}
+	}
+}
+
+impl From<entity::dat_file_import::Model> for DatFileImportResponse {
+	fn from(value: entity::dat_file_import::Model) -> Self {
+		DatFileImportResponse {
+			id: value.id,
+			dat_file_id: value.dat_file_id,
+			name: value.name,
+			version: value.version,
+			md5_hash: value.md5_hash,
+			imported_at: value.imported_at,
+		}
+	}
+}
+
+impl From<entity::dat_file_import_change::Model> for DatFileImportChangeResponse {
+	fn from(value: entity::dat_file_import_change::Model) -> Self {
+		DatFileImportChangeResponse {
+			id: value.id,
+			change_type: value.change_type.into(),
+			game_id: value.game_id,
+			game_name: value.game_name,
+			previous_game_name: value.previous_game_name,
+			file_name: value.file_name,
+			previous_crc: value.previous_crc,
+			crc: value.crc,
+			previous_sha1: value.previous_sha1,
+			sha1: value.sha1,
+		}
+	}
+}
+
+impl From<crate::db::game::GameMatchCandidate> for GameMatchCandidateResponse {
+	fn from(value: crate::db::game::GameMatchCandidate) -> Self {
+		GameMatchCandidateResponse {
+			id: value.game.id,
+			name: value.game.name,
+			kind: value.game.kind.into(),
+			retired_at: value.game.retired_at,
+			dat_file_id: value.dat_file.id,
+			dat_file_name: value.dat_file.name,
+			signature_group_id: value.signature_group.id,
+			signature_group_name: value.signature_group.name,
+			platform_id: value.platform.id,
+			platform_name: value.platform.name,
+			external_metadata: value
+				.signature_metadata_mappings
+				.into_iter()
+				.map(Into::into)
+				.collect(),
+		}
+	}
+}
+
+impl From<entity::game_file::Model> for GameFileResponse {
+	fn from(value: entity::game_file::Model) -> Self {
+		GameFileResponse {
+			id: value.id,
+			file_name: value.file_name,
+			file_size_in_bytes: value.file_size_in_bytes,
+			crc: value.crc,
+			md5: value.md5,
+			sha1: value.sha1,
+			sha256: value.sha256,
+			serial: value.serial,
+			status: value.status,
+		}
+	}
+}
+
+impl From<DatFileImportChangeTypeEnum> for DatFileImportChangeType {
+	fn from(change_type: DatFileImportChangeTypeEnum) -> Self {
+		match change_type {
+			DatFileImportChangeTypeEnum::GameAdded => DatFileImportChangeType::GameAdded,
+			DatFileImportChangeTypeEnum::GameRemoved => DatFileImportChangeType::GameRemoved,
+			DatFileImportChangeTypeEnum::GameRenamed => DatFileImportChangeType::GameRenamed,
+			DatFileImportChangeTypeEnum::FileAdded => DatFileImportChangeType::FileAdded,
+			DatFileImportChangeTypeEnum::FileRemoved => DatFileImportChangeType::FileRemoved,
+			DatFileImportChangeTypeEnum::FileChanged => DatFileImportChangeType::FileChanged,
+		}
+	}
+}
+
+impl From<GameKindEnum> for GameKind {
+	fn from(game_kind: GameKindEnum) -> Self {
+		match game_kind {
+			GameKindEnum::Game => GameKind::Game,
+			GameKindEnum::Bios => GameKind::Bios,
+			GameKindEnum::Firmware => GameKind::Firmware,
+			GameKindEnum::Application => GameKind::Application,
+		}
+	}
+}
+
 impl From<MatchTypeEnum> for MatchType {
 	fn from(match_type: MatchTypeEnum) -> Self {
 		match match_type {
@@ -225,6 +1135,27 @@ impl From<MatchTypeEnum> for MatchType {
 	}
 }
 
+impl From<MatchType> for MatchTypeEnum {
+	fn from(match_type: MatchType) -> Self {
+		match match_type {
+			MatchType::Automatic => MatchTypeEnum::Automatic,
+			MatchType::Failed => MatchTypeEnum::Failed,
+			MatchType::Manual => MatchTypeEnum::Manual,
+			MatchType::None => MatchTypeEnum::None,
+		}
+	}
+}
+
+impl From<entity::game::Model> for GameReferenceResponse {
+	fn from(value: entity::game::Model) -> Self {
+		GameReferenceResponse {
+			id: value.id,
+			name: value.name,
+			retired_at: value.retired_at,
+		}
+	}
+}
+
 impl From<ManualMatchModeEnum> for ManualMatchMode {
 	fn from(manual_match_mode: ManualMatchModeEnum) -> Self {
 		match manual_match_mode {
@@ -254,3 +1185,42 @@ impl From<AutomaticMatchReasonEnum> for AutomaticMatchReason {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_expand_values() {
+		assert_eq!(
+			"game, platform,dat,files"
+				.parse::<GameMatchExpand>()
+				.unwrap(),
+			GameMatchExpand {
+				game: true,
+				platform: true,
+				dat: true,
+				files: true,
+			}
+		);
+		assert_eq!(
+			"files".parse::<GameMatchExpand>().unwrap(),
+			GameMatchExpand {
+				files: true,
+				..Default::default()
+			}
+		);
+	}
+
+	#[test]
+	fn parses_empty_expand_values() {
+		assert!("".parse::<GameMatchExpand>().unwrap().is_empty());
+		assert!(" , ,".parse::<GameMatchExpand>().unwrap().is_empty());
+	}
+
+	#[test]
+	fn rejects_unknown_expand_values() {
+		assert!("game,metadata".parse::<GameMatchExpand>().is_err());
+		assert!("Game".parse::<GameMatchExpand>().is_err());
+	}
+}